@@ -6,18 +6,31 @@ fn main() {
     let target_arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
     let profile = std::env::var("PROFILE").unwrap_or_default();
     
-    let (lib_dir, lib_name) = match (target_os.as_str(), target_arch.as_str()) {
-        ("windows", "x86_64") => ("libs/pdfium/windows-x64", "pdfium.dll"),
-        ("linux", "x86_64") => ("libs/pdfium/linux-x64", "libpdfium.so"),
-        ("macos", "x86_64") => ("libs/pdfium/macos-x64", "libpdfium.dylib"),
-        ("macos", "aarch64") => ("libs/pdfium/macos-arm64", "libpdfium.dylib"),
-        _ => ("libs/pdfium/windows-x64", "pdfium.dll"),
+    let bundled = match (target_os.as_str(), target_arch.as_str()) {
+        ("windows", "x86_64") => Some(("libs/pdfium/windows-x64", "pdfium.dll")),
+        ("linux", "x86_64") => Some(("libs/pdfium/linux-x64", "libpdfium.so")),
+        ("macos", "x86_64") => Some(("libs/pdfium/macos-x64", "libpdfium.dylib")),
+        ("macos", "aarch64") => Some(("libs/pdfium/macos-arm64", "libpdfium.dylib")),
+        _ => None,
     };
+
+    if let Some((lib_dir, lib_name)) = bundled {
+        copy_pdfium(lib_dir, lib_name, &profile);
+    } else {
+        println!(
+            "cargo:warning=No bundled Pdfium for {}-{}; the app will use a system Pdfium or the built-in renderer",
+            target_os, target_arch
+        );
+    }
     
+    tauri_build::build()
+}
+
+fn copy_pdfium(lib_dir: &str, lib_name: &str, profile: &str) {
     let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
     let src_path = Path::new(&manifest_dir).join(lib_dir).join(lib_name);
     
-    let target_dir = Path::new(&manifest_dir).join("target").join(&profile);
+    let target_dir = Path::new(&manifest_dir).join("target").join(profile);
     let dest_path = target_dir.join(lib_name);
     
     if src_path.exists() && !dest_path.exists() {
@@ -30,6 +43,4 @@ fn main() {
             println!("cargo:warning=Copied {} to {}", src_path.display(), dest_path.display());
         }
     }
-    
-    tauri_build::build()
 }
@@ -0,0 +1,262 @@
+use crate::pdf_backend::PdfBackend;
+use crate::AppState;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Decodes a base64 PNG or JPEG, with or without its data URL prefix.
+fn decode_image_data(image_data: &str) -> Result<Vec<u8>, String> {
+    let base64_data = image_data
+        .strip_prefix("data:image/png;base64,")
+        .or_else(|| image_data.strip_prefix("data:image/jpeg;base64,"))
+        .unwrap_or(image_data);
+    
+    STANDARD.decode(base64_data)
+        .map_err(|e| format!("Failed to decode image: {}", e))
+}
+
+pub fn write_canvas(path: &Path, image_data: &str) -> Result<(), String> {
+    let decoded = decode_image_data(image_data)?;
+    
+    std::fs::write(path, decoded)
+        .map_err(|e| format!("Failed to write image: {}", e))?;
+    
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportPdfPage {
+    pub image_data: String,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExportPdfOptions {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub keywords: Option<Vec<String>>,
+    #[serde(default)]
+    pub source_pdf_path: Option<String>,
+    #[serde(default = "default_inherit_metadata")]
+    pub inherit_metadata: bool,
+}
+
+fn default_inherit_metadata() -> bool {
+    true
+}
+
+impl Default for ExportPdfOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            author: None,
+            subject: None,
+            keywords: None,
+            source_pdf_path: None,
+            inherit_metadata: default_inherit_metadata(),
+        }
+    }
+}
+
+fn split_keywords(keywords: &str) -> Vec<String> {
+    keywords
+        .split([',', ';'])
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty())
+        .collect()
+}
+
+impl ExportPdfOptions {
+    /// Fills every field the caller left unset from the source PDF's info
+    /// dictionary, when inheriting is enabled and the source can be read.
+    ///
+    /// Also returns the source's XMP packet, unless the caller changed any of
+    /// the fields: readers prefer XMP over the info dictionary, so a stale
+    /// packet would hide the new values.
+    fn resolve(mut self, backend: &dyn PdfBackend) -> (Self, Option<String>) {
+        if !self.inherit_metadata {
+            return (self, None);
+        }
+        let Some(source) = self.source_pdf_path.as_ref().map(PathBuf::from) else {
+            return (self, None);
+        };
+        let Ok(source_meta) = backend.open(&source).and_then(|document| document.metadata()) else {
+            return (self, None);
+        };
+
+        let source_keywords = source_meta.keywords.as_deref().map(split_keywords);
+        let unchanged = [
+            (&self.title, &source_meta.title),
+            (&self.author, &source_meta.author),
+            (&self.subject, &source_meta.subject),
+        ]
+        .iter()
+        .all(|(value, source)| value.is_none() || value == source)
+            && (self.keywords.is_none() || self.keywords == source_keywords);
+
+        self.title = self.title.or(source_meta.title);
+        self.author = self.author.or(source_meta.author);
+        self.subject = self.subject.or(source_meta.subject);
+        self.keywords = self.keywords.or(source_keywords);
+        let xmp = source_meta.xmp.filter(|_| unchanged);
+        (self, xmp)
+    }
+}
+
+/// Points the catalog's /Metadata at an uncompressed XMP stream.
+fn embed_xmp(pdf_bytes: Vec<u8>, xmp: &str) -> Result<Vec<u8>, String> {
+    use printpdf::lopdf::{dictionary, Document, Object, Stream};
+
+    let mut document = Document::load_mem(&pdf_bytes)
+        .map_err(|e| format!("Failed to reload PDF: {}", e))?;
+    let metadata_id = document.add_object(Stream::new(
+        dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+        xmp.as_bytes().to_vec(),
+    ));
+    let catalog_id = document
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .map_err(|e| format!("Failed to find PDF catalog: {}", e))?;
+    document
+        .get_object_mut(catalog_id)
+        .and_then(Object::as_dict_mut)
+        .map_err(|e| format!("Failed to find PDF catalog: {}", e))?
+        .set("Metadata", Object::Reference(metadata_id));
+
+    let mut out = Vec::new();
+    document
+        .save_to(&mut out)
+        .map_err(|e| format!("Failed to save PDF: {}", e))?;
+    Ok(out)
+}
+
+/// Writes one PDF page per entry, each showing its image stretched over a page
+/// of `width` x `height` CSS pixels.
+pub fn write_pdf(path: &Path, pages: &[ExportPdfPage], options: ExportPdfOptions, backend: &dyn PdfBackend) -> Result<(), String> {
+    use printpdf::{PdfDocument, Mm, Px, Image, ImageXObject, ColorSpace, ColorBits, ImageTransform};
+    use ::image::ImageReader;
+    
+    if pages.is_empty() {
+        return Err("No pages to export".to_string());
+    }
+    
+    let (options, xmp) = options.resolve(backend);
+    
+    let first_page = &pages[0];
+    let page_width_mm = Mm(first_page.width * 0.264583);
+    let page_height_mm = Mm(first_page.height * 0.264583);
+    
+    let title = options.title.clone().unwrap_or_else(|| "Annotate Studio Export".to_string());
+    
+    let (doc, page1, layer1) = PdfDocument::new(
+        title,
+        page_width_mm,
+        page_height_mm,
+        "Annotations"
+    );
+    
+    let mut doc = doc
+        .with_creator("Annotate Studio")
+        .with_producer(format!("Annotate Studio {}", env!("CARGO_PKG_VERSION")));
+    if let Some(author) = options.author {
+        doc = doc.with_author(author);
+    }
+    if let Some(subject) = options.subject {
+        doc = doc.with_subject(subject);
+    }
+    if let Some(keywords) = options.keywords {
+        doc = doc.with_keywords(keywords);
+    }
+    
+    for (i, page_data) in pages.iter().enumerate() {
+        let pw_mm = page_data.width * 0.264583;
+        let ph_mm = page_data.height * 0.264583;
+        
+        let (current_page, current_layer) = if i == 0 {
+            (page1, layer1)
+        } else {
+            let (page, layer) = doc.add_page(
+                Mm(pw_mm),
+                Mm(ph_mm),
+                "Annotations"
+            );
+            (page, layer)
+        };
+        
+        let decoded = decode_image_data(&page_data.image_data)?;
+        
+        let img = ImageReader::new(std::io::Cursor::new(&decoded))
+            .with_guessed_format()
+            .map_err(|e| format!("Failed to guess image format: {}", e))?
+            .decode()
+            .map_err(|e| format!("Failed to decode image: {}", e))?;
+        
+        let img_rgb = img.to_rgb8();
+        let (img_width, img_height) = (img_rgb.width(), img_rgb.height());
+        
+        let image = Image::from(ImageXObject {
+            width: Px(img_width as usize),
+            height: Px(img_height as usize),
+            color_space: ColorSpace::Rgb,
+            bits_per_component: ColorBits::Bit8,
+            interpolate: true,
+            image_data: img_rgb.into_raw(),
+            image_filter: None,
+            clipping_bbox: None,
+            smask: None,
+        });
+        
+        let dpi = 72.0;
+        let img_width_mm = (img_width as f32 / dpi) * 25.4;
+        let img_height_mm = (img_height as f32 / dpi) * 25.4;
+        
+        let scale_x = pw_mm / img_width_mm;
+        let scale_y = ph_mm / img_height_mm;
+        
+        let layer = doc.get_page(current_page).get_layer(current_layer);
+        image.add_to_layer(
+            layer,
+            ImageTransform {
+                translate_x: Some(Mm(0.0)),
+                translate_y: Some(Mm(0.0)),
+                scale_x: Some(scale_x),
+                scale_y: Some(scale_y),
+                ..Default::default()
+            }
+        );
+    }
+    
+    let mut pdf_bytes = doc.save_to_bytes()
+        .map_err(|e| format!("Failed to save PDF: {}", e))?;
+    if let Some(xmp) = xmp {
+        pdf_bytes = embed_xmp(pdf_bytes, &xmp)?;
+    }
+    
+    std::fs::write(path, pdf_bytes)
+        .map_err(|e| format!("Failed to write PDF file: {}", e))?;
+    
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn export_canvas(path: String, image_data: String) -> Result<(), String> {
+    write_canvas(Path::new(&path), &image_data)
+}
+
+#[tauri::command]
+pub async fn export_to_pdf(
+    path: String,
+    pages: Vec<ExportPdfPage>,
+    options: Option<ExportPdfOptions>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    write_pdf(Path::new(&path), &pages, options.unwrap_or_default(), state.pdf_backend.as_ref())
+}
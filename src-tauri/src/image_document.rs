@@ -0,0 +1,158 @@
+use crate::pdf_backend::Document;
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, RgbImage, Rgba, RgbaImage};
+use std::path::Path;
+use std::sync::Arc;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::ColorType;
+
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "tif", "tiff"];
+
+/// A raster file opened as a document: one page per frame, with the page
+/// size in points equal to the image size in pixels.
+pub struct ImageDocument {
+    pages: Vec<Arc<DynamicImage>>,
+}
+
+pub fn is_image_document(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn is_tiff(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| matches!(ext.to_ascii_lowercase().as_str(), "tif" | "tiff"))
+        .unwrap_or(false)
+}
+
+impl ImageDocument {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let pages = if is_tiff(path) {
+            read_tiff_pages(path)?
+        } else {
+            let image = image::ImageReader::open(path)
+                .map_err(|e| format!("Failed to open image: {}", e))?
+                .with_guessed_format()
+                .map_err(|e| format!("Failed to guess image format: {}", e))?
+                .decode()
+                .map_err(|e| format!("Failed to decode image: {}", e))?;
+            vec![image]
+        };
+
+        if pages.is_empty() {
+            return Err("Image contains no pages".to_string());
+        }
+
+        Ok(Self {
+            pages: pages.into_iter().map(Arc::new).collect(),
+        })
+    }
+
+    fn page(&self, page_number: usize) -> Result<&Arc<DynamicImage>, String> {
+        page_number
+            .checked_sub(1)
+            .and_then(|index| self.pages.get(index))
+            .ok_or_else(|| format!("Page {} not found", page_number))
+    }
+}
+
+impl Document for ImageDocument {
+    fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    fn page_size(&self, page_number: usize) -> Result<(f32, f32), String> {
+        let page = self.page(page_number)?;
+        Ok((page.width() as f32, page.height() as f32))
+    }
+
+    fn render_bitmap(&self, page_number: usize, pixel_width: i32, pixel_height: i32) -> Result<DynamicImage, String> {
+        let page = self.page(page_number)?;
+        let (width, height) = (pixel_width.max(1) as u32, pixel_height.max(1) as u32);
+
+        if width == page.width() && height == page.height() {
+            Ok(page.as_ref().clone())
+        } else {
+            Ok(page.resize_exact(width, height, FilterType::Triangle))
+        }
+    }
+}
+
+fn read_tiff_pages(path: &Path) -> Result<Vec<DynamicImage>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open image: {}", e))?;
+    let mut decoder = Decoder::new(std::io::BufReader::new(file)).map_err(|e| format!("Failed to read TIFF: {}", e))?;
+
+    let mut pages = Vec::new();
+    loop {
+        let page_number = pages.len() + 1;
+        let (width, height) = decoder
+            .dimensions()
+            .map_err(|e| format!("Failed to read TIFF page {}: {}", page_number, e))?;
+        let color_type = decoder
+            .colortype()
+            .map_err(|e| format!("Failed to read TIFF page {}: {}", page_number, e))?;
+        let data = decoder
+            .read_image()
+            .map_err(|e| format!("Failed to decode TIFF page {}: {}", page_number, e))?;
+
+        pages.push(
+            tiff_page_to_image(width, height, color_type, data)
+                .ok_or_else(|| format!("Unsupported TIFF color type {:?} on page {}", color_type, page_number))?,
+        );
+
+        if !decoder.more_images() {
+            break;
+        }
+        decoder
+            .next_image()
+            .map_err(|e| format!("Failed to read TIFF page {}: {}", page_number + 1, e))?;
+    }
+
+    Ok(pages)
+}
+
+fn tiff_page_to_image(width: u32, height: u32, color_type: ColorType, data: DecodingResult) -> Option<DynamicImage> {
+    match (color_type, data) {
+        (ColorType::Gray(1), DecodingResult::U8(bits)) => {
+            // Bilevel scans come packed eight pixels per byte, each row padded to a byte.
+            let row_bytes = (width as usize).div_ceil(8);
+            let mut gray = GrayImage::new(width, height);
+            for (x, y, pixel) in gray.enumerate_pixels_mut() {
+                let byte = bits.get(y as usize * row_bytes + x as usize / 8)?;
+                let set = byte & (0x80 >> (x % 8)) != 0;
+                *pixel = Luma([if set { 255 } else { 0 }]);
+            }
+            Some(DynamicImage::ImageLuma8(gray))
+        }
+        (ColorType::Gray(8), DecodingResult::U8(buf)) => GrayImage::from_raw(width, height, buf).map(DynamicImage::ImageLuma8),
+        (ColorType::GrayA(8), DecodingResult::U8(buf)) => GrayAlphaImage::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8),
+        (ColorType::RGB(8), DecodingResult::U8(buf)) => RgbImage::from_raw(width, height, buf).map(DynamicImage::ImageRgb8),
+        (ColorType::RGBA(8), DecodingResult::U8(buf)) => RgbaImage::from_raw(width, height, buf).map(DynamicImage::ImageRgba8),
+        (ColorType::CMYK(8), DecodingResult::U8(buf)) => {
+            let rgb: Vec<u8> = buf
+                .chunks_exact(4)
+                .flat_map(|cmyk| {
+                    let k = 255 - cmyk[3] as u16;
+                    [0, 1, 2].map(|i| ((255 - cmyk[i] as u16) * k / 255) as u8)
+                })
+                .collect();
+            RgbImage::from_raw(width, height, rgb).map(DynamicImage::ImageRgb8)
+        }
+        (ColorType::Gray(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::<Luma<u16>, _>::from_raw(width, height, buf).map(DynamicImage::ImageLuma16)
+        }
+        (ColorType::GrayA(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::<LumaA<u16>, _>::from_raw(width, height, buf).map(DynamicImage::ImageLumaA16)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::<Rgb<u16>, _>::from_raw(width, height, buf).map(DynamicImage::ImageRgb16)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(buf)) => {
+            ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, buf).map(DynamicImage::ImageRgba16)
+        }
+        _ => None,
+    }
+}
@@ -1,118 +1,176 @@
+pub mod export;
+mod image_document;
+mod page_cache;
+pub mod pdf_backend;
+pub mod project;
+pub mod render;
+mod scheduler;
+mod thumbnails;
+
 use base64::{engine::general_purpose::STANDARD, Engine};
-use pdfium_render::prelude::*;
+use image_document::{is_image_document, ImageDocument};
+use page_cache::{CachedPage, PageCache, PageCacheKey};
+use pdf_backend::{Document, PdfBackend, PdfBackendInfo};
+use render::{RenderOptions, RenderSize};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::sync::Mutex;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 use tauri::State;
 
-struct AppState {
+pub struct AppState {
     current_pdf_path: Mutex<Option<PathBuf>>,
     pdf_page_count: Mutex<usize>,
+    document_hashes: Mutex<HashMap<PathBuf, DocumentFingerprint>>,
+    render_scheduler: scheduler::RenderScheduler,
+    page_cache: Mutex<PageCache>,
+    image_document: Mutex<Option<(PathBuf, Arc<ImageDocument>)>>,
+    pdf_backend: Arc<dyn PdfBackend>,
+    pdf_backend_info: PdfBackendInfo,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct PdfPageInfo {
-    page_number: usize,
-    width: f32,
-    height: f32,
-    image_data: String,
+struct DocumentFingerprint {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct PdfPageMeta {
-    page_number: usize,
-    width: f32,
-    height: f32,
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PdfPageInfo {
+    pub page_number: usize,
+    pub width: f32,
+    pub height: f32,
+    pub pixel_width: i32,
+    pub pixel_height: i32,
+    pub scale: f32,
+    pub dpi: f32,
+    pub image_data: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct PdfOpenedEvent {
-    path: String,
-    page_count: usize,
-    pages_meta: Vec<PdfPageMeta>,
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PdfPageMeta {
+    pub page_number: usize,
+    pub width: f32,
+    pub height: f32,
 }
 
-fn get_pdfium() -> Result<Pdfium, String> {
-    let exe_path = std::env::current_exe().map_err(|e| format!("Failed to get exe path: {}", e))?;
-    let exe_dir = exe_path.parent().ok_or("Failed to get exe directory")?;
-    
-    let lib_path = Pdfium::pdfium_platform_library_name_at_path(exe_dir);
-    
-    let bindings = Pdfium::bind_to_library(&lib_path)
-        .or_else(|_| Pdfium::bind_to_system_library())
-        .map_err(|e| format!("Failed to bind Pdfium: {}", e))?;
-    
-    Ok(Pdfium::new(bindings))
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentKind {
+    Pdf,
+    Image,
 }
 
-#[tauri::command]
-async fn open_pdf(path: String, state: State<'_, AppState>) -> Result<PdfOpenedEvent, String> {
-    let pdf_path = PathBuf::from(&path);
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PdfOpenedEvent {
+    pub path: String,
+    pub page_count: usize,
+    pub pages_meta: Vec<PdfPageMeta>,
+    pub document_kind: DocumentKind,
+}
 
-    if !pdf_path.exists() {
-        return Err("File not found".to_string());
+impl AppState {
+    pub fn new(pdf_backend: Arc<dyn PdfBackend>, pdf_backend_info: PdfBackendInfo) -> Self {
+        Self {
+            current_pdf_path: Mutex::new(None),
+            pdf_page_count: Mutex::new(0),
+            document_hashes: Mutex::new(HashMap::new()),
+            render_scheduler: scheduler::RenderScheduler::new(),
+            page_cache: Mutex::new(PageCache::default()),
+            image_document: Mutex::new(None),
+            pdf_backend,
+            pdf_backend_info,
+        }
     }
 
-    let pdfium = get_pdfium()?;
-    
-    let document = pdfium
-        .load_pdf_from_file(&pdf_path, None)
-        .map_err(|e| format!("Failed to load PDF: {}", e))?;
-
-    let page_count = document.pages().len() as usize;
-    
-    let mut pages_meta: Vec<PdfPageMeta> = Vec::new();
-    
-    for index in 0..page_count {
-        let page = document.pages().get(index as u16).map_err(|e| format!("Failed to get page {}: {}", index + 1, e))?;
-        pages_meta.push(PdfPageMeta {
-            page_number: index + 1,
-            width: page.width().value,
-            height: page.height().value,
-        });
+    /// Opens a PDF or image and makes it the current document.
+    pub fn open_document(&self, path: &Path) -> Result<PdfOpenedEvent, String> {
+        if !path.exists() {
+            return Err("File not found".to_string());
+        }
+
+        let pages_meta = with_document(self, path, |document| {
+            (1..=document.page_count())
+                .map(|page_number| {
+                    let (width, height) = document.page_size(page_number)?;
+                    Ok(PdfPageMeta {
+                        page_number,
+                        width,
+                        height,
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()
+        })?;
+        let page_count = pages_meta.len();
+
+        *self.current_pdf_path.lock().unwrap() = Some(path.to_path_buf());
+        *self.pdf_page_count.lock().unwrap() = page_count;
+
+        Ok(PdfOpenedEvent {
+            path: path.to_string_lossy().to_string(),
+            page_count,
+            pages_meta,
+            document_kind: if is_image_document(path) {
+                DocumentKind::Image
+            } else {
+                DocumentKind::Pdf
+            },
+        })
     }
-    
-    *state.current_pdf_path.lock().unwrap() = Some(pdf_path);
-    *state.pdf_page_count.lock().unwrap() = page_count;
-
-    Ok(PdfOpenedEvent {
-        path,
-        page_count,
-        pages_meta,
-    })
-}
 
-#[tauri::command]
-async fn render_pdf_page(path: String, page_number: usize, width: Option<i32>) -> Result<PdfPageInfo, String> {
-    let pdf_path = PathBuf::from(&path);
+    /// Renders a page through the page cache.
+    pub fn render_page(&self, path: &Path, page_number: usize, options: &RenderOptions) -> Result<PdfPageInfo, String> {
+        let key = page_cache_key(self, path, page_number, options)?;
+        if let Some(page) = self.page_cache.lock().unwrap().get(&key) {
+            return Ok(page_info(page_number, &page));
+        }
 
-    let pdfium = get_pdfium()?;
-    
-    let document = pdfium
-        .load_pdf_from_file(&pdf_path, None)
-        .map_err(|e| format!("Failed to load PDF: {}", e))?;
+        let page = with_document(self, path, |document| document.render_page(page_number, options))?;
+        self.page_cache.lock().unwrap().insert(key, page.clone());
 
-    let page_index: u16 = (page_number - 1).try_into().map_err(|_| "Invalid page number")?;
-    
-    let page = document
-        .pages()
-        .get(page_index)
-        .map_err(|_| format!("Page {} not found", page_number))?;
+        Ok(page_info(page_number, &page))
+    }
 
-    let page_width = page.width().value;
-    let page_height = page.height().value;
+    pub fn document_metadata(&self, path: &Path) -> Result<PdfDocumentMetadata, String> {
+        if !path.exists() {
+            return Err("File not found".to_string());
+        }
 
-    let target_width = width.unwrap_or(1600);
+        with_document(self, path, |document| document.metadata())
+    }
+}
+
+/// Returns the SHA-256 of the document's contents, reusing the previous hash
+/// while the file's size and modification time are unchanged.
+fn document_hash(path: &Path, state: &AppState) -> Result<String, String> {
+    let file_meta = std::fs::metadata(path).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+    let modified = file_meta.modified().ok();
+    let len = file_meta.len();
+
+    if let Some(fingerprint) = state.document_hashes.lock().unwrap().get(path) {
+        if fingerprint.modified == modified && fingerprint.len == len {
+            return Ok(fingerprint.hash.clone());
+        }
+    }
+
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let hash = format!("{:x}", Sha256::digest(&bytes));
 
-    let render_config = PdfRenderConfig::new()
-        .set_target_width(target_width)
-        .set_maximum_height((target_width as f32 * page_height / page_width) as i32 + 100);
+    state.document_hashes.lock().unwrap().insert(
+        path.to_path_buf(),
+        DocumentFingerprint {
+            modified,
+            len,
+            hash: hash.clone(),
+        },
+    );
 
-    let image = page
-        .render_with_config(&render_config)
-        .map_err(|e| format!("Failed to render page: {}", e))?
-        .as_image();
+    Ok(hash)
+}
 
+fn encode_jpeg(image: &image::DynamicImage) -> Result<Vec<u8>, String> {
     let mut jpeg_data: Vec<u8> = Vec::new();
     image
         .write_to(
@@ -120,188 +178,121 @@ async fn render_pdf_page(path: String, page_number: usize, width: Option<i32>) -
             image::ImageFormat::Jpeg,
         )
         .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(jpeg_data)
+}
 
-    let base64_image = STANDARD.encode(&jpeg_data);
+fn jpeg_data_url(jpeg_data: &[u8]) -> String {
+    format!("data:image/jpeg;base64,{}", STANDARD.encode(jpeg_data))
+}
 
-    Ok(PdfPageInfo {
-        page_number,
-        width: page_width,
-        height: page_height,
-        image_data: format!("data:image/jpeg;base64,{}", base64_image),
-    })
+/// Returns the decoded image document for `path`, keeping the most recently
+/// used one in memory so page renders do not decode the file again.
+fn image_document(state: &AppState, path: &Path) -> Result<Arc<ImageDocument>, String> {
+    let mut slot = state.image_document.lock().unwrap();
+    if let Some((open_path, document)) = slot.as_ref() {
+        if open_path == path {
+            return Ok(Arc::clone(document));
+        }
+    }
+
+    let document = Arc::new(ImageDocument::open(path)?);
+    *slot = Some((path.to_path_buf(), Arc::clone(&document)));
+    Ok(document)
+}
+
+/// Opens `path` with the image decoder or the PDF backend and hands it to `f`.
+fn with_document<R>(state: &AppState, path: &Path, f: impl FnOnce(&dyn Document) -> Result<R, String>) -> Result<R, String> {
+    if is_image_document(path) {
+        f(image_document(state, path)?.as_ref())
+    } else {
+        f(state.pdf_backend.open(path)?.as_ref())
+    }
 }
 
 #[tauri::command]
-fn get_pdf_info(state: State<'_, AppState>) -> Result<(Option<String>, usize), String> {
-    let path = state.current_pdf_path.lock().unwrap();
-    let count = *state.pdf_page_count.lock().unwrap();
+async fn open_pdf(path: String, state: State<'_, AppState>) -> Result<PdfOpenedEvent, String> {
+    state.open_document(Path::new(&path))
+}
 
-    Ok((
-        path.as_ref().map(|p| p.to_string_lossy().to_string()),
-        count,
-    ))
+fn page_cache_key(state: &AppState, pdf_path: &Path, page_number: usize, options: &RenderOptions) -> Result<PageCacheKey, String> {
+    Ok(PageCacheKey {
+        document_hash: document_hash(pdf_path, state)?,
+        page_number,
+        options: options.cache_token(),
+    })
 }
 
-#[derive(Serialize, Deserialize)]
-struct ProjectData {
-    version: String,
-    pdf_path: Option<String>,
-    strokes: String,
+fn page_info(page_number: usize, page: &CachedPage) -> PdfPageInfo {
+    let RenderSize { pixel_width, pixel_height, scale, dpi } = page.size;
+
+    PdfPageInfo {
+        page_number,
+        width: page.page_width,
+        height: page.page_height,
+        pixel_width,
+        pixel_height,
+        scale,
+        dpi,
+        image_data: page.image_data.to_string(),
+    }
 }
 
 #[tauri::command]
-async fn save_project(path: String, pdf_path: Option<String>, strokes_json: String) -> Result<(), String> {
-    let project = ProjectData {
-        version: "1.0.0".to_string(),
-        pdf_path,
-        strokes: strokes_json,
-    };
-    
-    let json = serde_json::to_string_pretty(&project)
-        .map_err(|e| format!("Failed to serialize project: {}", e))?;
-    
-    std::fs::write(&path, json)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-    
-    Ok(())
+async fn render_pdf_page(
+    path: String,
+    page_number: usize,
+    width: Option<i32>,
+    options: Option<RenderOptions>,
+    state: State<'_, AppState>,
+) -> Result<PdfPageInfo, String> {
+    let mut options = options.unwrap_or_default();
+    options.width = options.width.or(width);
+
+    state.render_page(Path::new(&path), page_number, &options)
 }
 
 #[tauri::command]
-async fn load_project(path: String) -> Result<ProjectData, String> {
-    let content = std::fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-    
-    let project: ProjectData = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse project: {}", e))?;
-    
-    Ok(project)
+fn get_pdf_backend(state: State<'_, AppState>) -> PdfBackendInfo {
+    state.pdf_backend_info.clone()
 }
 
 #[tauri::command]
-async fn export_canvas(path: String, image_data: String) -> Result<(), String> {
-    let base64_data = image_data
-        .strip_prefix("data:image/png;base64,")
-        .or_else(|| image_data.strip_prefix("data:image/jpeg;base64,"))
-        .unwrap_or(&image_data);
-    
-    let decoded = STANDARD.decode(base64_data)
-        .map_err(|e| format!("Failed to decode image: {}", e))?;
-    
-    std::fs::write(&path, decoded)
-        .map_err(|e| format!("Failed to write image: {}", e))?;
-    
-    Ok(())
+fn get_pdf_info(state: State<'_, AppState>) -> Result<(Option<String>, usize), String> {
+    let path = state.current_pdf_path.lock().unwrap();
+    let count = *state.pdf_page_count.lock().unwrap();
+
+    Ok((
+        path.as_ref().map(|p| p.to_string_lossy().to_string()),
+        count,
+    ))
 }
 
-#[derive(Serialize, Deserialize)]
-struct ExportPdfPage {
-    image_data: String,
-    width: f32,
-    height: f32,
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PdfDocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    pub creation_date: Option<String>,
+    pub modification_date: Option<String>,
+    pub xmp: Option<String>,
 }
 
 #[tauri::command]
-async fn export_to_pdf(path: String, pages: Vec<ExportPdfPage>) -> Result<(), String> {
-    use printpdf::{PdfDocument, Mm, Px, Image, ImageXObject, ColorSpace, ColorBits, ImageTransform};
-    use ::image::ImageReader;
-    
-    if pages.is_empty() {
-        return Err("No pages to export".to_string());
-    }
-    
-    let first_page = &pages[0];
-    let page_width_mm = Mm(first_page.width * 0.264583);
-    let page_height_mm = Mm(first_page.height * 0.264583);
-    
-    let (doc, page1, layer1) = PdfDocument::new(
-        "Annotate Studio Export",
-        page_width_mm,
-        page_height_mm,
-        "Layer 1"
-    );
-    
-    for (i, page_data) in pages.iter().enumerate() {
-        let pw_mm = page_data.width * 0.264583;
-        let ph_mm = page_data.height * 0.264583;
-        
-        let (current_page, current_layer) = if i == 0 {
-            (page1, layer1)
-        } else {
-            let (page, layer) = doc.add_page(
-                Mm(pw_mm),
-                Mm(ph_mm),
-                "Layer 1"
-            );
-            (page, layer)
-        };
-        
-        let base64_data = page_data.image_data
-            .strip_prefix("data:image/png;base64,")
-            .or_else(|| page_data.image_data.strip_prefix("data:image/jpeg;base64,"))
-            .unwrap_or(&page_data.image_data);
-        
-        let decoded = STANDARD.decode(base64_data)
-            .map_err(|e| format!("Failed to decode image: {}", e))?;
-        
-        let img = ImageReader::new(std::io::Cursor::new(&decoded))
-            .with_guessed_format()
-            .map_err(|e| format!("Failed to guess image format: {}", e))?
-            .decode()
-            .map_err(|e| format!("Failed to decode image: {}", e))?;
-        
-        let img_rgb = img.to_rgb8();
-        let (img_width, img_height) = (img_rgb.width(), img_rgb.height());
-        
-        let image = Image::from(ImageXObject {
-            width: Px(img_width as usize),
-            height: Px(img_height as usize),
-            color_space: ColorSpace::Rgb,
-            bits_per_component: ColorBits::Bit8,
-            interpolate: true,
-            image_data: img_rgb.into_raw(),
-            image_filter: None,
-            clipping_bbox: None,
-            smask: None,
-        });
-        
-        let dpi = 72.0;
-        let img_width_mm = (img_width as f32 / dpi) * 25.4;
-        let img_height_mm = (img_height as f32 / dpi) * 25.4;
-        
-        let scale_x = pw_mm / img_width_mm;
-        let scale_y = ph_mm / img_height_mm;
-        
-        let layer = doc.get_page(current_page).get_layer(current_layer);
-        image.add_to_layer(
-            layer,
-            ImageTransform {
-                translate_x: Some(Mm(0.0)),
-                translate_y: Some(Mm(0.0)),
-                scale_x: Some(scale_x),
-                scale_y: Some(scale_y),
-                ..Default::default()
-            }
-        );
-    }
-    
-    let pdf_bytes = doc.save_to_bytes()
-        .map_err(|e| format!("Failed to save PDF: {}", e))?;
-    
-    std::fs::write(&path, pdf_bytes)
-        .map_err(|e| format!("Failed to write PDF file: {}", e))?;
-    
-    Ok(())
+async fn get_pdf_metadata(path: String, state: State<'_, AppState>) -> Result<PdfDocumentMetadata, String> {
+    state.document_metadata(Path::new(&path))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     std::env::set_var("WEBKIT_DISABLE_COMPOSITING_MODE", "1");
 
+    let (pdf_backend, pdf_backend_info) = pdf_backend::default_backend();
+
     tauri::Builder::default()
-        .manage(AppState {
-            current_pdf_path: Mutex::new(None),
-            pdf_page_count: Mutex::new(0),
-        })
+        .manage(AppState::new(pdf_backend, pdf_backend_info))
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
@@ -309,10 +300,20 @@ pub fn run() {
             open_pdf,
             render_pdf_page,
             get_pdf_info,
-            save_project,
-            load_project,
-            export_canvas,
-            export_to_pdf
+            get_pdf_metadata,
+            get_pdf_backend,
+            thumbnails::render_thumbnails,
+            scheduler::schedule_page_renders,
+            scheduler::cancel_page_renders,
+            scheduler::cancel_all_page_renders,
+            scheduler::get_render_queue_status,
+            page_cache::get_page_cache_stats,
+            page_cache::set_page_cache_limit,
+            page_cache::clear_page_cache,
+            project::save_project,
+            project::load_project,
+            export::export_canvas,
+            export::export_to_pdf
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,178 @@
+use crate::render::{RenderMode, RenderSize};
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tauri::State;
+
+pub const DEFAULT_PAGE_CACHE_BYTES: usize = 256 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PageCacheKey {
+    pub document_hash: String,
+    pub page_number: usize,
+    pub options: (Option<i32>, Option<u32>, Option<u32>, RenderMode),
+}
+
+/// A rendered page, already encoded as the JPEG data URL sent to the frontend.
+#[derive(Clone)]
+pub struct CachedPage {
+    pub image_data: Arc<str>,
+    pub page_width: f32,
+    pub page_height: f32,
+    pub size: RenderSize,
+}
+
+struct CacheEntry {
+    page: CachedPage,
+    bytes: usize,
+    last_used: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PageCacheStats {
+    pub entries: usize,
+    pub used_bytes: usize,
+    pub max_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub hit_rate: f64,
+}
+
+/// Least-recently-used cache of rendered pages, bounded by the total size of
+/// their encoded data rather than by entry count.
+pub struct PageCache {
+    entries: HashMap<PageCacheKey, CacheEntry>,
+    recency: BTreeMap<u64, PageCacheKey>,
+    tick: u64,
+    used_bytes: usize,
+    max_bytes: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl PageCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            used_bytes: 0,
+            max_bytes,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    fn touch(&mut self, key: &PageCacheKey) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.recency.insert(self.tick, key.clone());
+        }
+    }
+
+    pub fn get(&mut self, key: &PageCacheKey) -> Option<CachedPage> {
+        if self.entries.contains_key(key) {
+            self.hits += 1;
+            self.touch(key);
+            self.entries.get(key).map(|entry| entry.page.clone())
+        } else {
+            self.misses += 1;
+            None
+        }
+    }
+
+    pub fn insert(&mut self, key: PageCacheKey, page: CachedPage) {
+        let bytes = page.image_data.len();
+        if bytes > self.max_bytes {
+            return;
+        }
+
+        self.remove(&key);
+
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            CacheEntry {
+                page,
+                bytes,
+                last_used: self.tick,
+            },
+        );
+        self.used_bytes += bytes;
+
+        self.evict_to(self.max_bytes);
+    }
+
+    fn remove(&mut self, key: &PageCacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+            self.used_bytes -= entry.bytes;
+        }
+    }
+
+    fn evict_to(&mut self, max_bytes: usize) {
+        while self.used_bytes > max_bytes {
+            let Some((_, key)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&key) {
+                self.used_bytes -= entry.bytes;
+                self.evictions += 1;
+            }
+        }
+    }
+
+    pub fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+        self.evict_to(max_bytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.used_bytes = 0;
+    }
+
+    pub fn stats(&self) -> PageCacheStats {
+        let lookups = self.hits + self.misses;
+        PageCacheStats {
+            entries: self.entries.len(),
+            used_bytes: self.used_bytes,
+            max_bytes: self.max_bytes,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            hit_rate: if lookups > 0 { self.hits as f64 / lookups as f64 } else { 0.0 },
+        }
+    }
+}
+
+impl Default for PageCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_PAGE_CACHE_BYTES)
+    }
+}
+
+#[tauri::command]
+pub fn get_page_cache_stats(state: State<'_, AppState>) -> PageCacheStats {
+    state.page_cache.lock().unwrap().stats()
+}
+
+#[tauri::command]
+pub fn set_page_cache_limit(max_bytes: usize, state: State<'_, AppState>) -> PageCacheStats {
+    let mut cache = state.page_cache.lock().unwrap();
+    cache.set_max_bytes(max_bytes);
+    cache.stats()
+}
+
+#[tauri::command]
+pub fn clear_page_cache(state: State<'_, AppState>) {
+    state.page_cache.lock().unwrap().clear();
+}
@@ -0,0 +1,86 @@
+mod native;
+mod pdfium;
+pub mod raster;
+
+use crate::page_cache::CachedPage;
+use crate::render::{self, RenderOptions};
+use crate::{encode_jpeg, jpeg_data_url, PdfDocumentMetadata};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+pub use native::{read_xmp_metadata, NativeBackend};
+pub use pdfium::PdfiumBackend;
+
+/// Opens PDF files. Commands only talk to the backend through this trait, so
+/// the Pdfium binding can be swapped for the built-in renderer or a test mock.
+pub trait PdfBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn open<'a>(&'a self, path: &Path) -> Result<Box<dyn Document + 'a>, String>;
+}
+
+/// An open document with 1-based page numbers and page sizes in points.
+pub trait Document {
+    fn page_count(&self) -> usize;
+
+    fn page_size(&self, page_number: usize) -> Result<(f32, f32), String>;
+
+    /// Rasterises the whole page into exactly `pixel_width` x `pixel_height`.
+    fn render_bitmap(&self, page_number: usize, pixel_width: i32, pixel_height: i32) -> Result<DynamicImage, String>;
+
+    fn metadata(&self) -> Result<PdfDocumentMetadata, String> {
+        Ok(PdfDocumentMetadata::default())
+    }
+
+    fn render_page(&self, page_number: usize, options: &RenderOptions) -> Result<CachedPage, String> {
+        let (page_width, page_height) = self.page_size(page_number)?;
+        let size = options.size_for(page_width, page_height);
+        let image = self.render_bitmap(page_number, size.pixel_width, size.pixel_height)?;
+        let jpeg_data = encode_jpeg(&render::apply_render_mode(image, options.mode))?;
+
+        Ok(CachedPage {
+            image_data: jpeg_data_url(&jpeg_data).into(),
+            page_width,
+            page_height,
+            size,
+        })
+    }
+
+    /// Renders the page to fit in a `width` x `width` box.
+    fn render_thumbnail(&self, page_number: usize, width: i32) -> Result<DynamicImage, String> {
+        let (page_width, page_height) = self.page_size(page_number)?;
+        let scale = width.max(1) as f32 / page_width.max(page_height).max(1.0);
+        let pixel_width = (page_width * scale).round().max(1.0) as i32;
+        let pixel_height = (page_height * scale).round().max(1.0) as i32;
+        self.render_bitmap(page_number, pixel_width, pixel_height)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PdfBackendInfo {
+    pub name: String,
+    pub fallback_reason: Option<String>,
+}
+
+/// Binds Pdfium when it is available and otherwise falls back to the
+/// built-in renderer, which draws vector paths and images but no text.
+pub fn default_backend() -> (Arc<dyn PdfBackend>, PdfBackendInfo) {
+    match PdfiumBackend::new() {
+        Ok(backend) => (
+            Arc::new(backend),
+            PdfBackendInfo {
+                name: "pdfium".to_string(),
+                fallback_reason: None,
+            },
+        ),
+        Err(e) => (
+            Arc::new(NativeBackend),
+            PdfBackendInfo {
+                name: NativeBackend.name().to_string(),
+                fallback_reason: Some(e),
+            },
+        ),
+    }
+}
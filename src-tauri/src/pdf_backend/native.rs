@@ -0,0 +1,580 @@
+use super::raster::{self, FillRule, Matrix, Path as RasterPath};
+use super::{Document, PdfBackend};
+use crate::PdfDocumentMetadata;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use printpdf::lopdf::content::{Content, Operation};
+use printpdf::lopdf::{self, Dictionary, Object, ObjectId, Stream};
+use std::path::Path;
+
+const MAX_FORM_DEPTH: usize = 8;
+const MAX_TREE_DEPTH: usize = 32;
+const LETTER: (f32, f32, f32, f32) = (0.0, 0.0, 612.0, 792.0);
+
+/// Pure-Rust fallback built on lopdf. It reads metadata and page geometry
+/// exactly, and renders vector paths and raster images; text is not drawn.
+pub struct NativeBackend;
+
+impl PdfBackend for NativeBackend {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn open<'a>(&'a self, path: &Path) -> Result<Box<dyn Document + 'a>, String> {
+        let document = lopdf::Document::load(path).map_err(|e| format!("Failed to load PDF: {}", e))?;
+        if document.is_encrypted() {
+            return Err("Failed to load PDF: encrypted documents need Pdfium".to_string());
+        }
+
+        let pages = document.get_pages().into_values().collect();
+        Ok(Box::new(NativeDocument { document, pages }))
+    }
+}
+
+struct NativeDocument {
+    document: lopdf::Document,
+    pages: Vec<ObjectId>,
+}
+
+fn number(object: &Object) -> Option<f32> {
+    match object {
+        Object::Integer(value) => Some(*value as f32),
+        Object::Real(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn numbers(operands: &[Object]) -> Vec<f32> {
+    operands.iter().filter_map(number).collect()
+}
+
+/// Decodes a PDF text string: UTF-16BE or UTF-8 with a byte order mark,
+/// otherwise PDFDocEncoding, which matches Latin-1 for printable text.
+fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        let units = utf16.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+        char::decode_utf16(units).map(|c| c.unwrap_or('\u{FFFD}')).collect()
+    } else if let Some(utf8) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        String::from_utf8_lossy(utf8).to_string()
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    }
+}
+
+fn xmp_from(document: &lopdf::Document) -> Option<String> {
+    let metadata_ref = document.catalog().ok()?.get(b"Metadata").ok()?;
+    let (_, metadata) = document.dereference(metadata_ref).ok()?;
+    let stream = metadata.as_stream().ok()?;
+
+    let bytes = stream
+        .decompressed_content()
+        .unwrap_or_else(|_| stream.content.clone());
+
+    let xmp = String::from_utf8_lossy(&bytes).trim().to_string();
+    if xmp.is_empty() {
+        None
+    } else {
+        Some(xmp)
+    }
+}
+
+/// Reads the XMP packet from the catalog's /Metadata stream.
+pub fn read_xmp_metadata(pdf_path: &Path) -> Option<String> {
+    xmp_from(&lopdf::Document::load(pdf_path).ok()?)
+}
+
+impl NativeDocument {
+    fn page_id(&self, page_number: usize) -> Result<ObjectId, String> {
+        page_number
+            .checked_sub(1)
+            .and_then(|index| self.pages.get(index))
+            .copied()
+            .ok_or_else(|| format!("Page {} not found", page_number))
+    }
+
+    fn deref<'a>(&'a self, object: &'a Object) -> Option<&'a Object> {
+        self.document.dereference(object).ok().map(|(_, object)| object)
+    }
+
+    /// Looks `key` up on the page and then up its /Parent chain.
+    fn inherited(&self, page_id: ObjectId, key: &[u8]) -> Option<&Object> {
+        let mut node = self.document.get_dictionary(page_id).ok()?;
+        for _ in 0..MAX_TREE_DEPTH {
+            if let Ok(value) = node.get(key) {
+                return self.deref(value);
+            }
+            node = self.deref(node.get(b"Parent").ok()?)?.as_dict().ok()?;
+        }
+        None
+    }
+
+    fn page_box(&self, page_id: ObjectId) -> (f32, f32, f32, f32) {
+        let rect = |key: &[u8]| {
+            let values: Vec<f32> = self
+                .inherited(page_id, key)?
+                .as_array()
+                .ok()?
+                .iter()
+                .filter_map(|value| self.deref(value).and_then(number))
+                .collect();
+            match values[..] {
+                [x0, y0, x1, y1] if x0 != x1 && y0 != y1 => Some((x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))),
+                _ => None,
+            }
+        };
+        rect(b"CropBox").or_else(|| rect(b"MediaBox")).unwrap_or(LETTER)
+    }
+
+    fn rotation(&self, page_id: ObjectId) -> i64 {
+        self.inherited(page_id, b"Rotate")
+            .and_then(number)
+            .map(|degrees| ((degrees / 90.0).round() as i64 * 90).rem_euclid(360))
+            .unwrap_or(0)
+    }
+
+    fn info(&self) -> Option<&Dictionary> {
+        self.deref(self.document.trailer.get(b"Info").ok()?)?.as_dict().ok()
+    }
+}
+
+impl Document for NativeDocument {
+    fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    fn page_size(&self, page_number: usize) -> Result<(f32, f32), String> {
+        let page_id = self.page_id(page_number)?;
+        let (x0, y0, x1, y1) = self.page_box(page_id);
+        let (width, height) = (x1 - x0, y1 - y0);
+        match self.rotation(page_id) {
+            90 | 270 => Ok((height, width)),
+            _ => Ok((width, height)),
+        }
+    }
+
+    fn render_bitmap(&self, page_number: usize, pixel_width: i32, pixel_height: i32) -> Result<DynamicImage, String> {
+        let page_id = self.page_id(page_number)?;
+        let (x0, y0, x1, y1) = self.page_box(page_id);
+
+        // Maps user space to top-left-origin display points, applying /Rotate clockwise.
+        let (base, display_width, display_height) = match self.rotation(page_id) {
+            90 => (Matrix::new(0.0, 1.0, 1.0, 0.0, -y0, -x0), y1 - y0, x1 - x0),
+            180 => (Matrix::new(-1.0, 0.0, 0.0, 1.0, x1, -y0), x1 - x0, y1 - y0),
+            270 => (Matrix::new(0.0, -1.0, -1.0, 0.0, y1, x1), y1 - y0, x1 - x0),
+            _ => (Matrix::new(1.0, 0.0, 0.0, -1.0, -x0, y1), x1 - x0, y1 - y0),
+        };
+        let (pixel_width, pixel_height) = (pixel_width.max(1) as u32, pixel_height.max(1) as u32);
+        let device = base.then(&Matrix::new(
+            pixel_width as f32 / display_width,
+            0.0,
+            0.0,
+            pixel_height as f32 / display_height,
+            0.0,
+            0.0,
+        ));
+
+        let content = self
+            .document
+            .get_and_decode_page_content(page_id)
+            .map_err(|e| format!("Failed to parse page content: {}", e))?;
+
+        let (inline, resource_ids) = self.document.get_page_resources(page_id);
+        let resources: Vec<&Dictionary> = inline
+            .into_iter()
+            .chain(resource_ids.iter().filter_map(|id| self.document.get_dictionary(*id).ok()))
+            .collect();
+
+        let mut painter = Painter {
+            document: self,
+            image: RgbaImage::from_pixel(pixel_width, pixel_height, Rgba([255, 255, 255, 255])),
+        };
+        painter.run(&content.operations, &resources, device, 0);
+
+        Ok(DynamicImage::ImageRgba8(painter.image))
+    }
+
+    fn metadata(&self) -> Result<PdfDocumentMetadata, String> {
+        let info = self.info();
+        let tag = |key: &[u8]| {
+            let value = self.deref(info?.get(key).ok()?)?;
+            let text = decode_text_string(value.as_str().ok()?).trim().to_string();
+            if text.is_empty() {
+                None
+            } else {
+                Some(text)
+            }
+        };
+
+        Ok(PdfDocumentMetadata {
+            title: tag(b"Title"),
+            author: tag(b"Author"),
+            subject: tag(b"Subject"),
+            keywords: tag(b"Keywords"),
+            creator: tag(b"Creator"),
+            producer: tag(b"Producer"),
+            creation_date: tag(b"CreationDate"),
+            modification_date: tag(b"ModDate"),
+            xmp: xmp_from(&self.document),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct GraphicsState {
+    ctm: Matrix,
+    fill: [u8; 3],
+    stroke: [u8; 3],
+    line_width: f32,
+    fill_alpha: f32,
+    stroke_alpha: f32,
+}
+
+#[derive(Clone)]
+enum ColorSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+    /// Separation and DeviceN tints, drawn as gray ink.
+    Tint(usize),
+    Indexed(Box<ColorSpace>, Vec<u8>),
+}
+
+impl ColorSpace {
+    fn components(&self) -> usize {
+        match self {
+            ColorSpace::Gray | ColorSpace::Indexed(..) => 1,
+            ColorSpace::Rgb => 3,
+            ColorSpace::Cmyk => 4,
+            ColorSpace::Tint(n) => *n,
+        }
+    }
+
+    /// Converts one pixel's components, each scaled to 0..=255.
+    fn to_rgb(&self, components: &[u8]) -> [u8; 3] {
+        match self {
+            ColorSpace::Gray => [components[0]; 3],
+            ColorSpace::Rgb => [components[0], components[1], components[2]],
+            ColorSpace::Cmyk => cmyk_to_rgb(components[0], components[1], components[2], components[3]),
+            ColorSpace::Tint(_) => {
+                let ink = components.iter().map(|&c| c as u32).max().unwrap_or(0);
+                [255 - ink as u8; 3]
+            }
+            ColorSpace::Indexed(base, lookup) => {
+                let n = base.components();
+                let start = components[0] as usize * n;
+                match lookup.get(start..start + n) {
+                    Some(entry) => base.to_rgb(entry),
+                    None => [0, 0, 0],
+                }
+            }
+        }
+    }
+}
+
+fn cmyk_to_rgb(c: u8, m: u8, y: u8, k: u8) -> [u8; 3] {
+    let k = 255 - k as u16;
+    [c, m, y].map(|v| ((255 - v as u16) * k / 255) as u8)
+}
+
+fn color_from(values: &[f32]) -> Option<[u8; 3]> {
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    match values {
+        [gray] => Some([channel(*gray); 3]),
+        [r, g, b] => Some([channel(*r), channel(*g), channel(*b)]),
+        [c, m, y, k] => Some(cmyk_to_rgb(channel(*c), channel(*m), channel(*y), channel(*k))),
+        _ => None,
+    }
+}
+
+struct Painter<'a> {
+    document: &'a NativeDocument,
+    image: RgbaImage,
+}
+
+impl<'a> Painter<'a> {
+    fn resource(&self, resources: &[&'a Dictionary], category: &[u8], name: &[u8]) -> Option<&'a Object> {
+        resources.iter().find_map(|dict| {
+            let entries = self.document.deref(dict.get(category).ok()?)?.as_dict().ok()?;
+            self.document.deref(entries.get(name).ok()?)
+        })
+    }
+
+    fn run(&mut self, operations: &[Operation], resources: &[&'a Dictionary], ctm: Matrix, depth: usize) {
+        let mut state = GraphicsState {
+            ctm,
+            fill: [0, 0, 0],
+            stroke: [0, 0, 0],
+            line_width: 1.0,
+            fill_alpha: 1.0,
+            stroke_alpha: 1.0,
+        };
+        let mut saved = Vec::new();
+        let mut path = RasterPath::default();
+
+        for operation in operations {
+            let operands = &operation.operands;
+            let values = numbers(operands);
+            let point = |i: usize| state.ctm.apply(values[i], values[i + 1]);
+
+            match (operation.operator.as_str(), values.len()) {
+                ("q", _) => saved.push(state),
+                ("Q", _) => state = saved.pop().unwrap_or(state),
+                ("cm", 6) => {
+                    let m = Matrix::new(values[0], values[1], values[2], values[3], values[4], values[5]);
+                    state.ctm = m.then(&state.ctm);
+                }
+                ("w", 1) => state.line_width = values[0],
+                ("m", 2) => path.move_to(point(0)),
+                ("l", 2) => path.line_to(point(0)),
+                ("c", 6) => path.curve_to(point(0), point(2), point(4)),
+                ("v", 4) => {
+                    let start = path.current_point().unwrap_or(point(2));
+                    path.curve_to(start, point(0), point(2));
+                }
+                ("y", 4) => path.curve_to(point(0), point(2), point(2)),
+                ("h", _) => path.close(),
+                ("re", 4) => {
+                    let (x, y, w, h) = (values[0], values[1], values[2], values[3]);
+                    path.move_to(state.ctm.apply(x, y));
+                    path.line_to(state.ctm.apply(x + w, y));
+                    path.line_to(state.ctm.apply(x + w, y + h));
+                    path.line_to(state.ctm.apply(x, y + h));
+                    path.close();
+                }
+                ("S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "n", _) => {
+                    let op = operation.operator.as_str();
+                    if matches!(op, "s" | "b" | "b*") {
+                        path.close();
+                    }
+                    if !path.is_empty() {
+                        let fill_rule = match op {
+                            "f" | "F" | "B" | "b" => Some(FillRule::NonZero),
+                            "f*" | "B*" | "b*" => Some(FillRule::EvenOdd),
+                            _ => None,
+                        };
+                        if let Some(rule) = fill_rule {
+                            raster::fill_polygons(&mut self.image, &path.subpaths, rule, state.fill, state.fill_alpha);
+                        }
+                        if matches!(op, "S" | "s" | "B" | "B*" | "b" | "b*") {
+                            let width = state.line_width * state.ctm.scale();
+                            raster::stroke_path(&mut self.image, &path, width, state.stroke, state.stroke_alpha);
+                        }
+                    }
+                    path = RasterPath::default();
+                }
+                ("g" | "rg" | "k" | "sc" | "scn", _) => {
+                    if let Some(color) = color_from(&values) {
+                        state.fill = color;
+                    }
+                }
+                ("G" | "RG" | "K" | "SC" | "SCN", _) => {
+                    if let Some(color) = color_from(&values) {
+                        state.stroke = color;
+                    }
+                }
+                ("cs", _) => state.fill = [0, 0, 0],
+                ("CS", _) => state.stroke = [0, 0, 0],
+                ("gs", _) => {
+                    let Some(name) = operands.first().and_then(|o| o.as_name().ok()) else {
+                        continue;
+                    };
+                    if let Some(ext) = self.resource(resources, b"ExtGState", name).and_then(|o| o.as_dict().ok()) {
+                        let value = |key: &[u8]| ext.get(key).ok().and_then(number);
+                        if let Some(width) = value(b"LW") {
+                            state.line_width = width;
+                        }
+                        if let Some(alpha) = value(b"CA") {
+                            state.stroke_alpha = alpha.clamp(0.0, 1.0);
+                        }
+                        if let Some(alpha) = value(b"ca") {
+                            state.fill_alpha = alpha.clamp(0.0, 1.0);
+                        }
+                    }
+                }
+                ("Do", _) => {
+                    let Some(name) = operands.first().and_then(|o| o.as_name().ok()) else {
+                        continue;
+                    };
+                    if let Some(stream) = self.resource(resources, b"XObject", name).and_then(|o| o.as_stream().ok()) {
+                        self.draw_xobject(stream, resources, &state, depth);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn draw_xobject(&mut self, stream: &'a Stream, resources: &[&'a Dictionary], state: &GraphicsState, depth: usize) {
+        match stream.dict.get(b"Subtype").and_then(Object::as_name).ok() {
+            Some(b"Image") => {
+                if let Some(image) = self.decode_image(stream, resources) {
+                    raster::draw_image(&mut self.image, &image, &state.ctm, state.fill_alpha);
+                }
+            }
+            Some(b"Form") if depth < MAX_FORM_DEPTH => {
+                let values: Vec<f32> = stream
+                    .dict
+                    .get(b"Matrix")
+                    .and_then(Object::as_array)
+                    .map(|array| array.iter().filter_map(number).collect())
+                    .unwrap_or_default();
+                let matrix = match values[..] {
+                    [a, b, c, d, e, f] => Matrix::new(a, b, c, d, e, f),
+                    _ => Matrix::IDENTITY,
+                };
+
+                let bytes = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+                let Ok(content) = Content::decode(&bytes) else {
+                    return;
+                };
+
+                let form_resources = stream
+                    .dict
+                    .get(b"Resources")
+                    .ok()
+                    .and_then(|o| self.document.deref(o))
+                    .and_then(|o| o.as_dict().ok());
+                let resources: Vec<&'a Dictionary> = match form_resources {
+                    Some(dict) => vec![dict],
+                    None => resources.to_vec(),
+                };
+
+                self.run(&content.operations, &resources, matrix.then(&state.ctm), depth + 1);
+            }
+            _ => {}
+        }
+    }
+
+    fn color_space(&self, object: &Object, resources: &[&'a Dictionary]) -> Option<ColorSpace> {
+        let object = self.document.deref(object)?;
+        match object {
+            Object::Name(name) => match name.as_slice() {
+                b"DeviceGray" | b"CalGray" | b"G" => Some(ColorSpace::Gray),
+                b"DeviceRGB" | b"CalRGB" | b"RGB" => Some(ColorSpace::Rgb),
+                b"DeviceCMYK" | b"CMYK" => Some(ColorSpace::Cmyk),
+                other => self.color_space(self.resource(resources, b"ColorSpace", other)?, resources),
+            },
+            Object::Array(array) => {
+                let family = array.first()?.as_name().ok()?;
+                match family {
+                    b"CalGray" => Some(ColorSpace::Gray),
+                    b"CalRGB" | b"Lab" => Some(ColorSpace::Rgb),
+                    b"ICCBased" => {
+                        let profile = self.document.deref(array.get(1)?)?.as_stream().ok()?;
+                        match profile.dict.get(b"N").ok().and_then(number).map(|n| n as usize) {
+                            Some(1) => Some(ColorSpace::Gray),
+                            Some(4) => Some(ColorSpace::Cmyk),
+                            _ => Some(ColorSpace::Rgb),
+                        }
+                    }
+                    b"Indexed" | b"I" => {
+                        let base = self.color_space(array.get(1)?, resources)?;
+                        let lookup = match self.document.deref(array.get(3)?)? {
+                            Object::String(bytes, _) => bytes.clone(),
+                            Object::Stream(stream) => stream.decompressed_content().unwrap_or_else(|_| stream.content.clone()),
+                            _ => return None,
+                        };
+                        Some(ColorSpace::Indexed(Box::new(base), lookup))
+                    }
+                    b"Separation" => Some(ColorSpace::Tint(1)),
+                    b"DeviceN" => {
+                        let names = self.document.deref(array.get(1)?)?.as_array().ok()?;
+                        Some(ColorSpace::Tint(names.len().max(1)))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn decode_image(&self, stream: &Stream, resources: &[&'a Dictionary]) -> Option<RgbaImage> {
+        let dict = &stream.dict;
+        if dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false) {
+            return None;
+        }
+
+        let filters = stream.filters().unwrap_or_default();
+        let mut image = if filters.iter().any(|f| f == "DCTDecode" || f == "DCT") {
+            image::load_from_memory_with_format(&stream.content, ImageFormat::Jpeg)
+                .ok()?
+                .to_rgba8()
+        } else {
+            let width = dict.get(b"Width").ok().and_then(number)? as u32;
+            let height = dict.get(b"Height").ok().and_then(number)? as u32;
+            let bits = dict.get(b"BitsPerComponent").ok().and_then(number).unwrap_or(8.0) as u32;
+            let color_space = match dict.get(b"ColorSpace") {
+                Ok(object) => self.color_space(object, resources)?,
+                Err(_) => ColorSpace::Gray,
+            };
+
+            let data = if filters.is_empty() {
+                stream.content.clone()
+            } else {
+                // lopdf refuses to decompress image streams, so present it as a plain stream.
+                let mut plain = stream.clone();
+                plain.dict.remove(b"Subtype");
+                plain.decompressed_content().ok()?
+            };
+            unpack_samples(&data, width, height, bits, &color_space)?
+        };
+
+        if let Some(mask) = dict
+            .get(b"SMask")
+            .ok()
+            .and_then(|o| self.document.deref(o))
+            .and_then(|o| o.as_stream().ok())
+            .and_then(|mask| self.decode_image(mask, resources))
+        {
+            let (width, height) = image.dimensions();
+            let mask = image::imageops::resize(&mask, width, height, image::imageops::FilterType::Nearest);
+            for (pixel, alpha) in image.pixels_mut().zip(mask.pixels()) {
+                pixel.0[3] = alpha.0[0];
+            }
+        }
+
+        Some(image)
+    }
+}
+
+/// Expands packed image samples of 1, 2, 4, 8 or 16 bits per component,
+/// with every row starting on a byte boundary.
+fn unpack_samples(data: &[u8], width: u32, height: u32, bits: u32, color_space: &ColorSpace) -> Option<RgbaImage> {
+    if width == 0 || height == 0 || !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+        return None;
+    }
+
+    let components = color_space.components();
+    let row_bytes = (width as usize * components * bits as usize).div_ceil(8);
+    if data.len() < row_bytes * height as usize {
+        return None;
+    }
+
+    let indexed = matches!(color_space, ColorSpace::Indexed(..));
+    let max_value = (1u32 << bits.min(8)) - 1;
+    let mut image = RgbaImage::new(width, height);
+    let mut pixel_components = vec![0u8; components];
+
+    for y in 0..height {
+        let row = &data[y as usize * row_bytes..(y as usize + 1) * row_bytes];
+        for x in 0..width {
+            for (c, value) in pixel_components.iter_mut().enumerate() {
+                let index = x as usize * components + c;
+                let sample = match bits {
+                    16 => row[index * 2] as u32,
+                    8 => row[index] as u32,
+                    _ => {
+                        let bit = index * bits as usize;
+                        let shift = 8 - bits as usize - bit % 8;
+                        (row[bit / 8] as u32 >> shift) & max_value
+                    }
+                };
+                // Indexed samples are palette indices; everything else is scaled to 0..=255.
+                *value = if indexed || bits >= 8 { sample as u8 } else { (sample * 255 / max_value) as u8 };
+            }
+            let [r, g, b] = color_space.to_rgb(&pixel_components);
+            image.put_pixel(x, y, Rgba([r, g, b, 255]));
+        }
+    }
+
+    Some(image)
+}
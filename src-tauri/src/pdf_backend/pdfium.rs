@@ -0,0 +1,108 @@
+use super::{read_xmp_metadata, Document, PdfBackend};
+use crate::PdfDocumentMetadata;
+use image::DynamicImage;
+use pdfium_render::prelude::{PdfDocument, PdfDocumentMetadataTagType, PdfPage, PdfRenderConfig, Pdfium};
+use std::path::{Path, PathBuf};
+
+pub struct PdfiumBackend {
+    pdfium: Pdfium,
+}
+
+impl PdfiumBackend {
+    /// Binds the Pdfium library next to the executable, then the system one.
+    pub fn new() -> Result<Self, String> {
+        let exe_path = std::env::current_exe().map_err(|e| format!("Failed to get exe path: {}", e))?;
+        let exe_dir = exe_path.parent().ok_or("Failed to get exe directory")?;
+
+        let lib_path = Pdfium::pdfium_platform_library_name_at_path(exe_dir);
+
+        let bindings = Pdfium::bind_to_library(&lib_path)
+            .or_else(|_| Pdfium::bind_to_system_library())
+            .map_err(|e| format!("Failed to bind Pdfium: {}", e))?;
+
+        Ok(Self {
+            pdfium: Pdfium::new(bindings),
+        })
+    }
+}
+
+impl PdfBackend for PdfiumBackend {
+    fn name(&self) -> &'static str {
+        "pdfium"
+    }
+
+    fn open<'a>(&'a self, path: &Path) -> Result<Box<dyn Document + 'a>, String> {
+        let document = self
+            .pdfium
+            .load_pdf_from_file(path, None)
+            .map_err(|e| format!("Failed to load PDF: {}", e))?;
+
+        Ok(Box::new(PdfiumDocument {
+            document,
+            path: path.to_path_buf(),
+        }))
+    }
+}
+
+struct PdfiumDocument<'a> {
+    document: PdfDocument<'a>,
+    path: PathBuf,
+}
+
+impl PdfiumDocument<'_> {
+    fn page(&self, page_number: usize) -> Result<PdfPage<'_>, String> {
+        let page_index: u16 = page_number
+            .checked_sub(1)
+            .and_then(|index| index.try_into().ok())
+            .ok_or("Invalid page number")?;
+
+        self.document
+            .pages()
+            .get(page_index)
+            .map_err(|_| format!("Page {} not found", page_number))
+    }
+}
+
+impl Document for PdfiumDocument<'_> {
+    fn page_count(&self) -> usize {
+        self.document.pages().len() as usize
+    }
+
+    fn page_size(&self, page_number: usize) -> Result<(f32, f32), String> {
+        let page = self.page(page_number)?;
+        Ok((page.width().value, page.height().value))
+    }
+
+    fn render_bitmap(&self, page_number: usize, pixel_width: i32, pixel_height: i32) -> Result<DynamicImage, String> {
+        let render_config = PdfRenderConfig::new().set_fixed_size(pixel_width, pixel_height);
+
+        Ok(self
+            .page(page_number)?
+            .render_with_config(&render_config)
+            .map_err(|e| format!("Failed to render page: {}", e))?
+            .as_image())
+    }
+
+    fn metadata(&self) -> Result<PdfDocumentMetadata, String> {
+        let metadata = self.document.metadata();
+        let tag = |tag_type: PdfDocumentMetadataTagType| {
+            metadata
+                .get(tag_type)
+                .map(|tag| tag.value().trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        Ok(PdfDocumentMetadata {
+            title: tag(PdfDocumentMetadataTagType::Title),
+            author: tag(PdfDocumentMetadataTagType::Author),
+            subject: tag(PdfDocumentMetadataTagType::Subject),
+            keywords: tag(PdfDocumentMetadataTagType::Keywords),
+            creator: tag(PdfDocumentMetadataTagType::Creator),
+            producer: tag(PdfDocumentMetadataTagType::Producer),
+            creation_date: tag(PdfDocumentMetadataTagType::CreationDate),
+            modification_date: tag(PdfDocumentMetadataTagType::ModificationDate),
+            // Pdfium has no API for the catalog's /Metadata stream.
+            xmp: read_xmp_metadata(&self.path),
+        })
+    }
+}
@@ -0,0 +1,336 @@
+use image::{Rgba, RgbaImage};
+
+/// Vertical samples per pixel row; horizontal coverage is computed exactly.
+const SUBSAMPLES: usize = 4;
+const CIRCLE_SEGMENTS: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 };
+
+    pub fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    /// The transform that applies `self` first and `next` second.
+    pub fn then(&self, next: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * next.a + self.b * next.c,
+            b: self.a * next.b + self.b * next.d,
+            c: self.c * next.a + self.d * next.c,
+            d: self.c * next.b + self.d * next.d,
+            e: self.e * next.a + self.f * next.c + next.e,
+            f: self.e * next.b + self.f * next.d + next.f,
+        }
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.a * x + self.c * y + self.e, self.b * x + self.d * y + self.f)
+    }
+
+    pub fn invert(&self) -> Option<Matrix> {
+        let det = self.a * self.d - self.b * self.c;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        Some(Matrix {
+            a: self.d / det,
+            b: -self.b / det,
+            c: -self.c / det,
+            d: self.a / det,
+            e: (self.c * self.f - self.d * self.e) / det,
+            f: (self.b * self.e - self.a * self.f) / det,
+        })
+    }
+
+    /// Average scale factor, used to turn user-space line widths into pixels.
+    pub fn scale(&self) -> f32 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+/// A flattened path in device pixels, one polyline per subpath.
+#[derive(Clone, Default, Debug)]
+pub struct Path {
+    pub subpaths: Vec<Vec<(f32, f32)>>,
+    closed: Vec<bool>,
+}
+
+impl Path {
+    pub fn move_to(&mut self, point: (f32, f32)) {
+        self.subpaths.push(vec![point]);
+        self.closed.push(false);
+    }
+
+    pub fn line_to(&mut self, point: (f32, f32)) {
+        match self.subpaths.last_mut() {
+            Some(subpath) => subpath.push(point),
+            None => self.move_to(point),
+        }
+    }
+
+    pub fn curve_to(&mut self, c1: (f32, f32), c2: (f32, f32), end: (f32, f32)) {
+        let Some(&start) = self.subpaths.last().and_then(|subpath| subpath.last()) else {
+            self.move_to(end);
+            return;
+        };
+
+        let hull = distance(start, c1) + distance(c1, c2) + distance(c2, end);
+        let steps = (hull / 2.0).ceil().clamp(1.0, 64.0) as usize;
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            let mt = 1.0 - t;
+            let x = mt * mt * mt * start.0 + 3.0 * mt * mt * t * c1.0 + 3.0 * mt * t * t * c2.0 + t * t * t * end.0;
+            let y = mt * mt * mt * start.1 + 3.0 * mt * mt * t * c1.1 + 3.0 * mt * t * t * c2.1 + t * t * t * end.1;
+            self.line_to((x, y));
+        }
+    }
+
+    pub fn close(&mut self) {
+        if let (Some(subpath), Some(closed)) = (self.subpaths.last(), self.closed.last_mut()) {
+            *closed = true;
+            let start = subpath[0];
+            // Later segments start a new subpath at the same point, as in PDF.
+            self.subpaths.push(vec![start]);
+            self.closed.push(false);
+        }
+    }
+
+    pub fn current_point(&self) -> Option<(f32, f32)> {
+        self.subpaths.last().and_then(|subpath| subpath.last()).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subpaths.iter().all(|subpath| subpath.len() < 2)
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+struct Edge {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    winding: i32,
+}
+
+/// Fills `polygons` (each implicitly closed) with `color`, blending over the
+/// existing pixels with anti-aliased coverage.
+pub fn fill_polygons(image: &mut RgbaImage, polygons: &[Vec<(f32, f32)>], rule: FillRule, color: [u8; 3], alpha: f32) {
+    let mut edges = Vec::new();
+    let (mut min_y, mut max_y) = (f32::MAX, f32::MIN);
+    let (mut min_x, mut max_x) = (f32::MAX, f32::MIN);
+
+    for polygon in polygons.iter().filter(|polygon| polygon.len() >= 2) {
+        for i in 0..polygon.len() {
+            let (x0, y0) = polygon[i];
+            let (x1, y1) = polygon[(i + 1) % polygon.len()];
+            if !(x0.is_finite() && y0.is_finite() && x1.is_finite() && y1.is_finite()) || y0 == y1 {
+                continue;
+            }
+            min_y = min_y.min(y0.min(y1));
+            max_y = max_y.max(y0.max(y1));
+            min_x = min_x.min(x0.min(x1));
+            max_x = max_x.max(x0.max(x1));
+            edges.push(Edge {
+                x0,
+                y0,
+                x1,
+                y1,
+                winding: if y1 > y0 { 1 } else { -1 },
+            });
+        }
+    }
+
+    if edges.is_empty() || alpha <= 0.0 {
+        return;
+    }
+
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let first_row = min_y.floor().max(0.0) as usize;
+    let last_row = (max_y.ceil().max(0.0) as usize).min(height);
+    let first_col = min_x.floor().max(0.0) as usize;
+    let last_col = (max_x.ceil().max(0.0) as usize + 1).min(width);
+    if first_row >= last_row || first_col >= last_col {
+        return;
+    }
+
+    let mut coverage = vec![0.0f32; width + 1];
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+
+    for row in first_row..last_row {
+        coverage[first_col..last_col].iter_mut().for_each(|c| *c = 0.0);
+
+        for sample in 0..SUBSAMPLES {
+            let y = row as f32 + (sample as f32 + 0.5) / SUBSAMPLES as f32;
+            crossings.clear();
+            for edge in &edges {
+                let (top, bottom) = if edge.y0 < edge.y1 { (edge.y0, edge.y1) } else { (edge.y1, edge.y0) };
+                if y < top || y >= bottom {
+                    continue;
+                }
+                let x = edge.x0 + (y - edge.y0) * (edge.x1 - edge.x0) / (edge.y1 - edge.y0);
+                crossings.push((x, edge.winding));
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                let inside = match rule {
+                    FillRule::NonZero => winding != 0,
+                    FillRule::EvenOdd => winding % 2 != 0,
+                };
+                if inside {
+                    add_span(&mut coverage[..width], pair[0].0, pair[1].0, 1.0 / SUBSAMPLES as f32);
+                }
+            }
+        }
+
+        for (col, cell) in coverage.iter().enumerate().take(last_col).skip(first_col) {
+            let amount = cell.min(1.0) * alpha;
+            if amount > 0.0 {
+                blend(image.get_pixel_mut(col as u32, row as u32), color, amount);
+            }
+        }
+    }
+}
+
+fn add_span(coverage: &mut [f32], start: f32, end: f32, weight: f32) {
+    let start = start.max(0.0);
+    let end = end.min(coverage.len() as f32);
+    if end <= start {
+        return;
+    }
+
+    let first = start.floor() as usize;
+    let last = (end.ceil() as usize).min(coverage.len());
+    for (col, cell) in coverage.iter_mut().enumerate().take(last).skip(first) {
+        let left = start.max(col as f32);
+        let right = end.min(col as f32 + 1.0);
+        if right > left {
+            *cell += (right - left) * weight;
+        }
+    }
+}
+
+pub fn blend(pixel: &mut Rgba<u8>, color: [u8; 3], alpha: f32) {
+    let alpha = alpha.clamp(0.0, 1.0);
+    for (channel, &target) in pixel.0.iter_mut().zip(&color) {
+        let base = *channel as f32;
+        *channel = (base + (target as f32 - base) * alpha).round() as u8;
+    }
+    pixel.0[3] = 255;
+}
+
+/// Strokes every subpath with round joins and caps by filling one polygon per
+/// segment and one per vertex; the nonzero rule merges the overlaps.
+pub fn stroke_path(image: &mut RgbaImage, path: &Path, width: f32, color: [u8; 3], alpha: f32) {
+    let half = width.max(1.0) / 2.0;
+    let mut polygons = Vec::new();
+
+    for (subpath, closed) in path.subpaths.iter().zip(&path.closed) {
+        let mut points = subpath.clone();
+        if *closed && points.len() > 2 {
+            points.push(points[0]);
+        }
+
+        for pair in points.windows(2) {
+            let (p0, p1) = (pair[0], pair[1]);
+            let length = distance(p0, p1);
+            if length == 0.0 {
+                continue;
+            }
+            let (nx, ny) = (-(p1.1 - p0.1) / length * half, (p1.0 - p0.0) / length * half);
+            polygons.push(oriented(vec![
+                (p0.0 + nx, p0.1 + ny),
+                (p1.0 + nx, p1.1 + ny),
+                (p1.0 - nx, p1.1 - ny),
+                (p0.0 - nx, p0.1 - ny),
+            ]));
+        }
+
+        if half > 0.75 {
+            for &point in &points {
+                polygons.push(oriented(circle(point, half)));
+            }
+        }
+    }
+
+    fill_polygons(image, &polygons, FillRule::NonZero, color, alpha);
+}
+
+fn circle(center: (f32, f32), radius: f32) -> Vec<(f32, f32)> {
+    (0..CIRCLE_SEGMENTS)
+        .map(|i| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// Reverses the polygon if needed so every stroke piece winds the same way.
+fn oriented(mut polygon: Vec<(f32, f32)>) -> Vec<(f32, f32)> {
+    let area: f32 = (0..polygon.len())
+        .map(|i| {
+            let (x0, y0) = polygon[i];
+            let (x1, y1) = polygon[(i + 1) % polygon.len()];
+            x0 * y1 - x1 * y0
+        })
+        .sum();
+    if area < 0.0 {
+        polygon.reverse();
+    }
+    polygon
+}
+
+/// Draws `source` so that the unit square maps through `transform` (image
+/// space, with row 0 at the top of the square), sampling nearest pixels.
+pub fn draw_image(image: &mut RgbaImage, source: &RgbaImage, transform: &Matrix, alpha: f32) {
+    let Some(inverse) = transform.invert() else {
+        return;
+    };
+    if source.width() == 0 || source.height() == 0 {
+        return;
+    }
+
+    let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(x, y)| transform.apply(x, y));
+    let min_x = corners.iter().map(|c| c.0).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+    let max_x = corners.iter().map(|c| c.0).fold(f32::MIN, f32::max).ceil().min(image.width() as f32) as u32;
+    let min_y = corners.iter().map(|c| c.1).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+    let max_y = corners.iter().map(|c| c.1).fold(f32::MIN, f32::max).ceil().min(image.height() as f32) as u32;
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (u, v) = inverse.apply(x as f32 + 0.5, y as f32 + 0.5);
+            if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                continue;
+            }
+            let sx = ((u * source.width() as f32) as u32).min(source.width() - 1);
+            let sy = (((1.0 - v) * source.height() as f32) as u32).min(source.height() - 1);
+            let [r, g, b, a] = source.get_pixel(sx, sy).0;
+            let amount = alpha * a as f32 / 255.0;
+            if amount > 0.0 {
+                blend(image.get_pixel_mut(x, y), [r, g, b], amount);
+            }
+        }
+    }
+}
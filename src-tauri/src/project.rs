@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Page setup for a project that has no backing PDF. Sizes are in points.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NotebookSettings {
+    pub page_width: f32,
+    pub page_height: f32,
+    pub page_count: usize,
+    pub template: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ProjectData {
+    pub version: String,
+    pub pdf_path: Option<String>,
+    pub strokes: String,
+    #[serde(default)]
+    pub notebook: Option<NotebookSettings>,
+}
+
+pub fn save(path: &Path, pdf_path: Option<String>, strokes_json: String, notebook: Option<NotebookSettings>) -> Result<(), String> {
+    if let Some(notebook) = &notebook {
+        if notebook.page_width <= 0.0 || notebook.page_height <= 0.0 || notebook.page_count == 0 {
+            return Err("Invalid notebook page setup".to_string());
+        }
+    }
+
+    let project = ProjectData {
+        version: "1.0.0".to_string(),
+        pdf_path,
+        strokes: strokes_json,
+        notebook,
+    };
+
+    let json = serde_json::to_string_pretty(&project)
+        .map_err(|e| format!("Failed to serialize project: {}", e))?;
+
+    std::fs::write(path, json)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<ProjectData, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse project: {}", e))
+}
+
+#[tauri::command]
+pub async fn save_project(
+    path: String,
+    pdf_path: Option<String>,
+    strokes_json: String,
+    notebook: Option<NotebookSettings>,
+) -> Result<(), String> {
+    save(Path::new(&path), pdf_path, strokes_json, notebook)
+}
+
+#[tauri::command]
+pub async fn load_project(path: String) -> Result<ProjectData, String> {
+    load(Path::new(&path))
+}
@@ -0,0 +1,133 @@
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_RENDER_WIDTH: i32 = 1600;
+pub const POINTS_PER_INCH: f32 = 72.0;
+
+const MAX_RENDER_DIMENSION: f32 = 16384.0;
+const MAX_RENDER_PIXELS: f32 = 100_000_000.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum RenderMode {
+    #[default]
+    Normal,
+    Inverted,
+    Grayscale,
+    Sepia,
+    HighContrast,
+}
+
+/// How large to rasterise a page. `zoom` is CSS pixels per PDF point (1.0 shows
+/// the page at its natural size) and takes precedence over the legacy `width`
+/// in CSS pixels; either is multiplied by `device_pixel_ratio`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderOptions {
+    #[serde(default)]
+    pub width: Option<i32>,
+    #[serde(default)]
+    pub zoom: Option<f32>,
+    #[serde(default)]
+    pub device_pixel_ratio: Option<f32>,
+    #[serde(default)]
+    pub mode: RenderMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RenderSize {
+    pub pixel_width: i32,
+    pub pixel_height: i32,
+    pub scale: f32,
+    pub dpi: f32,
+}
+
+fn positive(value: Option<f32>) -> Option<f32> {
+    value.filter(|v| v.is_finite() && *v > 0.0)
+}
+
+impl RenderOptions {
+    /// Output pixels per PDF point before any size limits are applied.
+    fn requested_scale(&self, page_width: f32) -> f32 {
+        let css_scale = match (positive(self.zoom), self.width.filter(|w| *w > 0)) {
+            (Some(zoom), _) => zoom,
+            (None, Some(width)) => width as f32 / page_width,
+            (None, None) => DEFAULT_RENDER_WIDTH as f32 / page_width,
+        };
+        css_scale * positive(self.device_pixel_ratio).unwrap_or(1.0)
+    }
+
+    /// Computes the bitmap size for a page measured in points, keeping the
+    /// page's aspect ratio and staying within the renderer's pixel limits.
+    pub fn size_for(&self, page_width: f32, page_height: f32) -> RenderSize {
+        let page_width = page_width.max(1.0);
+        let page_height = page_height.max(1.0);
+
+        let scale = self
+            .requested_scale(page_width)
+            .min(MAX_RENDER_DIMENSION / page_width.max(page_height))
+            .min((MAX_RENDER_PIXELS / (page_width * page_height)).sqrt());
+
+        RenderSize {
+            pixel_width: (page_width * scale).round().max(1.0) as i32,
+            pixel_height: (page_height * scale).round().max(1.0) as i32,
+            scale,
+            dpi: scale * POINTS_PER_INCH,
+        }
+    }
+
+    /// Hashable form of the options, with ratios quantised to thousandths.
+    pub fn cache_token(&self) -> (Option<i32>, Option<u32>, Option<u32>, RenderMode) {
+        let quantise = |value: Option<f32>| positive(value).map(|v| (v * 1000.0).round() as u32);
+        (self.width, quantise(self.zoom), quantise(self.device_pixel_ratio), self.mode)
+    }
+}
+
+fn luma(r: u8, g: u8, b: u8) -> f32 {
+    0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32
+}
+
+fn to_channel(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+/// Recolours a rendered page in place. `Inverted` flips HSL lightness while
+/// keeping hue and saturation, so dark text turns light without red turning cyan.
+pub fn apply_render_mode(image: DynamicImage, mode: RenderMode) -> DynamicImage {
+    if mode == RenderMode::Normal {
+        return image;
+    }
+
+    let mut rgba = image.into_rgba8();
+    for pixel in rgba.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        pixel.0 = match mode {
+            RenderMode::Normal => [r, g, b, a],
+            RenderMode::Inverted => {
+                // Shifting every channel by the same amount keeps chroma and hue;
+                // this shift maps lightness (max + min) / 2 to 1 - lightness.
+                let shift = 255 - r.max(g).max(b) as i32 - r.min(g).min(b) as i32;
+                let invert = |c: u8| (c as i32 + shift).clamp(0, 255) as u8;
+                [invert(r), invert(g), invert(b), a]
+            }
+            RenderMode::Grayscale => {
+                let y = to_channel(luma(r, g, b));
+                [y, y, y, a]
+            }
+            RenderMode::Sepia => {
+                let (r, g, b) = (r as f32, g as f32, b as f32);
+                [
+                    to_channel(0.393 * r + 0.769 * g + 0.189 * b),
+                    to_channel(0.349 * r + 0.686 * g + 0.168 * b),
+                    to_channel(0.272 * r + 0.534 * g + 0.131 * b),
+                    a,
+                ]
+            }
+            RenderMode::HighContrast => {
+                let y = to_channel((luma(r, g, b) - 128.0) * 2.5 + 128.0);
+                [y, y, y, a]
+            }
+        };
+    }
+
+    DynamicImage::ImageRgba8(rgba)
+}
@@ -0,0 +1,433 @@
+use crate::image_document::is_image_document;
+use crate::page_cache::CachedPage;
+use crate::pdf_backend::{Document, PdfBackend};
+use crate::render::RenderOptions;
+use crate::{image_document, page_cache_key, page_info, AppState};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Condvar, Mutex};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const MAX_WORKERS: usize = 4;
+
+pub const RENDER_COMPLETE_EVENT: &str = "page-render-complete";
+pub const RENDER_FAILED_EVENT: &str = "page-render-failed";
+pub const RENDER_CANCELLED_EVENT: &str = "page-render-cancelled";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderPriority {
+    Visible,
+    Adjacent,
+    Prefetch,
+}
+
+#[derive(Clone)]
+struct RenderJob {
+    request_id: u64,
+    path: PathBuf,
+    page_number: usize,
+    options: RenderOptions,
+    priority: RenderPriority,
+    sequence: u64,
+}
+
+impl RenderJob {
+    fn same_target(&self, path: &PathBuf, page_number: usize, options: &RenderOptions) -> bool {
+        self.path == *path && self.page_number == page_number && self.options == *options
+    }
+}
+
+// BinaryHeap is a max-heap: the most urgent priority wins, and within one
+// priority the oldest request (lowest sequence) goes first.
+impl Ord for RenderJob {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .cmp(&self.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for RenderJob {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for RenderJob {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for RenderJob {}
+
+#[derive(Default)]
+struct QueueState {
+    pending: BinaryHeap<RenderJob>,
+    running: Vec<RenderJob>,
+    cancelled: HashSet<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PageRenderComplete {
+    pub request_id: u64,
+    pub path: String,
+    pub page_number: usize,
+    pub width: f32,
+    pub height: f32,
+    pub pixel_width: i32,
+    pub pixel_height: i32,
+    pub scale: f32,
+    pub dpi: f32,
+    pub image_data: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PageRenderFailed {
+    pub request_id: u64,
+    pub path: String,
+    pub page_number: usize,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PageRenderCancelled {
+    pub request_id: u64,
+    pub path: String,
+    pub page_number: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PageRenderRequest {
+    pub page_number: usize,
+    pub priority: RenderPriority,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenderQueueStatus {
+    pub pending: usize,
+    pub running: usize,
+    pub workers: usize,
+}
+
+/// Renders PDF pages on a small worker pool, most urgent request first.
+/// Results are delivered as events; queued requests can be cancelled or
+/// re-prioritised before a worker picks them up.
+pub struct RenderScheduler {
+    queue: Arc<(Mutex<QueueState>, Condvar)>,
+    next_id: AtomicU64,
+    workers: usize,
+    // Workers actually running; spawning is retried until all of them are.
+    started: Mutex<usize>,
+}
+
+impl RenderScheduler {
+    pub fn new() -> Self {
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(2)
+            .clamp(1, MAX_WORKERS);
+
+        Self {
+            queue: Arc::new((Mutex::new(QueueState::default()), Condvar::new())),
+            next_id: AtomicU64::new(1),
+            workers,
+            started: Mutex::new(0),
+        }
+    }
+
+    /// Starts any workers that are not running yet. Fails only when none
+    /// are, since queued requests would then never be answered.
+    fn ensure_workers(&self, app: &AppHandle) -> Result<(), String> {
+        let mut started = self.started.lock().unwrap();
+        while *started < self.workers {
+            let queue = Arc::clone(&self.queue);
+            let app = app.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("page-render-{}", *started))
+                .spawn(move || worker_loop(queue, app));
+            match spawned {
+                Ok(_) => *started += 1,
+                Err(e) if *started == 0 => return Err(format!("Failed to start page render workers: {}", e)),
+                Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Queues a render and returns its request id. A request for the same page
+    /// and size that is still waiting is re-prioritised instead of duplicated.
+    pub fn schedule(&self, app: &AppHandle, path: PathBuf, page_number: usize, options: RenderOptions, priority: RenderPriority) -> Result<u64, String> {
+        self.ensure_workers(app)?;
+
+        let (lock, condvar) = &*self.queue;
+        let mut state = lock.lock().unwrap();
+
+        if let Some(running) = state.running.iter().find(|job| job.same_target(&path, page_number, &options)) {
+            if !state.cancelled.contains(&running.request_id) {
+                return Ok(running.request_id);
+            }
+        }
+
+        let sequence = self.next_id.fetch_add(1, AtomicOrdering::Relaxed);
+        let mut jobs = std::mem::take(&mut state.pending).into_vec();
+
+        let request_id = match jobs.iter_mut().find(|job| job.same_target(&path, page_number, &options)) {
+            Some(existing) => {
+                existing.priority = existing.priority.min(priority);
+                existing.request_id
+            }
+            None => {
+                jobs.push(RenderJob {
+                    request_id: sequence,
+                    path,
+                    page_number,
+                    options,
+                    priority,
+                    sequence,
+                });
+                sequence
+            }
+        };
+
+        state.pending = BinaryHeap::from(jobs);
+        condvar.notify_one();
+
+        Ok(request_id)
+    }
+
+    /// Drops the given requests. Queued ones never run; ones already rendering
+    /// finish but report `page-render-cancelled` instead of their image.
+    pub fn cancel(&self, request_ids: &[u64]) -> usize {
+        let (lock, _) = &*self.queue;
+        let mut state = lock.lock().unwrap();
+
+        let before = state.pending.len();
+        state.pending.retain(|job| !request_ids.contains(&job.request_id));
+        let mut cancelled = before - state.pending.len();
+
+        let running: Vec<u64> = state
+            .running
+            .iter()
+            .map(|job| job.request_id)
+            .filter(|id| request_ids.contains(id))
+            .collect();
+        cancelled += running.len();
+        state.cancelled.extend(running);
+
+        cancelled
+    }
+
+    /// Cancels everything queued or running, optionally only for one document.
+    pub fn cancel_all(&self, path: Option<&PathBuf>) -> usize {
+        let ids: Vec<u64> = {
+            let (lock, _) = &*self.queue;
+            let state = lock.lock().unwrap();
+            state
+                .pending
+                .iter()
+                .chain(state.running.iter())
+                .filter(|job| path.is_none_or(|p| job.path == *p))
+                .map(|job| job.request_id)
+                .collect()
+        };
+        self.cancel(&ids)
+    }
+
+    pub fn status(&self) -> RenderQueueStatus {
+        let (lock, _) = &*self.queue;
+        let state = lock.lock().unwrap();
+        RenderQueueStatus {
+            pending: state.pending.len(),
+            running: state.running.len(),
+            workers: *self.started.lock().unwrap(),
+        }
+    }
+}
+
+impl Default for RenderScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn next_job(queue: &(Mutex<QueueState>, Condvar)) -> RenderJob {
+    let (lock, condvar) = queue;
+    let mut state = lock.lock().unwrap();
+    loop {
+        if let Some(job) = state.pending.pop() {
+            state.running.push(job.clone());
+            return job;
+        }
+        state = condvar.wait(state).unwrap();
+    }
+}
+
+fn finish_job(queue: &(Mutex<QueueState>, Condvar), request_id: u64) -> bool {
+    let (lock, _) = queue;
+    let mut state = lock.lock().unwrap();
+    state.running.retain(|job| job.request_id != request_id);
+    state.cancelled.remove(&request_id)
+}
+
+/// A document kept open between jobs, with the path and content hash it was
+/// opened at.
+type OpenDocument<'a> = (PathBuf, String, Box<dyn Document + 'a>);
+
+/// Renders with the open document when it is still the file's current
+/// contents, and reopens it otherwise. Without a hash nothing is kept open.
+fn load_and_render<'a>(
+    backend: &'a dyn PdfBackend,
+    open_document: &mut Option<OpenDocument<'a>>,
+    state: &AppState,
+    job: &RenderJob,
+    document_hash: Option<&str>,
+) -> Result<CachedPage, String> {
+    if is_image_document(&job.path) {
+        return image_document(state, &job.path)?.render_page(job.page_number, &job.options);
+    }
+
+    let Some(document_hash) = document_hash else {
+        *open_document = None;
+        return backend.open(&job.path)?.render_page(job.page_number, &job.options);
+    };
+
+    let current = open_document
+        .as_ref()
+        .is_some_and(|(path, hash, _)| *path == job.path && hash == document_hash);
+    if !current {
+        *open_document = None;
+        *open_document = Some((job.path.clone(), document_hash.to_string(), backend.open(&job.path)?));
+    }
+
+    match open_document {
+        Some((_, _, document)) => document.render_page(job.page_number, &job.options),
+        None => Err("Failed to load PDF".to_string()),
+    }
+}
+
+fn worker_loop(queue: Arc<(Mutex<QueueState>, Condvar)>, app: AppHandle) {
+    let backend = Arc::clone(&app.state::<AppState>().pdf_backend);
+
+    // Consecutive jobs usually target the same document, so keep it open.
+    let mut open_document: Option<OpenDocument> = None;
+
+    loop {
+        let job = next_job(&queue);
+        let state = app.state::<AppState>();
+
+        let key = page_cache_key(&state, &job.path, job.page_number, &job.options);
+        let cached = match &key {
+            Ok(key) => state.page_cache.lock().unwrap().get(key),
+            Err(_) => None,
+        };
+
+        let result = match cached {
+            Some(page) => Ok(page),
+            None => {
+                let document_hash = key.as_ref().ok().map(|key| key.document_hash.as_str());
+                let rendered = load_and_render(backend.as_ref(), &mut open_document, &state, &job, document_hash);
+                if let (Ok(page), Ok(key)) = (&rendered, key) {
+                    state.page_cache.lock().unwrap().insert(key, page.clone());
+                }
+                rendered
+            }
+        };
+
+        let path = job.path.to_string_lossy().to_string();
+        if finish_job(&queue, job.request_id) {
+            app.emit(
+                RENDER_CANCELLED_EVENT,
+                PageRenderCancelled {
+                    request_id: job.request_id,
+                    path,
+                    page_number: job.page_number,
+                },
+            )
+            .ok();
+            continue;
+        }
+
+        match result.map(|page| page_info(job.page_number, &page)) {
+            Ok(page) => {
+                app.emit(
+                    RENDER_COMPLETE_EVENT,
+                    PageRenderComplete {
+                        request_id: job.request_id,
+                        path,
+                        page_number: page.page_number,
+                        width: page.width,
+                        height: page.height,
+                        pixel_width: page.pixel_width,
+                        pixel_height: page.pixel_height,
+                        scale: page.scale,
+                        dpi: page.dpi,
+                        image_data: page.image_data,
+                    },
+                )
+                .ok();
+            }
+            Err(error) => emit_failed(&app, &job, error),
+        }
+    }
+}
+
+fn emit_failed(app: &AppHandle, job: &RenderJob, error: String) {
+    app.emit(
+        RENDER_FAILED_EVENT,
+        PageRenderFailed {
+            request_id: job.request_id,
+            path: job.path.to_string_lossy().to_string(),
+            page_number: job.page_number,
+            error,
+        },
+    )
+    .ok();
+}
+
+#[tauri::command]
+pub fn schedule_page_renders(
+    app: AppHandle,
+    path: String,
+    requests: Vec<PageRenderRequest>,
+    width: Option<i32>,
+    options: Option<RenderOptions>,
+    state: State<'_, AppState>,
+) -> Result<Vec<u64>, String> {
+    let pdf_path = PathBuf::from(&path);
+    let mut options = options.unwrap_or_default();
+    options.width = options.width.or(width);
+
+    if !pdf_path.exists() {
+        return Err("File not found".to_string());
+    }
+
+    requests
+        .into_iter()
+        .map(|request| {
+            state
+                .render_scheduler
+                .schedule(&app, pdf_path.clone(), request.page_number, options, request.priority)
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn cancel_page_renders(request_ids: Vec<u64>, state: State<'_, AppState>) -> usize {
+    state.render_scheduler.cancel(&request_ids)
+}
+
+#[tauri::command]
+pub fn cancel_all_page_renders(path: Option<String>, state: State<'_, AppState>) -> usize {
+    state.render_scheduler.cancel_all(path.map(PathBuf::from).as_ref())
+}
+
+#[tauri::command]
+pub fn get_render_queue_status(state: State<'_, AppState>) -> RenderQueueStatus {
+    state.render_scheduler.status()
+}
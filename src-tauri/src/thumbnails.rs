@@ -0,0 +1,231 @@
+use crate::image_document::is_image_document;
+use crate::pdf_backend::Document;
+use crate::{document_hash, encode_jpeg, image_document, jpeg_data_url, with_document, AppState};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const DEFAULT_THUMBNAIL_WIDTH: i32 = 160;
+const MAX_THUMBNAIL_WIDTH: i32 = 512;
+const MAX_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+pub const THUMBNAIL_EVENT: &str = "thumbnail-rendered";
+pub const THUMBNAILS_COMPLETE_EVENT: &str = "thumbnails-complete";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThumbnailBatch {
+    pub document_hash: String,
+    pub pages: Vec<usize>,
+    pub width: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThumbnailRendered {
+    pub document_hash: String,
+    pub page_number: usize,
+    pub width: u32,
+    pub height: u32,
+    pub image_data: String,
+    pub cached: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThumbnailsComplete {
+    pub document_hash: String,
+    pub rendered: usize,
+    pub failed: Vec<usize>,
+}
+
+fn thumbnail_cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))?
+        .join("thumbnails");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    Ok(dir)
+}
+
+fn cache_file(dir: &Path, hash: &str, page_number: usize, width: i32) -> PathBuf {
+    dir.join(format!("{}-p{}-w{}.jpg", hash, page_number, width))
+}
+
+fn read_cached(file: &Path) -> Option<(Vec<u8>, u32, u32)> {
+    let bytes = fs::read(file).ok()?;
+    let (width, height) = image::ImageReader::new(std::io::Cursor::new(&bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+
+    // Bump the modification time so pruning evicts the least recently used files first.
+    if let Ok(handle) = fs::File::options().write(true).open(file) {
+        handle.set_modified(SystemTime::now()).ok();
+    }
+
+    Some((bytes, width, height))
+}
+
+/// Deletes the oldest thumbnails until the cache directory fits in `max_bytes`.
+fn prune_cache(dir: &Path, max_bytes: u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            if !meta.is_file() {
+                return None;
+            }
+            Some((entry.path(), meta.len(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+        })
+        .collect();
+
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if total <= max_bytes {
+        return;
+    }
+
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, len, _) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
+
+fn render_thumbnail(document: &dyn Document, page_number: usize, width: i32) -> Result<(Vec<u8>, u32, u32), String> {
+    let image = document.render_thumbnail(page_number, width)?;
+    Ok((encode_jpeg(&image)?, image.width(), image.height()))
+}
+
+fn run_batch(app: AppHandle, pdf_path: PathBuf, hash: String, pages: Vec<usize>, width: i32) {
+    let cache_dir = thumbnail_cache_dir(&app).ok();
+    let backend = Arc::clone(&app.state::<AppState>().pdf_backend);
+    let mut document = None;
+    let mut rendered = 0;
+    let mut failed = Vec::new();
+
+    for page_number in pages {
+        let file = cache_dir.as_ref().map(|dir| cache_file(dir, &hash, page_number, width));
+
+        let (bytes, w, h, cached) = match file.as_deref().and_then(read_cached) {
+            Some((bytes, w, h)) => (bytes, w, h, true),
+            None => {
+                let result = if is_image_document(&pdf_path) {
+                    image_document(&app.state::<AppState>(), &pdf_path)
+                        .and_then(|document| render_thumbnail(document.as_ref(), page_number, width))
+                } else {
+                    if document.is_none() {
+                        document = backend.open(&pdf_path).ok();
+                    }
+                    match &document {
+                        Some(document) => render_thumbnail(document.as_ref(), page_number, width),
+                        None => Err("Failed to load PDF".to_string()),
+                    }
+                };
+
+                match result {
+                    Ok((bytes, w, h)) => {
+                        if let Some(file) = &file {
+                            fs::write(file, &bytes).ok();
+                        }
+                        (bytes, w, h, false)
+                    }
+                    Err(_) => {
+                        failed.push(page_number);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        rendered += 1;
+        app.emit(
+            THUMBNAIL_EVENT,
+            ThumbnailRendered {
+                document_hash: hash.clone(),
+                page_number,
+                width: w,
+                height: h,
+                image_data: jpeg_data_url(&bytes),
+                cached,
+            },
+        )
+        .ok();
+    }
+
+    if let Some(dir) = &cache_dir {
+        prune_cache(dir, MAX_CACHE_BYTES);
+    }
+
+    app.emit(
+        THUMBNAILS_COMPLETE_EVENT,
+        ThumbnailsComplete {
+            document_hash: hash,
+            rendered,
+            failed,
+        },
+    )
+    .ok();
+}
+
+/// Starts rendering thumbnails for pages `start_page..=end_page` in the background.
+/// Each finished page is delivered as a `thumbnail-rendered` event, followed by a
+/// single `thumbnails-complete` event once the batch is done.
+#[tauri::command]
+pub async fn render_thumbnails(
+    app: AppHandle,
+    path: String,
+    start_page: usize,
+    end_page: usize,
+    width: Option<i32>,
+    state: State<'_, AppState>,
+) -> Result<ThumbnailBatch, String> {
+    let pdf_path = PathBuf::from(&path);
+
+    if !pdf_path.exists() {
+        return Err("File not found".to_string());
+    }
+
+    if start_page == 0 || end_page < start_page {
+        return Err(format!("Invalid page range {}-{}", start_page, end_page));
+    }
+
+    let width = width.unwrap_or(DEFAULT_THUMBNAIL_WIDTH).clamp(16, MAX_THUMBNAIL_WIDTH);
+    let hash = document_hash(&pdf_path, &state)?;
+
+    let open_page_count = {
+        let current = state.current_pdf_path.lock().unwrap();
+        if current.as_deref() == Some(pdf_path.as_path()) {
+            Some(*state.pdf_page_count.lock().unwrap())
+        } else {
+            None
+        }
+    };
+    // Other documents are opened to clamp the range to pages that exist.
+    let page_count = match open_page_count {
+        Some(count) => count,
+        None => with_document(&state, &pdf_path, |document| Ok(document.page_count()))?,
+    };
+    let last_page = end_page.min(page_count);
+    let pages: Vec<usize> = (start_page..=last_page).collect();
+
+    let batch = ThumbnailBatch {
+        document_hash: hash.clone(),
+        pages: pages.clone(),
+        width,
+    };
+
+    tauri::async_runtime::spawn_blocking(move || run_batch(app, pdf_path, hash, pages, width));
+
+    Ok(batch)
+}
@@ -0,0 +1,72 @@
+#![allow(dead_code)]
+
+use annotate_studio_lib::pdf_backend::{default_backend, NativeBackend, PdfBackend, PdfBackendInfo, PdfiumBackend};
+use annotate_studio_lib::AppState;
+use printpdf::{Color, Mm, PdfDocument, Rect, Rgb};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const LETTER: (f32, f32) = (612.0, 792.0);
+pub const A5_LANDSCAPE: (f32, f32) = (595.0, 420.0);
+
+pub fn app_state() -> AppState {
+    let (backend, info) = default_backend();
+    AppState::new(backend, info)
+}
+
+/// Every backend available on this machine. The built-in renderer is always
+/// included, so it is covered even where Pdfium loads and would be the default.
+pub fn backends() -> Vec<Arc<dyn PdfBackend>> {
+    let mut backends: Vec<Arc<dyn PdfBackend>> = Vec::new();
+    if let Ok(pdfium) = PdfiumBackend::new() {
+        backends.push(Arc::new(pdfium));
+    }
+    backends.push(Arc::new(NativeBackend));
+    backends
+}
+
+pub fn app_state_with(backend: &Arc<dyn PdfBackend>) -> AppState {
+    let info = PdfBackendInfo {
+        name: backend.name().to_string(),
+        fallback_reason: None,
+    };
+    AppState::new(Arc::clone(backend), info)
+}
+
+/// A fresh directory under the cargo target dir for one test's files.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn mm(points: f32) -> Mm {
+    Mm(points * 25.4 / 72.0)
+}
+
+/// Writes a PDF with one page per size (in points), each with a filled
+/// rectangle in its lower-left quarter.
+pub fn fixture_pdf(dir: &Path, name: &str, title: &str, page_sizes: &[(f32, f32)]) -> PathBuf {
+    let (first_width, first_height) = page_sizes[0];
+    let (doc, first_page, first_layer) = PdfDocument::new(title, mm(first_width), mm(first_height), "Content");
+
+    for (index, &(width, height)) in page_sizes.iter().enumerate() {
+        let (page, layer) = if index == 0 {
+            (first_page, first_layer)
+        } else {
+            doc.add_page(mm(width), mm(height), "Content")
+        };
+        let layer = doc.get_page(page).get_layer(layer);
+        layer.set_fill_color(Color::Rgb(Rgb::new(0.8, 0.1, 0.1, None)));
+        layer.add_rect(Rect::new(mm(0.0), mm(0.0), mm(width / 2.0), mm(height / 2.0)));
+    }
+
+    let path = dir.join(name);
+    std::fs::write(&path, doc.save_to_bytes().unwrap()).unwrap();
+    path
+}
+
+pub fn assert_close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 0.5, "expected {} to be close to {}", actual, expected);
+}
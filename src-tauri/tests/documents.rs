@@ -0,0 +1,199 @@
+mod common;
+
+use annotate_studio_lib::pdf_backend::{Document, PdfBackend, PdfBackendInfo};
+use annotate_studio_lib::render::RenderOptions;
+use annotate_studio_lib::{AppState, DocumentKind};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::{app_state, app_state_with, assert_close, backends, fixture_pdf, scratch_dir, A5_LANDSCAPE, LETTER};
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use std::path::Path;
+use std::sync::Arc;
+
+fn decode_page_image(image_data: &str) -> DynamicImage {
+    let jpeg = STANDARD.decode(image_data.strip_prefix("data:image/jpeg;base64,").unwrap()).unwrap();
+    image::load_from_memory(&jpeg).unwrap()
+}
+
+#[test]
+fn open_reports_page_count_and_sizes() {
+    let dir = scratch_dir("open_reports_page_count_and_sizes");
+    let pdf = fixture_pdf(&dir, "mixed.pdf", "Mixed", &[LETTER, A5_LANDSCAPE, LETTER]);
+
+    for backend in backends() {
+        let opened = app_state_with(&backend).open_document(&pdf).unwrap();
+
+        assert_eq!(opened.page_count, 3, "{}", backend.name());
+        assert_eq!(opened.document_kind, DocumentKind::Pdf);
+        let sizes: Vec<_> = opened.pages_meta.iter().map(|page| (page.page_number, page.width, page.height)).collect();
+        for ((page_number, width, height), (expected_number, (expected_width, expected_height))) in
+            sizes.into_iter().zip([(1, LETTER), (2, A5_LANDSCAPE), (3, LETTER)])
+        {
+            assert_eq!(page_number, expected_number, "{}", backend.name());
+            assert_close(width, expected_width);
+            assert_close(height, expected_height);
+        }
+    }
+}
+
+#[test]
+fn open_missing_file_fails() {
+    let dir = scratch_dir("open_missing_file_fails");
+    let state = app_state();
+
+    assert_eq!(state.open_document(&dir.join("missing.pdf")).unwrap_err(), "File not found");
+}
+
+#[test]
+fn render_matches_requested_width() {
+    let dir = scratch_dir("render_matches_requested_width");
+    let pdf = fixture_pdf(&dir, "letter.pdf", "Letter", &[LETTER]);
+    let options = RenderOptions {
+        width: Some(800),
+        ..Default::default()
+    };
+
+    for backend in backends() {
+        let page = app_state_with(&backend).render_page(&pdf, 1, &options).unwrap();
+
+        assert_eq!(page.pixel_width, 800, "{}", backend.name());
+        assert_eq!(page.pixel_height, (LETTER.1 * 800.0 / LETTER.0).round() as i32, "{}", backend.name());
+        let image = decode_page_image(&page.image_data);
+        assert_eq!(image.dimensions(), (page.pixel_width as u32, page.pixel_height as u32), "{}", backend.name());
+    }
+}
+
+#[test]
+fn render_applies_zoom_and_device_pixel_ratio() {
+    let dir = scratch_dir("render_applies_zoom_and_device_pixel_ratio");
+    let pdf = fixture_pdf(&dir, "landscape.pdf", "Landscape", &[LETTER, A5_LANDSCAPE]);
+    let options = RenderOptions {
+        zoom: Some(1.5),
+        device_pixel_ratio: Some(2.0),
+        ..Default::default()
+    };
+
+    for backend in backends() {
+        let page = app_state_with(&backend).render_page(&pdf, 2, &options).unwrap();
+
+        assert_eq!(page.page_number, 2, "{}", backend.name());
+        assert_close(page.width, A5_LANDSCAPE.0);
+        assert_eq!(page.pixel_width, (A5_LANDSCAPE.0 * 3.0).round() as i32, "{}", backend.name());
+        assert_eq!(page.pixel_height, (A5_LANDSCAPE.1 * 3.0).round() as i32, "{}", backend.name());
+        assert_close(page.dpi, 216.0);
+    }
+}
+
+#[test]
+fn render_draws_page_content() {
+    let dir = scratch_dir("render_draws_page_content");
+    let pdf = fixture_pdf(&dir, "letter.pdf", "Letter", &[LETTER]);
+    let options = RenderOptions {
+        width: Some(400),
+        ..Default::default()
+    };
+
+    for backend in backends() {
+        let page = app_state_with(&backend).render_page(&pdf, 1, &options).unwrap();
+        let image = decode_page_image(&page.image_data).to_rgb8();
+        let (width, height) = image.dimensions();
+
+        // The fixture fills the lower-left quarter and leaves the rest blank.
+        let filled = image.get_pixel(width / 4, height * 3 / 4);
+        let blank = image.get_pixel(width * 3 / 4, height / 4);
+        assert!(filled[0] > 150 && filled[1] < 100, "{}: expected red, got {:?}", backend.name(), filled);
+        assert!(blank.0.iter().all(|&channel| channel > 230), "{}: expected white, got {:?}", backend.name(), blank);
+    }
+}
+
+#[test]
+fn render_rejects_out_of_range_pages() {
+    let dir = scratch_dir("render_rejects_out_of_range_pages");
+    let pdf = fixture_pdf(&dir, "letter.pdf", "Letter", &[LETTER]);
+
+    for backend in backends() {
+        let state = app_state_with(&backend);
+
+        assert!(state.render_page(&pdf, 0, &RenderOptions::default()).is_err(), "{}", backend.name());
+        assert!(state.render_page(&pdf, 2, &RenderOptions::default()).is_err(), "{}", backend.name());
+    }
+}
+
+#[test]
+fn metadata_reads_document_title() {
+    let dir = scratch_dir("metadata_reads_document_title");
+    let pdf = fixture_pdf(&dir, "titled.pdf", "Quarterly Report", &[LETTER]);
+
+    for backend in backends() {
+        let metadata = app_state_with(&backend).document_metadata(&pdf).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Quarterly Report"), "{}", backend.name());
+    }
+}
+
+#[test]
+fn images_open_as_single_page_documents() {
+    let dir = scratch_dir("images_open_as_single_page_documents");
+    let path = dir.join("scan.png");
+    RgbaImage::from_pixel(300, 200, image::Rgba([10, 20, 30, 255])).save(&path).unwrap();
+    let state = app_state();
+
+    let opened = state.open_document(&path).unwrap();
+
+    assert_eq!(opened.document_kind, DocumentKind::Image);
+    assert_eq!(opened.page_count, 1);
+    let page = state.render_page(&path, 1, &RenderOptions { width: Some(150), ..Default::default() }).unwrap();
+    assert_eq!((page.pixel_width, page.pixel_height), (150, 100));
+}
+
+struct MockBackend;
+
+struct MockDocument;
+
+impl PdfBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn open<'a>(&'a self, _path: &Path) -> Result<Box<dyn Document + 'a>, String> {
+        Ok(Box::new(MockDocument))
+    }
+}
+
+impl Document for MockDocument {
+    fn page_count(&self) -> usize {
+        2
+    }
+
+    fn page_size(&self, page_number: usize) -> Result<(f32, f32), String> {
+        match page_number {
+            1 => Ok((100.0, 200.0)),
+            2 => Ok((300.0, 150.0)),
+            _ => Err(format!("Page {} not found", page_number)),
+        }
+    }
+
+    fn render_bitmap(&self, _page_number: usize, pixel_width: i32, pixel_height: i32) -> Result<DynamicImage, String> {
+        Ok(DynamicImage::new_rgb8(pixel_width as u32, pixel_height as u32))
+    }
+}
+
+#[test]
+fn commands_use_the_configured_backend() {
+    let dir = scratch_dir("commands_use_the_configured_backend");
+    let path = dir.join("anything.pdf");
+    std::fs::write(&path, b"not really a pdf").unwrap();
+    let state = AppState::new(
+        Arc::new(MockBackend),
+        PdfBackendInfo {
+            name: "mock".to_string(),
+            fallback_reason: None,
+        },
+    );
+
+    let opened = state.open_document(&path).unwrap();
+    assert_eq!(opened.page_count, 2);
+    assert_eq!((opened.pages_meta[1].width, opened.pages_meta[1].height), (300.0, 150.0));
+
+    let page = state.render_page(&path, 2, &RenderOptions { zoom: Some(2.0), ..Default::default() }).unwrap();
+    assert_eq!((page.pixel_width, page.pixel_height), (600, 300));
+}
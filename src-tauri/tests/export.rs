@@ -0,0 +1,138 @@
+mod common;
+
+use annotate_studio_lib::export::{self, ExportPdfOptions, ExportPdfPage};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::{app_state_with, assert_close, backends, fixture_pdf, scratch_dir, LETTER};
+use image::{GenericImageView, ImageFormat, RgbImage};
+
+fn png_data_url(width: u32, height: u32) -> String {
+    let mut png = Vec::new();
+    RgbImage::from_pixel(width, height, image::Rgb([40, 120, 200]))
+        .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+        .unwrap();
+    format!("data:image/png;base64,{}", STANDARD.encode(png))
+}
+
+const XMP: &str = "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><dc:title>Field Notes</dc:title></x:xmpmeta>";
+
+/// Adds an XMP packet to an existing PDF, as other editors would have.
+fn add_xmp(path: &std::path::Path) {
+    use printpdf::lopdf::{dictionary, Document, Object, Stream};
+
+    let mut document = Document::load(path).unwrap();
+    let metadata_id = document.add_object(Stream::new(dictionary! { "Type" => "Metadata" }, XMP.as_bytes().to_vec()));
+    let catalog_id = document.trailer.get(b"Root").and_then(Object::as_reference).unwrap();
+    document
+        .get_object_mut(catalog_id)
+        .and_then(Object::as_dict_mut)
+        .unwrap()
+        .set("Metadata", Object::Reference(metadata_id));
+    document.save(path).unwrap();
+}
+
+fn page(width: f32, height: f32) -> ExportPdfPage {
+    ExportPdfPage {
+        image_data: png_data_url(width as u32 / 4, height as u32 / 4),
+        width,
+        height,
+    }
+}
+
+#[test]
+fn canvas_export_writes_the_decoded_image() {
+    let dir = scratch_dir("canvas_export_writes_the_decoded_image");
+    let path = dir.join("canvas.png");
+
+    export::write_canvas(&path, &png_data_url(64, 48)).unwrap();
+
+    assert_eq!(image::open(&path).unwrap().dimensions(), (64, 48));
+}
+
+#[test]
+fn canvas_export_rejects_invalid_data() {
+    let dir = scratch_dir("canvas_export_rejects_invalid_data");
+
+    assert!(export::write_canvas(&dir.join("canvas.png"), "data:image/png;base64,@@@").is_err());
+}
+
+#[test]
+fn pdf_export_writes_one_page_per_canvas() {
+    let dir = scratch_dir("pdf_export_writes_one_page_per_canvas");
+    let pages = [page(816.0, 1056.0), page(1056.0, 816.0), page(400.0, 400.0)];
+
+    for backend in backends() {
+        let path = dir.join(format!("export-{}.pdf", backend.name()));
+
+        export::write_pdf(&path, &pages, ExportPdfOptions::default(), backend.as_ref()).unwrap();
+
+        let opened = app_state_with(&backend).open_document(&path).unwrap();
+        assert_eq!(opened.page_count, 3, "{}", backend.name());
+        // Page sizes are given in CSS pixels, which are 0.75pt.
+        for (meta, page) in opened.pages_meta.iter().zip(&pages) {
+            assert_close(meta.width, page.width * 0.75);
+            assert_close(meta.height, page.height * 0.75);
+        }
+    }
+}
+
+#[test]
+fn pdf_export_requires_pages() {
+    let dir = scratch_dir("pdf_export_requires_pages");
+
+    for backend in backends() {
+        let error = export::write_pdf(&dir.join("empty.pdf"), &[], ExportPdfOptions::default(), backend.as_ref()).unwrap_err();
+
+        assert_eq!(error, "No pages to export", "{}", backend.name());
+    }
+}
+
+#[test]
+fn pdf_export_inherits_source_metadata() {
+    let dir = scratch_dir("pdf_export_inherits_source_metadata");
+    let source = fixture_pdf(&dir, "source.pdf", "Field Notes", &[LETTER]);
+
+    for backend in backends() {
+        let path = dir.join(format!("export-{}.pdf", backend.name()));
+        let options = ExportPdfOptions {
+            source_pdf_path: Some(source.to_string_lossy().to_string()),
+            author: Some("Reviewer".to_string()),
+            ..Default::default()
+        };
+
+        export::write_pdf(&path, &[page(816.0, 1056.0)], options, backend.as_ref()).unwrap();
+
+        let metadata = app_state_with(&backend).document_metadata(&path).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Field Notes"), "{}", backend.name());
+        assert_eq!(metadata.author.as_deref(), Some("Reviewer"), "{}", backend.name());
+    }
+}
+
+#[test]
+fn pdf_export_carries_xmp_only_while_metadata_is_unchanged() {
+    let dir = scratch_dir("pdf_export_carries_xmp_only_while_metadata_is_unchanged");
+    let source = fixture_pdf(&dir, "source.pdf", "Field Notes", &[LETTER]);
+    add_xmp(&source);
+
+    for backend in backends() {
+        let state = app_state_with(&backend);
+        let inherit = ExportPdfOptions {
+            source_pdf_path: Some(source.to_string_lossy().to_string()),
+            title: Some("Field Notes".to_string()),
+            ..Default::default()
+        };
+        let retitle = ExportPdfOptions {
+            title: Some("Reviewed Notes".to_string()),
+            ..inherit.clone()
+        };
+        let kept = dir.join(format!("kept-{}.pdf", backend.name()));
+        let dropped = dir.join(format!("dropped-{}.pdf", backend.name()));
+
+        export::write_pdf(&kept, &[page(816.0, 1056.0)], inherit, backend.as_ref()).unwrap();
+        export::write_pdf(&dropped, &[page(816.0, 1056.0)], retitle, backend.as_ref()).unwrap();
+
+        assert_eq!(state.document_metadata(&kept).unwrap().xmp.as_deref(), Some(XMP), "{}", backend.name());
+        let metadata = state.document_metadata(&dropped).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Reviewed Notes"), "{}", backend.name());
+        assert_eq!(metadata.xmp, None, "{}", backend.name());
+    }
+}
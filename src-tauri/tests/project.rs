@@ -0,0 +1,64 @@
+mod common;
+
+use annotate_studio_lib::project::{self, NotebookSettings};
+use common::scratch_dir;
+
+fn notebook() -> NotebookSettings {
+    NotebookSettings {
+        page_width: 595.0,
+        page_height: 842.0,
+        page_count: 12,
+        template: "dot-grid".to_string(),
+    }
+}
+
+#[test]
+fn project_round_trips() {
+    let dir = scratch_dir("project_round_trips");
+    let path = dir.join("notes.annotate");
+    let strokes = r#"[{"id":"s1","points":[[1,2],[3,4]]}]"#.to_string();
+
+    project::save(&path, Some("/docs/source.pdf".to_string()), strokes.clone(), Some(notebook())).unwrap();
+    let loaded = project::load(&path).unwrap();
+
+    assert_eq!(loaded.version, "1.0.0");
+    assert_eq!(loaded.pdf_path.as_deref(), Some("/docs/source.pdf"));
+    assert_eq!(loaded.strokes, strokes);
+    assert_eq!(loaded.notebook, Some(notebook()));
+}
+
+#[test]
+fn projects_without_notebook_settings_still_load() {
+    let dir = scratch_dir("projects_without_notebook_settings_still_load");
+    let path = dir.join("legacy.annotate");
+    std::fs::write(&path, r#"{"version":"1.0.0","pdf_path":null,"strokes":"[]"}"#).unwrap();
+
+    let loaded = project::load(&path).unwrap();
+
+    assert_eq!(loaded.pdf_path, None);
+    assert_eq!(loaded.notebook, None);
+}
+
+#[test]
+fn invalid_notebook_setup_is_rejected() {
+    let dir = scratch_dir("invalid_notebook_setup_is_rejected");
+    let path = dir.join("broken.annotate");
+    let notebook = NotebookSettings {
+        page_count: 0,
+        ..notebook()
+    };
+
+    let error = project::save(&path, None, "[]".to_string(), Some(notebook)).unwrap_err();
+
+    assert_eq!(error, "Invalid notebook page setup");
+    assert!(!path.exists());
+}
+
+#[test]
+fn corrupt_projects_fail_to_load() {
+    let dir = scratch_dir("corrupt_projects_fail_to_load");
+    let path = dir.join("corrupt.annotate");
+    std::fs::write(&path, "{ not json").unwrap();
+
+    assert!(project::load(&path).unwrap_err().starts_with("Failed to parse project"));
+}
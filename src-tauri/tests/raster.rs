@@ -0,0 +1,164 @@
+use annotate_studio_lib::pdf_backend::raster::{self, FillRule, Matrix, Path};
+use image::{Rgba, RgbaImage};
+
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const RED: [u8; 3] = [255, 0, 0];
+
+fn blank(width: u32, height: u32) -> RgbaImage {
+    RgbaImage::from_pixel(width, height, WHITE)
+}
+
+fn square(x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<(f32, f32)> {
+    vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)]
+}
+
+fn is_red(pixel: &Rgba<u8>) -> bool {
+    pixel.0 == [255, 0, 0, 255]
+}
+
+#[test]
+fn fill_covers_the_inside_and_nothing_else() {
+    let mut image = blank(10, 10);
+
+    raster::fill_polygons(&mut image, &[square(2.0, 2.0, 8.0, 8.0)], FillRule::NonZero, RED, 1.0);
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let inside = (2..8).contains(&x) && (2..8).contains(&y);
+        assert_eq!(is_red(pixel), inside, "pixel ({}, {}) is {:?}", x, y, pixel);
+        if !inside {
+            assert_eq!(*pixel, WHITE, "pixel ({}, {}) was touched", x, y);
+        }
+    }
+}
+
+#[test]
+fn fill_antialiases_partly_covered_pixels() {
+    let mut image = blank(10, 10);
+
+    raster::fill_polygons(&mut image, &[square(2.5, 0.0, 8.0, 10.0)], FillRule::NonZero, RED, 1.0);
+
+    // Half of column 2 is covered, so it ends up halfway between white and red.
+    let edge = image.get_pixel(2, 5);
+    assert_eq!(edge.0[0], 255);
+    assert!((126..=129).contains(&edge.0[1]), "expected half coverage, got {:?}", edge);
+    assert!(is_red(image.get_pixel(3, 5)));
+}
+
+#[test]
+fn fill_rules_differ_on_nested_outlines() {
+    let outlines = [square(0.0, 0.0, 10.0, 10.0), square(3.0, 3.0, 7.0, 7.0)];
+    let mut nonzero = blank(10, 10);
+    let mut even_odd = blank(10, 10);
+
+    raster::fill_polygons(&mut nonzero, &outlines, FillRule::NonZero, RED, 1.0);
+    raster::fill_polygons(&mut even_odd, &outlines, FillRule::EvenOdd, RED, 1.0);
+
+    // Both outlines wind the same way: nonzero fills the middle, even-odd leaves a hole.
+    assert!(is_red(nonzero.get_pixel(5, 5)));
+    assert_eq!(*even_odd.get_pixel(5, 5), WHITE);
+    assert!(is_red(even_odd.get_pixel(1, 1)));
+}
+
+#[test]
+fn fill_clips_to_the_image_and_skips_degenerate_input() {
+    let mut image = blank(10, 10);
+
+    raster::fill_polygons(&mut image, &[square(-20.0, -20.0, 30.0, 30.0)], FillRule::NonZero, RED, 1.0);
+    assert!(image.pixels().all(is_red));
+
+    let mut untouched = blank(10, 10);
+    let flat = vec![(1.0, 5.0), (9.0, 5.0)];
+    let invalid = vec![(f32::NAN, 0.0), (5.0, 5.0), (0.0, 9.0)];
+    raster::fill_polygons(&mut untouched, &[flat, invalid], FillRule::NonZero, RED, 1.0);
+    raster::fill_polygons(&mut untouched, &[square(2.0, 2.0, 8.0, 8.0)], FillRule::NonZero, RED, 0.0);
+    assert!(untouched.pixels().all(|pixel| *pixel == WHITE));
+}
+
+#[test]
+fn fill_blends_with_alpha() {
+    let mut image = blank(4, 4);
+
+    raster::fill_polygons(&mut image, &[square(0.0, 0.0, 4.0, 4.0)], FillRule::NonZero, [0, 0, 0], 0.5);
+
+    assert_eq!(image.get_pixel(1, 1).0, [128, 128, 128, 255]);
+}
+
+#[test]
+fn stroke_follows_the_path_with_round_caps() {
+    let mut image = blank(20, 20);
+    let mut path = Path::default();
+    path.move_to((4.0, 10.0));
+    path.line_to((16.0, 10.0));
+
+    raster::stroke_path(&mut image, &path, 4.0, RED, 1.0);
+
+    assert!(is_red(image.get_pixel(10, 9)) && is_red(image.get_pixel(10, 10)));
+    assert_eq!(*image.get_pixel(10, 4), WHITE);
+    // The round cap reaches past the end point by half the width.
+    assert!(is_red(image.get_pixel(16, 10)));
+    assert_eq!(*image.get_pixel(19, 10), WHITE);
+}
+
+#[test]
+fn closed_stroke_joins_back_to_the_start() {
+    let mut open = blank(20, 20);
+    let mut closed = blank(20, 20);
+    let mut path = Path::default();
+    path.move_to((4.0, 4.0));
+    path.line_to((16.0, 4.0));
+    path.line_to((16.0, 16.0));
+    let mut closed_path = path.clone();
+    closed_path.close();
+
+    raster::stroke_path(&mut open, &path, 2.0, RED, 1.0);
+    raster::stroke_path(&mut closed, &closed_path, 2.0, RED, 1.0);
+
+    // Only the closed path has the diagonal edge back to the start.
+    assert_eq!(*open.get_pixel(10, 10), WHITE);
+    assert!(is_red(closed.get_pixel(10, 10)));
+}
+
+#[test]
+fn curves_are_flattened_through_their_end_point() {
+    let mut path = Path::default();
+    path.move_to((0.0, 0.0));
+    path.curve_to((0.0, 20.0), (20.0, 20.0), (20.0, 0.0));
+
+    let points = &path.subpaths[0];
+    assert!(points.len() > 4);
+    assert_eq!(path.current_point(), Some((20.0, 0.0)));
+    // The midpoint of this symmetric curve is three quarters of the way to the controls.
+    let peak = points.iter().map(|p| p.1).fold(f32::MIN, f32::max);
+    assert!((peak - 15.0).abs() < 0.5, "peak at {}", peak);
+}
+
+#[test]
+fn matrices_compose_and_invert() {
+    let scale = Matrix::new(2.0, 0.0, 0.0, 3.0, 0.0, 0.0);
+    let shift = Matrix::new(1.0, 0.0, 0.0, 1.0, 5.0, -1.0);
+
+    let combined = scale.then(&shift);
+    assert_eq!(combined.apply(1.0, 1.0), (7.0, 2.0));
+
+    let inverse = combined.invert().unwrap();
+    let (x, y) = inverse.apply(7.0, 2.0);
+    assert!((x - 1.0).abs() < 1e-5 && (y - 1.0).abs() < 1e-5);
+    assert!(Matrix::new(1.0, 2.0, 2.0, 4.0, 0.0, 0.0).invert().is_none());
+    assert!((scale.scale() - 6.0f32.sqrt()).abs() < 1e-5);
+}
+
+#[test]
+fn images_map_the_unit_square_with_row_zero_on_top() {
+    let mut source = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 255, 255]));
+    source.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+    let mut image = blank(20, 20);
+    // As in PDF, the square's top edge is at v = 1, here flipped onto the pixel grid.
+    let transform = Matrix::new(10.0, 0.0, 0.0, -10.0, 5.0, 15.0);
+
+    raster::draw_image(&mut image, &source, &transform, 1.0);
+
+    assert!(is_red(image.get_pixel(6, 6)));
+    assert_eq!(image.get_pixel(13, 13).0, [0, 0, 255, 255]);
+    assert_eq!(*image.get_pixel(2, 2), WHITE);
+    assert_eq!(*image.get_pixel(16, 16), WHITE);
+}
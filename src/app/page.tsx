@@ -8,6 +8,7 @@ import { Sidebar } from "@/components/Sidebar"
 import { Inspector } from "@/components/Inspector"
 import { ThemeProvider } from "@/components/ThemeProvider"
 import { useCanvasStore, usePdfStore } from "@/lib/store"
+import type { CurveFit, DashStyle, EraserMode, FontFamily, LineCap, LineJoin, LineMarker, PressureCurvePreset, ShapeSnap, StabilizerMode, TextAlign } from "@/lib/wasm-engine"
 
 interface Page {
   id: number
@@ -21,14 +22,31 @@ interface ToolSettings {
   opacity: number
   borderColor?: string
   backgroundColor?: string
+  pressureCurve?: PressureCurvePreset
+  stabilizer?: StabilizerMode
+  stabilizerStrength?: number
+  curveFit?: CurveFit
+  eraserMode?: EraserMode
+  shapeSnap?: ShapeSnap
+  dash?: DashStyle
+  lineCap?: LineCap
+  lineJoin?: LineJoin
+  startMarker?: LineMarker
+  endMarker?: LineMarker
+  fontFamily?: FontFamily
+  bold?: boolean
+  italic?: boolean
+  underline?: boolean
+  textAlign?: TextAlign
+  lineSpacing?: number
 }
 
 const defaultToolSettings: Record<string, ToolSettings> = {
-  pen: { color: "#18181b", thickness: 3, opacity: 100 },
-  highlighter: { color: "#eab308", thickness: 20, opacity: 40 },
-  eraser: { color: "#ffffff", thickness: 20, opacity: 100 },
-  shapes: { color: "#3b82f6", thickness: 2, opacity: 100, borderColor: "#3b82f6", backgroundColor: "transparent" },
-  text: { color: "#18181b", thickness: 4, opacity: 100 },
+  pen: { color: "#18181b", thickness: 3, opacity: 100, pressureCurve: "linear", stabilizer: "off", stabilizerStrength: 5, curveFit: "none", shapeSnap: "hold" },
+  highlighter: { color: "#eab308", thickness: 20, opacity: 40, pressureCurve: "off", stabilizer: "off", stabilizerStrength: 5, curveFit: "none" },
+  eraser: { color: "#ffffff", thickness: 20, opacity: 100, eraserMode: "partial" },
+  shapes: { color: "#3b82f6", thickness: 2, opacity: 100, borderColor: "#3b82f6", backgroundColor: "transparent", dash: "solid", lineCap: "round", lineJoin: "round", startMarker: "none", endMarker: "none" },
+  text: { color: "#18181b", thickness: 4, opacity: 100, backgroundColor: "transparent", borderColor: "transparent", fontFamily: "sans", bold: false, italic: false, underline: false, textAlign: "left", lineSpacing: 1.2 },
   fill: { color: "#ef4444", thickness: 1, opacity: 100 },
 }
 
@@ -78,9 +96,13 @@ export default function Home() {
     }
   }, [pages, currentPage])
 
-  const handleNewFile = useCallback(() => {
+  const handleNewFile = useCallback((pageCount = 1) => {
     clearPdf()
-    setPages([{ id: 1, name: "Page 1", hasAnnotations: false }])
+    setPages(Array.from({ length: Math.max(1, pageCount) }, (_, i) => ({
+      id: i + 1,
+      name: `Page ${i + 1}`,
+      hasAnnotations: false,
+    })))
     setCurrentPage(1)
   }, [clearPdf])
 
@@ -36,11 +36,17 @@ import {
   Heart,
   Pi,
   PaintBucket,
+  Spline,
+  Waypoints,
+  Cloud,
+  Cloudy,
+  StickyNote,
+  MessageSquare,
 } from "lucide-react"
 import { ScrollArea } from "@/components/ui/scroll-area"
 
 export type Tool = "select" | "pan" | "pen" | "highlighter" | "eraser" | "text" | "shapes" | "math" | "fill"
-export type ShapeType = "rectangle" | "circle" | "triangle" | "line" | "arrow" | "star" | "diamond" | "heart"
+export type ShapeType = "rectangle" | "circle" | "triangle" | "line" | "arrow" | "star" | "diamond" | "heart" | "polyline" | "polygon" | "bezier" | "cloud" | "cloud-polygon" | "callout" | "bubble"
 
 const mathSymbols = {
   basic: [
@@ -147,6 +153,13 @@ const shapes = [
   { id: "star" as ShapeType, icon: Star, label: "Star", shortcut: "" },
   { id: "diamond" as ShapeType, icon: Diamond, label: "Diamond", shortcut: "" },
   { id: "heart" as ShapeType, icon: Heart, label: "Heart", shortcut: "" },
+  { id: "polyline" as ShapeType, icon: Waypoints, label: "Polyline", shortcut: "" },
+  { id: "polygon" as ShapeType, icon: Pentagon, label: "Polygon", shortcut: "" },
+  { id: "bezier" as ShapeType, icon: Spline, label: "Curve", shortcut: "" },
+  { id: "cloud" as ShapeType, icon: Cloud, label: "Revision Cloud", shortcut: "" },
+  { id: "cloud-polygon" as ShapeType, icon: Cloudy, label: "Polygon Cloud", shortcut: "" },
+  { id: "callout" as ShapeType, icon: StickyNote, label: "Callout", shortcut: "" },
+  { id: "bubble" as ShapeType, icon: MessageSquare, label: "Speech Bubble", shortcut: "" },
 ]
 
 interface DockProps {
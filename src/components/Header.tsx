@@ -1,17 +1,24 @@
 "use client"
 
-import { useState, memo, useCallback, useEffect } from "react"
+import { useState, memo, useCallback, useEffect, useRef } from "react"
 import { Download, Keyboard, Info, FilePlus, FolderOpen, Save, Undo2, Redo2, Scissors, Copy, ClipboardPaste, ZoomIn, ZoomOut, Maximize, Expand, GithubIcon, ExternalLink, Loader2, RefreshCw, Bell, Sun, Moon } from "lucide-react"
 import { Button } from "@/components/ui/button"
-import { useCanvasStore, usePdfStore } from "@/lib/store"
-import { openPdfDialog, openPdf, saveProjectDialog, saveProject, exportDialog, exportCanvas, exportToPdf } from "@/lib/tauri"
+import { useCanvasStore, useNotebookStore, usePdfStore, useSettingsStore, PAGE_SIZE_PRESETS, PAPER_TEMPLATES } from "@/lib/store"
+import type { PaperTemplate } from "@/lib/store"
+import { openPdfDialog, openPdf, saveProjectDialog, saveProject, exportDialog, exportCanvas, exportToPdf, getPdfMetadata } from "@/lib/tauri"
+import type { ExportPdfOptions, PdfDocumentMetadata } from "@/lib/tauri"
 import {
   Menubar,
   MenubarContent,
   MenubarItem,
   MenubarMenu,
+  MenubarRadioGroup,
+  MenubarRadioItem,
   MenubarSeparator,
   MenubarShortcut,
+  MenubarSub,
+  MenubarSubContent,
+  MenubarSubTrigger,
   MenubarTrigger,
 } from "@/components/ui/menubar"
 import {
@@ -22,11 +29,12 @@ import {
 } from "@/components/ui/dialog"
 import { ScrollArea } from "@/components/ui/scroll-area"
 import useThemeToggle from "@/hooks/useThemeToggle"
+import * as wasmEngine from "@/lib/wasm-engine"
 
 const shortcuts = [
   {
     category: "File", items: [
-      { key: "Ctrl+N", action: "New File" },
+      { key: "Ctrl+N", action: "New Notebook" },
       { key: "Ctrl+O", action: "Open PDF" },
       { key: "Ctrl+S", action: "Save" },
       { key: "Ctrl+Shift+S", action: "Save As" },
@@ -53,6 +61,8 @@ const shortcuts = [
       { key: "M", action: "Highlighter" },
       { key: "E", action: "Eraser" },
       { key: "T", action: "Text Tool" },
+      { key: "Double Click", action: "Edit Text Box" },
+      { key: "Ctrl+Enter", action: "Finish Text" },
     ]
   },
   {
@@ -61,6 +71,11 @@ const shortcuts = [
       { key: "C", action: "Circle / Ellipse" },
       { key: "L", action: "Line" },
       { key: "A", action: "Arrow" },
+      { key: "Click", action: "Add Path Vertex" },
+      { key: "Drag (Curve)", action: "Pull Out Handles" },
+      { key: "Double Click / Enter", action: "Finish Path" },
+      { key: "Esc", action: "Cancel Path" },
+      { key: "Hold Pen Still", action: "Snap Sketch to Shape" },
     ]
   },
   {
@@ -75,14 +90,21 @@ const shortcuts = [
   {
     category: "Selection", items: [
       { key: "Click + Drag", action: "Rubber Band Select" },
+      { key: "Alt+Drag", action: "Lasso Select" },
+      { key: "Ctrl+Drag", action: "Select Only Fully Enclosed" },
       { key: "Shift+Click", action: "Add to Selection" },
-      { key: "Corner Drag", action: "Resize Selected" },
+      { key: "Corner/Edge Drag", action: "Resize Selected" },
+      { key: "Knob Drag", action: "Rotate Selected" },
+      { key: "Vertex Drag", action: "Edit Path Point or Callout Tail" },
+      { key: "Shift+Resize", action: "Keep Aspect Ratio" },
+      { key: "Shift+Rotate", action: "Snap Rotation to 15°" },
+      { key: "Esc (while dragging)", action: "Cancel Transform" },
     ]
   },
 ]
 
 interface HeaderProps {
-  onNewFile?: () => void
+  onNewFile?: (pageCount?: number) => void
   onZoomIn?: () => void
   onZoomOut?: () => void
   onResetZoom?: () => void
@@ -92,10 +114,50 @@ interface HeaderProps {
   canvasRef?: React.RefObject<HTMLCanvasElement | null>
 }
 
+// The viewer culls strokes outside the visible area, so draw a full frame first.
+async function captureCanvas(canvas: HTMLCanvasElement): Promise<string> {
+  await wasmEngine.renderFullFrame()
+  return canvas.toDataURL("image/png")
+}
+
 const APP_VERSION = "1.0.0"
 const GITHUB_REPO = "annotate-studio/annotate-studio"
 const CHECK_INTERVAL = 10 * 60 * 1000
 
+interface ExportMetadataForm {
+  title: string
+  author: string
+  subject: string
+  keywords: string
+  source: PdfDocumentMetadata | null
+}
+
+const EXPORT_METADATA_FIELDS = [
+  { key: "title", label: "Title" },
+  { key: "author", label: "Author" },
+  { key: "subject", label: "Subject" },
+  { key: "keywords", label: "Keywords" },
+] as const
+
+function splitKeywords(keywords: string) {
+  return keywords.split(/[,;]/).map((k) => k.trim()).filter(Boolean)
+}
+
+// Blank fields inherit from the source PDF, so one the user cleared is sent
+// as empty to keep the source's value out of the export.
+function exportOptionsFrom(form: ExportMetadataForm, sourcePdfPath: string | null): ExportPdfOptions {
+  const field = (value: string, source: string | null | undefined) => value.trim() || (source ? "" : undefined)
+  const keywords = splitKeywords(form.keywords)
+
+  return {
+    title: field(form.title, form.source?.title),
+    author: field(form.author, form.source?.author),
+    subject: field(form.subject, form.source?.subject),
+    keywords: keywords.length > 0 || form.source?.keywords ? keywords : undefined,
+    source_pdf_path: sourcePdfPath,
+  }
+}
+
 interface GitHubRelease {
   tag_name: string
   html_url: string
@@ -111,6 +173,15 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
   const [isSaving, setIsSaving] = useState(false)
   const [updateAvailable, setUpdateAvailable] = useState<GitHubRelease | null>(null)
   const [isCheckingUpdate, setIsCheckingUpdate] = useState(false)
+  const [showNewNotebook, setShowNewNotebook] = useState(false)
+  const [newPageSize, setNewPageSize] = useState("A4")
+  const [customWidthMm, setCustomWidthMm] = useState(210)
+  const [customHeightMm, setCustomHeightMm] = useState(297)
+  const [newPageCount, setNewPageCount] = useState(1)
+  const [newTemplate, setNewTemplate] = useState<PaperTemplate>("ruled")
+  const [exportMetadata, setExportMetadata] = useState<ExportMetadataForm | null>(null)
+  const [lastExportOptions, setLastExportOptions] = useState<ExportPdfOptions | null>(null)
+  const exportMetadataResolveRef = useRef<((options: ExportPdfOptions | null) => void) | null>(null)
 
   const checkForUpdates = useCallback(async (showNoUpdate = false) => {
     try {
@@ -159,6 +230,31 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
   const setError = usePdfStore(s => s.setError)
   const isLoading = usePdfStore(s => s.isLoading)
   const clearPdf = usePdfStore(s => s.clearPdf)
+  const pageRenderMode = useSettingsStore(s => s.pageRenderMode)
+  const setPageRenderMode = useSettingsStore(s => s.setPageRenderMode)
+  const notebook = useNotebookStore()
+
+  const handleCreateNotebook = useCallback(() => {
+    const preset = PAGE_SIZE_PRESETS.find((p) => p.name === newPageSize)
+    const mmToPt = (mm: number) => Math.round((Math.max(mm, 10) * 72) / 25.4)
+    const pageCount = Math.min(Math.max(Math.round(newPageCount) || 1, 1), 500)
+
+    notebook.setNotebook({
+      page_width: preset ? preset.width : mmToPt(customWidthMm),
+      page_height: preset ? preset.height : mmToPt(customHeightMm),
+      page_count: pageCount,
+      template: newTemplate,
+    })
+    setCurrentProjectPath(null)
+    onNewFile?.(pageCount)
+    setShowNewNotebook(false)
+  }, [newPageSize, customWidthMm, customHeightMm, newPageCount, newTemplate, notebook, onNewFile])
+
+  const notebookSettings = useCallback(() => {
+    if (pdfPath) return null
+    const { page_width, page_height, page_count, template } = notebook
+    return { page_width, page_height, page_count, template }
+  }, [pdfPath, notebook])
 
   const handleOpenPdf = useCallback(async () => {
     try {
@@ -201,6 +297,38 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
     }
   }, [])
 
+  useEffect(() => {
+    setLastExportOptions(null)
+  }, [pdfPath])
+
+  // Opens the metadata dialog prefilled from the source PDF and resolves with
+  // the chosen options, or null when the user cancels.
+  const askExportMetadata = useCallback(async (): Promise<ExportPdfOptions | null> => {
+    const source = pdfPath ? await getPdfMetadata(pdfPath).catch(() => null) : null
+    exportMetadataResolveRef.current?.(null)
+    setExportMetadata({
+      title: source?.title ?? "",
+      author: source?.author ?? "",
+      subject: source?.subject ?? "",
+      keywords: source?.keywords ? splitKeywords(source.keywords).join(", ") : "",
+      source,
+    })
+    return new Promise((resolve) => {
+      exportMetadataResolveRef.current = resolve
+    })
+  }, [pdfPath])
+
+  const closeExportMetadata = useCallback((confirmed: boolean) => {
+    const resolve = exportMetadataResolveRef.current
+    exportMetadataResolveRef.current = null
+    setExportMetadata(null)
+    if (!resolve) return
+
+    const options = confirmed && exportMetadata ? exportOptionsFrom(exportMetadata, pdfPath) : null
+    if (options) setLastExportOptions(options)
+    resolve(options)
+  }, [exportMetadata, pdfPath])
+
   const handleSave = useCallback(async () => {
     if (!currentProjectPath) {
       handleSaveAs()
@@ -212,21 +340,23 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
 
       if (currentProjectPath.toLowerCase().endsWith(".pdf")) {
         if (!canvasRef?.current) return
+        const options = lastExportOptions ?? await askExportMetadata()
+        if (!options) return
         const canvas = canvasRef.current
-        const imageData = canvas.toDataURL("image/png")
-        const width = pagesMeta.length > 0 ? pagesMeta[0].width : canvas.width
-        const height = pagesMeta.length > 0 ? pagesMeta[0].height : canvas.height
-        await exportToPdf(currentProjectPath, [{ image_data: imageData, width, height }])
+        const imageData = await captureCanvas(canvas)
+        const width = pagesMeta.length > 0 ? pagesMeta[0].width : canvas.clientWidth
+        const height = pagesMeta.length > 0 ? pagesMeta[0].height : canvas.clientHeight
+        await exportToPdf(currentProjectPath, [{ image_data: imageData, width, height }], options)
       } else {
         const strokesJson = JSON.stringify(strokes)
-        await saveProject(currentProjectPath, pdfPath, strokesJson)
+        await saveProject(currentProjectPath, pdfPath, strokesJson, notebookSettings())
       }
     } catch (err) {
       console.error("Save failed:", err)
     } finally {
       setIsSaving(false)
     }
-  }, [currentProjectPath, strokes, pdfPath, canvasRef, pagesMeta])
+  }, [currentProjectPath, strokes, pdfPath, canvasRef, pagesMeta, notebookSettings, lastExportOptions, askExportMetadata])
 
   const handleSaveAs = useCallback(async () => {
     try {
@@ -239,14 +369,16 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
 
       if (filePath.toLowerCase().endsWith(".pdf")) {
         if (!canvasRef?.current) return
+        const options = await askExportMetadata()
+        if (!options) return
         const canvas = canvasRef.current
-        const imageData = canvas.toDataURL("image/png")
-        const width = pagesMeta.length > 0 ? pagesMeta[0].width : canvas.width
-        const height = pagesMeta.length > 0 ? pagesMeta[0].height : canvas.height
-        await exportToPdf(filePath, [{ image_data: imageData, width, height }])
+        const imageData = await captureCanvas(canvas)
+        const width = pagesMeta.length > 0 ? pagesMeta[0].width : canvas.clientWidth
+        const height = pagesMeta.length > 0 ? pagesMeta[0].height : canvas.clientHeight
+        await exportToPdf(filePath, [{ image_data: imageData, width, height }], options)
       } else {
         const strokesJson = JSON.stringify(strokes)
-        await saveProject(filePath, pdfPath, strokesJson)
+        await saveProject(filePath, pdfPath, strokesJson, notebookSettings())
       }
       setCurrentProjectPath(filePath)
     } catch (err) {
@@ -254,7 +386,7 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
     } finally {
       setIsSaving(false)
     }
-  }, [strokes, pdfPath, canvasRef, pagesMeta])
+  }, [strokes, pdfPath, canvasRef, pagesMeta, notebookSettings, askExportMetadata])
 
   const handleExport = useCallback(async () => {
     if (!canvasRef?.current) return
@@ -266,24 +398,26 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
       const isPdf = filePath.toLowerCase().endsWith(".pdf")
 
       if (isPdf) {
+        const options = await askExportMetadata()
+        if (!options || !canvasRef.current) return
         const canvas = canvasRef.current
-        const imageData = canvas.toDataURL("image/png")
-        const width = pagesMeta.length > 0 ? pagesMeta[0].width : canvas.width
-        const height = pagesMeta.length > 0 ? pagesMeta[0].height : canvas.height
+        const imageData = await captureCanvas(canvas)
+        const width = pagesMeta.length > 0 ? pagesMeta[0].width : canvas.clientWidth
+        const height = pagesMeta.length > 0 ? pagesMeta[0].height : canvas.clientHeight
 
         await exportToPdf(filePath, [{
           image_data: imageData,
           width,
           height,
-        }])
+        }], options)
       } else {
-        const imageData = canvasRef.current.toDataURL("image/png")
+        const imageData = await captureCanvas(canvasRef.current)
         await exportCanvas(filePath, imageData)
       }
     } catch (err) {
       console.error("Export failed:", err)
     }
-  }, [canvasRef, pagesMeta])
+  }, [canvasRef, pagesMeta, askExportMetadata])
 
   const { theme, toggle: ToggleTheme } = useThemeToggle()
 
@@ -302,9 +436,9 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
                 File
               </MenubarTrigger>
               <MenubarContent className="min-w-[180px] rounded-lg border-border/50 bg-background/95 backdrop-blur-xl shadow-lg p-1">
-                <MenubarItem className="gap-2 text-xs rounded-md h-7 px-2" onClick={onNewFile}>
+                <MenubarItem className="gap-2 text-xs rounded-md h-7 px-2" onClick={() => setShowNewNotebook(true)}>
                   <FilePlus className="h-3.5 w-3.5 text-muted-foreground" />
-                  New <MenubarShortcut className="text-[10px] opacity-60">Ctrl+N</MenubarShortcut>
+                  New Notebook <MenubarShortcut className="text-[10px] opacity-60">Ctrl+N</MenubarShortcut>
                 </MenubarItem>
                 <MenubarItem className="gap-2 text-xs rounded-md h-7 px-2" onClick={handleOpenPdf} disabled={isLoading}>
                   {isLoading ? <Loader2 className="h-3.5 w-3.5 animate-spin" /> : <FolderOpen className="h-3.5 w-3.5 text-muted-foreground" />}
@@ -374,6 +508,37 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
                   Reset Zoom <MenubarShortcut className="text-[10px] opacity-60">Ctrl+0</MenubarShortcut>
                 </MenubarItem>
                 <MenubarSeparator className="my-1 bg-border/30" />
+                <MenubarSub>
+                  <MenubarSubTrigger className="gap-2 text-xs rounded-md h-7 px-2">Page Colors</MenubarSubTrigger>
+                  <MenubarSubContent className="min-w-[160px] rounded-lg border-border/50 bg-background/95 backdrop-blur-xl shadow-lg p-1">
+                    <MenubarRadioGroup
+                      value={pageRenderMode}
+                      onValueChange={(value) => setPageRenderMode(value as typeof pageRenderMode)}
+                    >
+                      <MenubarRadioItem className="text-xs rounded-md h-7" value="normal">Normal</MenubarRadioItem>
+                      <MenubarRadioItem className="text-xs rounded-md h-7" value="inverted">Dark (Inverted)</MenubarRadioItem>
+                      <MenubarRadioItem className="text-xs rounded-md h-7" value="grayscale">Grayscale</MenubarRadioItem>
+                      <MenubarRadioItem className="text-xs rounded-md h-7" value="sepia">Sepia</MenubarRadioItem>
+                      <MenubarRadioItem className="text-xs rounded-md h-7" value="high-contrast">High Contrast</MenubarRadioItem>
+                    </MenubarRadioGroup>
+                  </MenubarSubContent>
+                </MenubarSub>
+                <MenubarSub>
+                  <MenubarSubTrigger className="gap-2 text-xs rounded-md h-7 px-2" disabled={!!pdfPath}>Paper</MenubarSubTrigger>
+                  <MenubarSubContent className="min-w-[160px] rounded-lg border-border/50 bg-background/95 backdrop-blur-xl shadow-lg p-1">
+                    <MenubarRadioGroup
+                      value={notebook.template}
+                      onValueChange={(value) => notebook.setTemplate(value as PaperTemplate)}
+                    >
+                      {PAPER_TEMPLATES.map((template) => (
+                        <MenubarRadioItem key={template.value} className="text-xs rounded-md h-7" value={template.value}>
+                          {template.label}
+                        </MenubarRadioItem>
+                      ))}
+                    </MenubarRadioGroup>
+                  </MenubarSubContent>
+                </MenubarSub>
+                <MenubarSeparator className="my-1 bg-border/30" />
                 <MenubarItem className="gap-2 text-xs rounded-md h-7 px-2" onClick={handleFullScreen}>
                   <Expand className="h-3.5 w-3.5 text-muted-foreground" />
                   Full Screen <MenubarShortcut className="text-[10px] opacity-60">F11</MenubarShortcut>
@@ -442,6 +607,119 @@ function HeaderComponent({ onNewFile, onZoomIn, onZoomOut, onResetZoom, onFullSc
         </div>
       </header>
 
+      <Dialog open={showNewNotebook} onOpenChange={setShowNewNotebook}>
+        <DialogContent className="max-w-sm">
+          <DialogHeader>
+            <DialogTitle className="flex items-center gap-2">
+              <FilePlus className="h-5 w-5 text-violet-500" />
+              New Notebook
+            </DialogTitle>
+          </DialogHeader>
+          <div className="flex flex-col gap-3 py-2 text-sm">
+            <label className="flex items-center justify-between gap-3">
+              <span className="text-muted-foreground">Page size</span>
+              <select
+                className="h-8 w-40 rounded-md border bg-background px-2 text-sm"
+                value={newPageSize}
+                onChange={(e) => setNewPageSize(e.target.value)}
+              >
+                {PAGE_SIZE_PRESETS.map((preset) => (
+                  <option key={preset.name} value={preset.name}>{preset.name}</option>
+                ))}
+                <option value="custom">Custom</option>
+              </select>
+            </label>
+            {newPageSize === "custom" && (
+              <div className="flex items-center justify-between gap-3">
+                <span className="text-muted-foreground">Size (mm)</span>
+                <div className="flex w-40 items-center gap-1">
+                  <input
+                    type="number"
+                    min={10}
+                    className="h-8 w-full rounded-md border bg-background px-2 text-sm"
+                    value={customWidthMm}
+                    onChange={(e) => setCustomWidthMm(Number(e.target.value))}
+                  />
+                  <span className="text-muted-foreground">×</span>
+                  <input
+                    type="number"
+                    min={10}
+                    className="h-8 w-full rounded-md border bg-background px-2 text-sm"
+                    value={customHeightMm}
+                    onChange={(e) => setCustomHeightMm(Number(e.target.value))}
+                  />
+                </div>
+              </div>
+            )}
+            <label className="flex items-center justify-between gap-3">
+              <span className="text-muted-foreground">Pages</span>
+              <input
+                type="number"
+                min={1}
+                max={500}
+                className="h-8 w-40 rounded-md border bg-background px-2 text-sm"
+                value={newPageCount}
+                onChange={(e) => setNewPageCount(Number(e.target.value))}
+              />
+            </label>
+            <label className="flex items-center justify-between gap-3">
+              <span className="text-muted-foreground">Paper</span>
+              <select
+                className="h-8 w-40 rounded-md border bg-background px-2 text-sm"
+                value={newTemplate}
+                onChange={(e) => setNewTemplate(e.target.value as PaperTemplate)}
+              >
+                {PAPER_TEMPLATES.map((template) => (
+                  <option key={template.value} value={template.value}>{template.label}</option>
+                ))}
+              </select>
+            </label>
+            <div className="flex gap-2 pt-2">
+              <Button variant="outline" className="flex-1" onClick={() => setShowNewNotebook(false)}>
+                Cancel
+              </Button>
+              <Button className="flex-1" onClick={handleCreateNotebook}>
+                Create
+              </Button>
+            </div>
+          </div>
+        </DialogContent>
+      </Dialog>
+
+      <Dialog open={exportMetadata !== null} onOpenChange={(open) => !open && closeExportMetadata(false)}>
+        <DialogContent className="max-w-sm">
+          <DialogHeader>
+            <DialogTitle className="flex items-center gap-2">
+              <Download className="h-5 w-5 text-violet-500" />
+              PDF Metadata
+            </DialogTitle>
+          </DialogHeader>
+          {exportMetadata && (
+            <div className="flex flex-col gap-3 py-2 text-sm">
+              {EXPORT_METADATA_FIELDS.map(({ key, label }) => (
+                <label key={key} className="flex items-center justify-between gap-3">
+                  <span className="text-muted-foreground">{label}</span>
+                  <input
+                    className="h-8 w-52 rounded-md border bg-background px-2 text-sm"
+                    placeholder={key === "keywords" ? "Comma separated" : undefined}
+                    value={exportMetadata[key]}
+                    onChange={(e) => setExportMetadata({ ...exportMetadata, [key]: e.target.value })}
+                  />
+                </label>
+              ))}
+              <div className="flex gap-2 pt-2">
+                <Button variant="outline" className="flex-1" onClick={() => closeExportMetadata(false)}>
+                  Cancel
+                </Button>
+                <Button className="flex-1" onClick={() => closeExportMetadata(true)}>
+                  Export
+                </Button>
+              </div>
+            </div>
+          )}
+        </DialogContent>
+      </Dialog>
+
       <Dialog open={showShortcuts} onOpenChange={setShowShortcuts}>
         <DialogContent className="max-h-[80vh] max-w-md overflow-hidden">
           <DialogHeader>
@@ -6,6 +6,7 @@ import { Button } from "@/components/ui/button"
 import { Slider } from "@/components/ui/slider"
 import { ScrollArea } from "@/components/ui/scroll-area"
 import { useColorStore } from "@/lib/store"
+import type { CurveFit, DashStyle, EraserMode, FontFamily, LineCap, LineJoin, LineMarker, PressureCurvePreset, ShapeSnap, StabilizerMode, TextAlign } from "@/lib/wasm-engine"
 import {
   Palette,
   Plus,
@@ -13,6 +14,12 @@ import {
   PanelRightClose,
   PanelRight,
   Pipette,
+  Bold,
+  Italic,
+  Underline,
+  AlignLeft,
+  AlignCenter,
+  AlignRight,
 } from "lucide-react"
 import {
   Tooltip,
@@ -45,8 +52,98 @@ interface ToolSettings {
   opacity: number
   borderColor?: string
   backgroundColor?: string
+  pressureCurve?: PressureCurvePreset
+  stabilizer?: StabilizerMode
+  stabilizerStrength?: number
+  curveFit?: CurveFit
+  eraserMode?: EraserMode
+  shapeSnap?: ShapeSnap
+  dash?: DashStyle
+  lineCap?: LineCap
+  lineJoin?: LineJoin
+  startMarker?: LineMarker
+  endMarker?: LineMarker
+  fontFamily?: FontFamily
+  bold?: boolean
+  italic?: boolean
+  underline?: boolean
+  textAlign?: TextAlign
+  lineSpacing?: number
 }
 
+const pressureCurveOptions: { value: PressureCurvePreset; label: string }[] = [
+  { value: "off", label: "Off" },
+  { value: "soft", label: "Soft" },
+  { value: "linear", label: "Linear" },
+  { value: "firm", label: "Firm" },
+]
+
+const stabilizerOptions: { value: StabilizerMode; label: string }[] = [
+  { value: "off", label: "Off" },
+  { value: "moving_average", label: "Average" },
+  { value: "one_euro", label: "Adaptive" },
+  { value: "lazy_brush", label: "String" },
+]
+
+const dashOptions: { value: DashStyle; label: string }[] = [
+  { value: "solid", label: "Solid" },
+  { value: "dashed", label: "Dashed" },
+  { value: "dotted", label: "Dotted" },
+  { value: "dash_dot", label: "Dash-Dot" },
+]
+
+const lineCapOptions: { value: LineCap; label: string }[] = [
+  { value: "round", label: "Round" },
+  { value: "butt", label: "Flat" },
+  { value: "square", label: "Square" },
+]
+
+const lineJoinOptions: { value: LineJoin; label: string }[] = [
+  { value: "round", label: "Round" },
+  { value: "miter", label: "Sharp" },
+  { value: "bevel", label: "Bevel" },
+]
+
+// Drawn at the ends of lines, arrows, polylines, curves and callout leaders.
+const markerOptions: { value: LineMarker; label: string }[] = [
+  { value: "none", label: "None" },
+  { value: "open_arrow", label: "Arrow" },
+  { value: "triangle", label: "Triangle" },
+  { value: "circle", label: "Circle" },
+  { value: "square", label: "Square" },
+  { value: "bar", label: "Bar" },
+]
+
+const eraserModeOptions: { value: EraserMode; label: string }[] = [
+  { value: "partial", label: "Partial" },
+  { value: "stroke", label: "Whole Stroke" },
+]
+
+const curveFitOptions: { value: CurveFit; label: string }[] = [
+  { value: "none", label: "None" },
+  { value: "catmull_rom", label: "Spline" },
+  { value: "chaikin", label: "Rounded" },
+]
+
+const shapeSnapOptions: { value: ShapeSnap; label: string }[] = [
+  { value: "off", label: "Off" },
+  { value: "release", label: "On Lift" },
+  { value: "hold", label: "Hold" },
+]
+
+const fontFamilyOptions: { value: FontFamily; label: string; css: string }[] = [
+  { value: "sans", label: "Sans", css: "Inter, system-ui, sans-serif" },
+  { value: "serif", label: "Serif", css: "Georgia, 'Times New Roman', serif" },
+  { value: "mono", label: "Mono", css: "'JetBrains Mono', Menlo, Consolas, monospace" },
+  { value: "handwriting", label: "Hand", css: "'Comic Sans MS', 'Segoe Print', cursive" },
+]
+
+const textAlignOptions: { value: TextAlign; icon: typeof AlignLeft }[] = [
+  { value: "left", icon: AlignLeft },
+  { value: "center", icon: AlignCenter },
+  { value: "right", icon: AlignRight },
+]
+
 interface InspectorProps {
   activeTool: string
   currentSettings: ToolSettings
@@ -77,9 +174,11 @@ export function Inspector({
   const { customColors, addCustomColor, removeCustomColor, recentColors, addRecentColor } = useColorStore()
 
   const showColor = activeTool !== "eraser" && activeTool !== "select" && activeTool !== "pan" && activeTool !== "shapes"
-  const showShapeColors = activeTool === "shapes"
+  const showShapeColors = activeTool === "shapes" || activeTool === "text"
   const showThickness = activeTool === "pen" || activeTool === "highlighter" || activeTool === "eraser" || activeTool === "shapes" || activeTool === "text"
   const showOpacity = activeTool === "pen" || activeTool === "highlighter" || activeTool === "shapes" || activeTool === "text"
+  const showPressure = activeTool === "pen" || activeTool === "highlighter"
+  const showLineStyle = activeTool === "shapes"
 
   const handleColorSelect = (color: string) => {
     onSettingsChange({ color })
@@ -88,7 +187,7 @@ export function Inspector({
 
   const handleBorderColorSelect = (color: string) => {
     onSettingsChange({ borderColor: color })
-    addRecentColor(color)
+    if (color !== "transparent") addRecentColor(color)
   }
 
   const handleBgColorSelect = (color: string) => {
@@ -301,7 +400,22 @@ export function Inspector({
                   Border Color
                 </h3>
                 <div className="grid grid-cols-5 gap-1.5">
-                  {presetColors.map((item) => (
+                  {activeTool === "text" && (
+                    <button
+                      onClick={() => handleBorderColorSelect("transparent")}
+                      className={cn(
+                        "h-7 w-7 rounded-md border transition-all duration-200 flex items-center justify-center",
+                        "hover:scale-110",
+                        currentSettings.borderColor === "transparent"
+                          ? "ring-2 ring-violet-500 ring-offset-1 ring-offset-background scale-110"
+                          : "border-border/50"
+                      )}
+                      style={{ background: "repeating-conic-gradient(#d4d4d8 0% 25%, transparent 0% 50%) 50% / 8px 8px" }}
+                    >
+                      <X className="h-3 w-3 text-muted-foreground" />
+                    </button>
+                  )}
+                  {(activeTool === "text" ? presetColors.slice(0, 9) : presetColors).map((item) => (
                     <button
                       key={`border-${item.name}`}
                       onClick={() => handleBorderColorSelect(item.color)}
@@ -385,6 +499,311 @@ export function Inspector({
               </section>
             )}
 
+            {activeTool === "eraser" && (
+              <section>
+                <h3 className="mb-2 text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  Erase
+                </h3>
+                <div className="grid grid-cols-2 gap-1 rounded-lg bg-muted/50 p-1">
+                  {eraserModeOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ eraserMode: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.eraserMode ?? "partial") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+              </section>
+            )}
+
+            {showLineStyle && (
+              <section>
+                <h3 className="mb-2 text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  Line Style
+                </h3>
+                <div className="grid grid-cols-4 gap-1 rounded-lg bg-muted/50 p-1">
+                  {dashOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ dash: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.dash ?? "solid") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+                <h3 className="mb-2 mt-4 text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  Line Cap
+                </h3>
+                <div className="grid grid-cols-3 gap-1 rounded-lg bg-muted/50 p-1">
+                  {lineCapOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ lineCap: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.lineCap ?? "round") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+                <h3 className="mb-2 mt-4 text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  Line Join
+                </h3>
+                <div className="grid grid-cols-3 gap-1 rounded-lg bg-muted/50 p-1">
+                  {lineJoinOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ lineJoin: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.lineJoin ?? "round") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+                <h3 className="mb-2 mt-4 text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  Start Marker
+                </h3>
+                <div className="grid grid-cols-3 gap-1 rounded-lg bg-muted/50 p-1">
+                  {markerOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ startMarker: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.startMarker ?? "none") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+                <h3 className="mb-2 mt-4 text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  End Marker
+                </h3>
+                <div className="grid grid-cols-3 gap-1 rounded-lg bg-muted/50 p-1">
+                  {markerOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ endMarker: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.endMarker ?? "none") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+              </section>
+            )}
+
+            {showPressure && (
+              <section>
+                <h3 className="mb-2 text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  Pen Pressure
+                </h3>
+                <div className="grid grid-cols-4 gap-1 rounded-lg bg-muted/50 p-1">
+                  {pressureCurveOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ pressureCurve: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.pressureCurve ?? "off") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+              </section>
+            )}
+
+            {showPressure && (
+              <section>
+                <div className="mb-2 flex items-center justify-between">
+                  <h3 className="text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                    Stabilizer
+                  </h3>
+                  {(currentSettings.stabilizer ?? "off") !== "off" && (
+                    <span className="text-xs font-medium tabular-nums">{currentSettings.stabilizerStrength ?? 5}</span>
+                  )}
+                </div>
+                <div className="grid grid-cols-4 gap-1 rounded-lg bg-muted/50 p-1">
+                  {stabilizerOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ stabilizer: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.stabilizer ?? "off") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+                {(currentSettings.stabilizer ?? "off") !== "off" && (
+                  <Slider
+                    value={[currentSettings.stabilizerStrength ?? 5]}
+                    onValueChange={(v) => onSettingsChange({ stabilizerStrength: v[0] })}
+                    min={1}
+                    max={10}
+                    step={1}
+                    className="mt-3 cursor-pointer"
+                  />
+                )}
+                <h3 className="mb-2 mt-4 text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  Curve Fit
+                </h3>
+                <div className="grid grid-cols-3 gap-1 rounded-lg bg-muted/50 p-1">
+                  {curveFitOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ curveFit: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.curveFit ?? "none") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+              </section>
+            )}
+
+            {activeTool === "text" && (
+              <section className="space-y-2">
+                <h3 className="text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  Text
+                </h3>
+                <div className="grid grid-cols-4 gap-1 rounded-lg bg-muted/50 p-1">
+                  {fontFamilyOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ fontFamily: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.fontFamily ?? "sans") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                      style={{ fontFamily: option.css }}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+                <div className="flex gap-2">
+                  <div className="grid flex-1 grid-cols-3 gap-1 rounded-lg bg-muted/50 p-1">
+                    {([
+                      ["bold", Bold],
+                      ["italic", Italic],
+                      ["underline", Underline],
+                    ] as const).map(([key, Icon]) => (
+                      <button
+                        key={key}
+                        onClick={() => onSettingsChange({ [key]: !currentSettings[key] } as Partial<ToolSettings>)}
+                        className={cn(
+                          "flex items-center justify-center rounded-md py-1 transition-all",
+                          currentSettings[key]
+                            ? "bg-background text-foreground shadow-sm"
+                            : "text-muted-foreground hover:text-foreground"
+                        )}
+                      >
+                        <Icon className="h-3.5 w-3.5" />
+                      </button>
+                    ))}
+                  </div>
+                  <div className="grid flex-1 grid-cols-3 gap-1 rounded-lg bg-muted/50 p-1">
+                    {textAlignOptions.map(({ value, icon: Icon }) => (
+                      <button
+                        key={value}
+                        onClick={() => onSettingsChange({ textAlign: value })}
+                        className={cn(
+                          "flex items-center justify-center rounded-md py-1 transition-all",
+                          (currentSettings.textAlign ?? "left") === value
+                            ? "bg-background text-foreground shadow-sm"
+                            : "text-muted-foreground hover:text-foreground"
+                        )}
+                      >
+                        <Icon className="h-3.5 w-3.5" />
+                      </button>
+                    ))}
+                  </div>
+                </div>
+                <div className="flex items-center justify-between">
+                  <span className="text-xs text-muted-foreground">Line Spacing</span>
+                  <span className="text-xs font-medium tabular-nums">{(currentSettings.lineSpacing ?? 1.2).toFixed(1)}×</span>
+                </div>
+                <Slider
+                  value={[currentSettings.lineSpacing ?? 1.2]}
+                  onValueChange={(v) => onSettingsChange({ lineSpacing: v[0] })}
+                  min={1}
+                  max={2.5}
+                  step={0.1}
+                  className="cursor-pointer"
+                />
+              </section>
+            )}
+
+            {activeTool === "pen" && (
+              <section>
+                <h3 className="mb-2 text-xs font-medium uppercase tracking-wider text-muted-foreground">
+                  Snap to Shapes
+                </h3>
+                <div className="grid grid-cols-3 gap-1 rounded-lg bg-muted/50 p-1">
+                  {shapeSnapOptions.map((option) => (
+                    <button
+                      key={option.value}
+                      onClick={() => onSettingsChange({ shapeSnap: option.value })}
+                      className={cn(
+                        "rounded-md px-2 py-1 text-xs font-medium transition-all",
+                        (currentSettings.shapeSnap ?? "off") === option.value
+                          ? "bg-background text-foreground shadow-sm"
+                          : "text-muted-foreground hover:text-foreground"
+                      )}
+                    >
+                      {option.label}
+                    </button>
+                  ))}
+                </div>
+              </section>
+            )}
+
             {showOpacity && (
               <section>
                 <div className="mb-2 flex items-center justify-between">
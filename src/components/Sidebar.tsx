@@ -1,6 +1,6 @@
 "use client"
 
-import { useState, memo, useCallback } from "react"
+import { useState, useEffect, memo, useCallback } from "react"
 import { cn } from "@/lib/utils"
 import { ScrollArea } from "@/components/ui/scroll-area"
 import { Button } from "@/components/ui/button"
@@ -27,11 +27,15 @@ interface SidebarProps {
   onDeletePage: (id: number) => void
 }
 
+// Thumbnails are shown at half this width, so they stay sharp on HiDPI screens.
+const THUMBNAIL_WIDTH = 96
+
 const PageItem = memo(function PageItem({ 
   page, 
   index, 
   isActive, 
   canDelete,
+  thumbnail,
   onSelect, 
   onDelete 
 }: { 
@@ -39,6 +43,7 @@ const PageItem = memo(function PageItem({
   index: number
   isActive: boolean
   canDelete: boolean
+  thumbnail?: string
   onSelect: () => void
   onDelete: () => void
 }) {
@@ -52,14 +57,25 @@ const PageItem = memo(function PageItem({
           : "hover:bg-muted"
       )}
     >
-      <div className={cn(
-        "flex h-8 w-6 shrink-0 items-center justify-center rounded border text-xs font-medium",
-        isActive 
-          ? "border-primary-foreground/30 bg-primary-foreground/10" 
-          : "border-border bg-background"
-      )}>
-        {index + 1}
-      </div>
+      {thumbnail ? (
+        <img
+          src={thumbnail}
+          alt={`Page ${index + 1}`}
+          className={cn(
+            "h-16 w-12 shrink-0 rounded border bg-white object-contain",
+            isActive ? "border-primary-foreground/30" : "border-border"
+          )}
+        />
+      ) : (
+        <div className={cn(
+          "flex h-8 w-6 shrink-0 items-center justify-center rounded border text-xs font-medium",
+          isActive 
+            ? "border-primary-foreground/30 bg-primary-foreground/10" 
+            : "border-border bg-background"
+        )}>
+          {index + 1}
+        </div>
+      )}
       
       <div className="flex flex-1 items-center justify-between min-w-0">
         <span className="text-sm font-medium truncate">
@@ -101,6 +117,45 @@ const PageItem = memo(function PageItem({
 function SidebarComponent({ currentPage, onPageChange, pages, onAddPage, onDeletePage }: SidebarProps) {
   const [isCollapsed, setIsCollapsed] = useState(false)
   const pagesMeta = usePdfStore(s => s.pagesMeta)
+  const pdfPath = usePdfStore(s => s.pdfPath)
+  const [documentHash, setDocumentHash] = useState<string | null>(null)
+  // Keyed by page; entries from another document are ignored by their hash.
+  const [thumbnails, setThumbnails] = useState<Map<number, { hash: string; image: string }>>(new Map())
+
+  useEffect(() => {
+    if (!pdfPath || pagesMeta.length === 0) return
+
+    let disposed = false
+    let unlisten: (() => void) | null = null
+    const load = async () => {
+      const { onThumbnailRendered, renderThumbnails } = await import("@/lib/tauri")
+      const stop = await onThumbnailRendered((thumbnail) => {
+        setThumbnails((current) => new Map(current).set(thumbnail.page_number, {
+          hash: thumbnail.document_hash,
+          image: thumbnail.image_data,
+        }))
+      })
+      if (disposed) {
+        stop()
+        return
+      }
+      unlisten = stop
+      const batch = await renderThumbnails(pdfPath, 1, pagesMeta.length, THUMBNAIL_WIDTH)
+      if (!disposed && batch) setDocumentHash(batch.document_hash)
+    }
+    load().catch((err) => console.error("Failed to render thumbnails:", err))
+
+    return () => {
+      disposed = true
+      unlisten?.()
+      setDocumentHash(null)
+    }
+  }, [pdfPath, pagesMeta.length])
+
+  const thumbnailFor = (pageNumber: number) => {
+    const thumbnail = thumbnails.get(pageNumber)
+    return thumbnail && thumbnail.hash === documentHash ? thumbnail.image : undefined
+  }
 
   if (isCollapsed) {
     return (
@@ -171,6 +226,7 @@ function SidebarComponent({ currentPage, onPageChange, pages, onAddPage, onDelet
                 index={index}
                 isActive={currentPage === page.id}
                 canDelete={pages.length > 1}
+                thumbnail={pdfPath ? thumbnailFor(page.id) : undefined}
                 onSelect={() => onPageChange(page.id)}
                 onDelete={() => onDeletePage(page.id)}
               />
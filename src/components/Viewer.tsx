@@ -16,14 +16,26 @@ import {
   ChevronRight,
   Maximize,
   RotateCcw,
+  Bold,
+  Italic,
+  Underline,
+  AlignLeft,
+  AlignCenter,
+  AlignRight,
 } from "lucide-react"
 import type { Tool, ShapeType } from "@/components/Dock"
-import { useCanvasStore, usePdfStore } from "@/lib/store"
+import { useCanvasStore, useNotebookStore, usePdfStore, useSettingsStore } from "@/lib/store"
+import type { TextBoxStyle } from "@/lib/store"
 import * as wasmEngine from "@/lib/wasm-engine"
+import type { RenderPriority } from "@/lib/tauri"
 
 interface Point {
   x: number
   y: number
+  pressure?: number
+  tiltX?: number
+  tiltY?: number
+  time?: number
 }
 
 interface ToolSettings {
@@ -32,6 +44,136 @@ interface ToolSettings {
   opacity: number
   borderColor?: string
   backgroundColor?: string
+  pressureCurve?: wasmEngine.PressureCurvePreset
+  stabilizer?: wasmEngine.StabilizerMode
+  stabilizerStrength?: number
+  curveFit?: wasmEngine.CurveFit
+  eraserMode?: wasmEngine.EraserMode
+  shapeSnap?: wasmEngine.ShapeSnap
+  dash?: wasmEngine.DashStyle
+  lineCap?: wasmEngine.LineCap
+  lineJoin?: wasmEngine.LineJoin
+  startMarker?: wasmEngine.LineMarker
+  endMarker?: wasmEngine.LineMarker
+  fontFamily?: wasmEngine.FontFamily
+  bold?: boolean
+  italic?: boolean
+  underline?: boolean
+  textAlign?: wasmEngine.TextAlign
+  lineSpacing?: number
+}
+
+const PATH_SHAPES: ShapeType[] = ["polyline", "polygon", "bezier", "cloud-polygon"]
+const CLOSED_PATH_SHAPES: ShapeType[] = ["polygon", "cloud-polygon"]
+
+interface PathAnchor {
+  point: Point
+  // Offset of the outgoing Bézier control point; the incoming one mirrors it.
+  handle: Point
+}
+
+// Engine points for a path being drawn, plus a trailing vertex under the pointer.
+const pathShapePoints = (shape: ShapeType, anchors: PathAnchor[], hover?: Point): Point[] => {
+  const all = hover ? [...anchors, { point: hover, handle: { x: 0, y: 0 } }] : anchors
+  if (shape !== "bezier") return all.map(a => a.point)
+  return all.flatMap((a, i) => {
+    if (i === 0) return [a.point]
+    const prev = all[i - 1]
+    return [
+      { x: prev.point.x + prev.handle.x, y: prev.point.y + prev.handle.y },
+      { x: a.point.x - a.handle.x, y: a.point.y - a.handle.y },
+      a.point,
+    ]
+  })
+}
+
+// The pointer adds a trailing vertex unless it is still on the last one.
+const pathHover = (anchors: PathAnchor[], pointer: Point | null): Point | undefined => {
+  const last = anchors[anchors.length - 1]
+  return pointer && last && (pointer.x !== last.point.x || pointer.y !== last.point.y) ? pointer : undefined
+}
+
+// Browsers refuse to allocate canvases much larger than this.
+const MAX_CANVAS_PIXELS = 16_777_216
+const RENDER_ZOOM_DELAY_MS = 250
+
+// A click without a drag would leave a markup shape's bumps, tail or leader
+// around an empty box, so these are only added once dragged out.
+const MARKUP_SHAPES: ShapeType[] = ["cloud", "callout", "bubble"]
+const MIN_MARKUP_SIZE = 2
+
+// A callout's anchor and a speech bubble's tail start below and left of the
+// box; they are dragged into place afterwards.
+const boxShapePoints = (shape: ShapeType, start: Point, end: Point): Point[] => {
+  if (shape !== "callout" && shape !== "bubble") return [start, end]
+  const height = Math.abs(end.y - start.y)
+  const tail = shape === "callout"
+    ? { x: Math.min(start.x, end.x) - Math.max(height, 40), y: Math.max(start.y, end.y) + Math.max(height, 40) }
+    : { x: Math.min(start.x, end.x) + Math.abs(end.x - start.x) * 0.2, y: Math.max(start.y, end.y) + Math.max(height * 0.5, 24) }
+  return [start, end, tail]
+}
+
+const DEFAULT_TEXT_BOX_WIDTH = 240
+// Matches the engine's text box padding so the editor lines up with the drawn text.
+const TEXT_BOX_PADDING = 6
+const TEXT_BOX_FONTS: Record<wasmEngine.FontFamily, string> = {
+  sans: "Inter, system-ui, sans-serif",
+  serif: "Georgia, 'Times New Roman', serif",
+  mono: "'JetBrains Mono', Menlo, Consolas, monospace",
+  handwriting: "'Comic Sans MS', 'Segoe Print', cursive",
+}
+const LINE_SPACINGS = [1, 1.2, 1.5, 2]
+
+const textBoxStyle = (settings: ToolSettings): TextBoxStyle => ({
+  fontFamily: settings.fontFamily ?? "sans",
+  bold: settings.bold ?? false,
+  italic: settings.italic ?? false,
+  underline: settings.underline ?? false,
+  align: settings.textAlign ?? "left",
+  lineSpacing: settings.lineSpacing ?? 1.2,
+  borderColor: settings.borderColor && settings.borderColor !== "transparent" ? settings.borderColor : undefined,
+})
+
+const fromWasmTextBox = (textBox: wasmEngine.TextBox): TextBoxStyle & { content: string; width: number } => ({
+  content: textBox.content,
+  width: textBox.width,
+  fontFamily: textBox.font_family,
+  bold: textBox.bold,
+  italic: textBox.italic,
+  underline: textBox.underline,
+  align: textBox.align,
+  lineSpacing: textBox.line_spacing,
+  borderColor: textBox.border_color ?? undefined,
+})
+
+// A text box open for typing: a new one, or an existing stroke being edited.
+interface TextBoxDraft {
+  id?: string
+  position: Point
+  value: string
+  width: number
+  rotation: number
+  color: string
+  fontSize: number
+  style: TextBoxStyle
+}
+
+// Holding the pen within this many screen pixels for this long snaps the
+// sketch to a shape.
+const SNAP_HOLD_MS = 500
+const SNAP_HOLD_SLOP = 4
+
+// Arrows and callout leaders always end in a head: the open arrow unless
+// another marker is picked.
+const shapeLineStyle = (settings: ToolSettings, shape: string): wasmEngine.LineStyle => {
+  const endMarker = settings.endMarker ?? "none"
+  return {
+    dash: settings.dash ?? "solid",
+    cap: settings.lineCap ?? "round",
+    join: settings.lineJoin ?? "round",
+    start_marker: settings.startMarker ?? "none",
+    end_marker: (shape === "arrow" || shape === "callout") && endMarker === "none" ? "open_arrow" : endMarker,
+  }
 }
 
 interface ViewerProps {
@@ -84,28 +226,55 @@ export function Viewer({
   const [shapeEnd, setShapeEnd] = useState<Point | null>(null)
   const [symbolStart, setSymbolStart] = useState<Point | null>(null)
   const [symbolEnd, setSymbolEnd] = useState<Point | null>(null)
-  const [textInput, setTextInput] = useState<{ position: Point; value: string } | null>(null)
-  const textInputRef = useRef<HTMLInputElement>(null)
+  const [textInput, setTextInput] = useState<TextBoxDraft | null>(null)
+  const textInputRef = useRef<HTMLTextAreaElement>(null)
   const [isDragging, setIsDragging] = useState(false)
   const [dragOffset, setDragOffset] = useState<Point>({ x: 0, y: 0 })
   const [isResizing, setIsResizing] = useState(false)
   const [resizeCorner, setResizeCorner] = useState<'tl' | 'tr' | 'bl' | 'br' | null>(null)
+  const [transformHandle, setTransformHandle] = useState<wasmEngine.TransformHandle | null>(null)
+  const [hoverHandle, setHoverHandle] = useState<wasmEngine.TransformHandle | null>(null)
+  const [editingVertex, setEditingVertex] = useState<number | null>(null)
+  const [pathAnchors, setPathAnchors] = useState<PathAnchor[] | null>(null)
+  const pathHandleDragRef = useRef(false)
   const [isZooming, setIsZooming] = useState<'in' | 'out' | null>(null)
   const [pdfImage, setPdfImage] = useState<HTMLImageElement | null>(null)
   const [fps, setFps] = useState(0)
   const [wasmReady, setWasmReady] = useState(false)
+  const isPartialErase = () => wasmReady && (getToolSettings("eraser").eraserMode ?? "partial") === "partial"
+
+  const clearSnapTimer = () => {
+    if (snapTimerRef.current) clearTimeout(snapTimerRef.current)
+    snapTimerRef.current = null
+    snapAnchorRef.current = null
+  }
+
+  useEffect(() => () => {
+    if (snapTimerRef.current) clearTimeout(snapTimerRef.current)
+  }, [])
   const rafIdRef = useRef<number | null>(null)
   const fpsIntervalRef = useRef<NodeJS.Timeout | null>(null)
   const [rubberBandStart, setRubberBandStart] = useState<Point | null>(null)
   const [rubberBandEnd, setRubberBandEnd] = useState<Point | null>(null)
   const [isRubberBanding, setIsRubberBanding] = useState(false)
+  const [isLassoing, setIsLassoing] = useState(false)
+  const lassoPointsRef = useRef<Point[]>([])
+  const selectFullyRef = useRef(false)
   
-  const [currentPressure, setCurrentPressure] = useState(0.5)
   const activePointerId = useRef<number | null>(null)
+  const strokeStartTimeRef = useRef(0)
+  const lastInkPointRef = useRef<Point | undefined>(undefined)
+  // Hold-to-snap: the timer restarts whenever the pen moves on, and a shape
+  // recognized when it fires stands in for the ink until the pen lifts.
+  const snapTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null)
+  const snapAnchorRef = useRef<Point | null>(null)
+  const snappedShapeRef = useRef<wasmEngine.RecognizedShape | null>(null)
 
   const strokes = useCanvasStore(s => s.strokes)
   const addStroke = useCanvasStore(s => s.addStroke)
   const updateStroke = useCanvasStore(s => s.updateStroke)
+  const transformStrokes = useCanvasStore(s => s.transformStrokes)
+  const splitStrokes = useCanvasStore(s => s.splitStrokes)
   const deleteStroke = useCanvasStore(s => s.deleteStroke)
   const selectStroke = useCanvasStore(s => s.selectStroke)
   const selectStrokes = useCanvasStore(s => s.selectStrokes)
@@ -127,83 +296,237 @@ export function Viewer({
   const pagesMeta = usePdfStore(s => s.pagesMeta)
   const renderedPages = usePdfStore(s => s.renderedPages)
   const setRenderedPage = usePdfStore(s => s.setRenderedPage)
+  const clearRenderedPages = usePdfStore(s => s.clearRenderedPages)
+  const pageRenderMode = useSettingsStore(s => s.pageRenderMode)
+  const notebookWidth = useNotebookStore(s => s.page_width)
+  const notebookHeight = useNotebookStore(s => s.page_height)
+  const paperTemplate = useNotebookStore(s => s.template)
+
+  const openTextBox = useCallback((draft: TextBoxDraft) => {
+    wasmEngine.setEditingText(draft.id ?? null)
+    needsRenderRef.current = true
+    setTextInput(draft)
+    setTimeout(() => textInputRef.current?.focus(), 10)
+  }, [])
 
+  const editTextBox = useCallback((id: string) => {
+    const stroke = getStrokeById(id)
+    if (!stroke?.textBox) return
+    const { content, width, ...style } = stroke.textBox
+    selectStroke(null)
+    openTextBox({
+      id,
+      position: stroke.points[0],
+      value: content,
+      width,
+      rotation: stroke.rotation ?? 0,
+      color: stroke.color,
+      fontSize: Math.max(14, stroke.thickness * 4),
+      style,
+    })
+  }, [getStrokeById, selectStroke, openTextBox])
+
+  // Emptying an existing box deletes it.
   const handleTextSubmit = useCallback(() => {
-    if (textInput && textInput.value.trim()) {
+    if (!textInput) return
+    const { id, value, width, style } = textInput
+    const textBox = { ...style, content: value, width }
+    if (id) {
+      const original = getStrokeById(id)?.textBox
+      if (!value.trim()) deleteStroke(id)
+      else if (JSON.stringify(original) !== JSON.stringify(textBox)) updateStroke(id, { textBox })
+    } else if (value.trim()) {
       const textSettings = getToolSettings("text")
       addStroke({
         points: [textInput.position],
         color: textSettings.color,
         thickness: textSettings.thickness,
         opacity: textSettings.opacity,
-        tool: `text:${textInput.value}`,
+        tool: "textbox",
         pageId: currentPage,
+        backgroundColor: textSettings.backgroundColor && textSettings.backgroundColor !== "transparent" ? textSettings.backgroundColor : undefined,
+        textBox,
       })
     }
+    wasmEngine.setEditingText(null)
+    needsRenderRef.current = true
+    setTextInput(null)
+  }, [textInput, getToolSettings, getStrokeById, currentPage, addStroke, updateStroke, deleteStroke])
+
+  const cancelTextBox = useCallback(() => {
+    wasmEngine.setEditingText(null)
+    needsRenderRef.current = true
     setTextInput(null)
-  }, [textInput, getToolSettings, currentPage, addStroke])
+  }, [])
 
   const currentPageMeta = pagesMeta.find((p) => p.pageNumber === currentPage)
   const currentPageImage = renderedPages.get(currentPage)
-  const canvasWidth = currentPageMeta ? Math.round(currentPageMeta.width) : 595
-  const canvasHeight = currentPageMeta ? Math.round(currentPageMeta.height) : 842
+  const canvasWidth = currentPageMeta ? Math.round(currentPageMeta.width) : Math.round(notebookWidth)
+  const canvasHeight = currentPageMeta ? Math.round(currentPageMeta.height) : Math.round(notebookHeight)
   const scale = zoom / 100
 
-  const pdfLoadingRef = useRef(false)
-  const pdfQueueRef = useRef<number[]>([])
-  
+  const [renderZoom, setRenderZoom] = useState(scale)
+  const [devicePixelRatio, setDevicePixelRatio] = useState(1)
+  // Canvas backing pixels per page point: the page is drawn at screen resolution.
+  const pixelRatio = Math.min(renderZoom * devicePixelRatio, Math.sqrt(MAX_CANVAS_PIXELS / (canvasWidth * canvasHeight)))
+
+  const renderRequestsRef = useRef<Map<number, number>>(new Map())
+  const staleRequestsRef = useRef<Set<number>>(new Set())
+  // Requests that settled before `schedulePageRenders` returned their ids.
+  const settledRequestsRef = useRef<Set<number>>(new Set())
+  const schedulingRef = useRef(0)
+  // Pages still shown from a render at an earlier zoom or pixel ratio.
+  const outdatedPagesRef = useRef<Set<number>>(new Set())
+  const listenersReadyRef = useRef<Promise<void>>(Promise.resolve())
+  const renderModeRef = useRef(pageRenderMode)
+  const renderResolutionRef = useRef({ zoom: renderZoom, devicePixelRatio })
+
+  // Zooming re-renders pages once it settles rather than on every step.
   useEffect(() => {
-    if (!pdfPath || !currentPageMeta) return
-    
-    let cancelled = false
-    
-    const loadPage = async (pageNum: number) => {
-      if (cancelled || renderedPages.has(pageNum)) return
-      
-      try {
-        const { renderPdfPage } = await import("@/lib/tauri")
-        const result = await renderPdfPage(pdfPath, pageNum, 1200)
-        if (result && !cancelled) {
-          setRenderedPage(pageNum, result.image_data)
-        }
-      } catch (err) {
-        console.error("Failed to render page:", pageNum, err)
-      }
+    const timer = setTimeout(() => setRenderZoom(scale), RENDER_ZOOM_DELAY_MS)
+    return () => clearTimeout(timer)
+  }, [scale])
+
+  // Follows moves between screens of different density and browser zoom.
+  useEffect(() => {
+    let query: MediaQueryList | null = null
+    const update = () => {
+      query?.removeEventListener("change", update)
+      setDevicePixelRatio(window.devicePixelRatio || 1)
+      query = window.matchMedia(`(resolution: ${window.devicePixelRatio || 1}dppx)`)
+      query.addEventListener("change", update)
     }
-    
-    const processQueue = async () => {
-      if (pdfLoadingRef.current || pdfQueueRef.current.length === 0 || cancelled) return
-      
-      pdfLoadingRef.current = true
-      const pageNum = pdfQueueRef.current.shift()!
-      
-      await loadPage(pageNum)
-      
-      pdfLoadingRef.current = false
-      
-      if (!cancelled && pdfQueueRef.current.length > 0) {
-        setTimeout(processQueue, 16)
+    update()
+    return () => query?.removeEventListener("change", update)
+  }, [])
+
+  const invalidateRenderRequests = useCallback(() => {
+    const stale = [...renderRequestsRef.current.values()]
+    stale.forEach((id) => staleRequestsRef.current.add(id))
+    renderRequestsRef.current.clear()
+    import("@/lib/tauri").then(({ cancelPageRenders }) => cancelPageRenders(stale))
+  }, [])
+
+  useEffect(() => {
+    if (renderModeRef.current === pageRenderMode) return
+    renderModeRef.current = pageRenderMode
+    invalidateRenderRequests()
+    outdatedPagesRef.current.clear()
+    clearRenderedPages()
+  }, [pageRenderMode, clearRenderedPages, invalidateRenderRequests])
+
+  // Pages keep showing at their old resolution until the sharper render arrives.
+  useEffect(() => {
+    const previous = renderResolutionRef.current
+    if (previous.zoom === renderZoom && previous.devicePixelRatio === devicePixelRatio) return
+    renderResolutionRef.current = { zoom: renderZoom, devicePixelRatio }
+    invalidateRenderRequests()
+    outdatedPagesRef.current = new Set(usePdfStore.getState().renderedPages.keys())
+  }, [renderZoom, devicePixelRatio, invalidateRenderRequests])
+
+  useEffect(() => {
+    if (!pdfPath) return
+
+    let disposed = false
+    const unlisteners: (() => void)[] = []
+    const requests = renderRequestsRef.current
+
+    const subscribe = async () => {
+      const { onPageRenderComplete, onPageRenderFailed, onPageRenderCancelled } = await import("@/lib/tauri")
+      const settled = (page: number, requestId: number) => {
+        if (requests.get(page) === requestId) requests.delete(page)
+        else if (schedulingRef.current > 0) settledRequestsRef.current.add(requestId)
+      }
+      const handlers = await Promise.all([
+        onPageRenderComplete((result) => {
+          if (result.path !== pdfPath) return
+          if (staleRequestsRef.current.delete(result.request_id)) return
+          settled(result.page_number, result.request_id)
+          outdatedPagesRef.current.delete(result.page_number)
+          setRenderedPage(result.page_number, result.image_data)
+        }),
+        onPageRenderFailed((result) => {
+          if (result.path !== pdfPath) return
+          if (staleRequestsRef.current.delete(result.request_id)) return
+          settled(result.page_number, result.request_id)
+          console.error("Failed to render page:", result.page_number, result.error)
+        }),
+        onPageRenderCancelled((result) => {
+          if (result.path !== pdfPath) return
+          if (staleRequestsRef.current.delete(result.request_id)) return
+          settled(result.page_number, result.request_id)
+        }),
+      ])
+      if (disposed) {
+        handlers.forEach((unlisten) => unlisten())
+      } else {
+        unlisteners.push(...handlers)
       }
     }
-    
-    pdfQueueRef.current = []
-    
-    if (!renderedPages.has(currentPage)) {
-      pdfQueueRef.current.push(currentPage)
+    // Renders are only scheduled once these listeners exist, so no event is missed.
+    listenersReadyRef.current = subscribe().catch((err) => console.error("Failed to listen for page renders:", err))
+
+    return () => {
+      disposed = true
+      unlisteners.forEach((unlisten) => unlisten())
+      requests.clear()
+      settledRequestsRef.current.clear()
+      outdatedPagesRef.current.clear()
+      import("@/lib/tauri").then(({ cancelAllPageRenders }) => cancelAllPageRenders(pdfPath))
     }
-    
+  }, [pdfPath, setRenderedPage])
+
+  useEffect(() => {
+    if (!pdfPath || !currentPageMeta) return
+
     const total = pagesMeta.length
-    if (currentPage > 1 && !renderedPages.has(currentPage - 1)) {
-      pdfQueueRef.current.push(currentPage - 1)
+    const wanted = new Map<number, RenderPriority>()
+    wanted.set(currentPage, "visible")
+    if (currentPage > 1) wanted.set(currentPage - 1, "adjacent")
+    if (currentPage < total) wanted.set(currentPage + 1, "adjacent")
+
+    const requests = renderRequestsRef.current
+    const stale: number[] = []
+    for (const [page, requestId] of requests) {
+      if (!wanted.has(page)) {
+        stale.push(requestId)
+        requests.delete(page)
+      }
     }
-    if (currentPage < total && !renderedPages.has(currentPage + 1)) {
-      pdfQueueRef.current.push(currentPage + 1)
+
+    const toSchedule = [...wanted]
+      .filter(([page]) => (!renderedPages.has(page) || outdatedPagesRef.current.has(page)) && !requests.has(page))
+      .map(([page_number, priority]) => ({ page_number, priority }))
+
+    const resolution = renderResolutionRef.current
+    const options = { zoom: resolution.zoom, device_pixel_ratio: resolution.devicePixelRatio, mode: pageRenderMode }
+
+    const update = async () => {
+      const { cancelPageRenders, schedulePageRenders } = await import("@/lib/tauri")
+      await cancelPageRenders(stale)
+      if (toSchedule.length === 0) return
+      await listenersReadyRef.current
+      schedulingRef.current += 1
+      let ids: number[]
+      try {
+        ids = await schedulePageRenders(pdfPath, toSchedule, undefined, options)
+      } finally {
+        schedulingRef.current -= 1
+      }
+      // The zoom or mode moved on while these were being queued.
+      if (renderResolutionRef.current !== resolution || renderModeRef.current !== options.mode) {
+        ids.forEach((id) => staleRequestsRef.current.add(id))
+        await cancelPageRenders(ids)
+        return
+      }
+      ids.forEach((id, i) => {
+        if (settledRequestsRef.current.delete(id)) return
+        requests.set(toSchedule[i].page_number, id)
+      })
+      if (schedulingRef.current === 0) settledRequestsRef.current.clear()
     }
-    
-    processQueue()
-    
-    return () => { cancelled = true }
-  }, [currentPage, pdfPath, currentPageMeta, pagesMeta.length, renderedPages, setRenderedPage])
+    update().catch((err) => console.error("Failed to schedule page renders:", err))
+  }, [currentPage, pdfPath, currentPageMeta, pagesMeta.length, renderedPages, pageRenderMode, renderZoom, devicePixelRatio])
 
   useEffect(() => {
     if (currentPageImage) {
@@ -249,7 +572,20 @@ export function Viewer({
     }
   }, [canvasWidth, canvasHeight, wasmReady])
 
+  useEffect(() => {
+    if (!wasmReady) return
+    wasmEngine.setAnnotationColorMode(pageRenderMode === "inverted" ? "inverted" : "normal")
+    needsRenderRef.current = true
+  }, [wasmReady, pageRenderMode])
+
+  useEffect(() => {
+    if (!wasmReady) return
+    wasmEngine.setPaperTemplate(paperTemplate)
+    needsRenderRef.current = true
+  }, [wasmReady, paperTemplate])
+
   const strokesVersionRef = useRef(0)
+  const syncedStrokesRef = useRef<{ page: number; strokes: ReturnType<typeof getPageStrokes> } | null>(null)
   
   useEffect(() => {
     if (!wasmReady) return
@@ -261,7 +597,7 @@ export function Viewer({
       if (version !== strokesVersionRef.current) return
       
       const pageStrokes = getPageStrokes(currentPage)
-      const wasmStrokes = pageStrokes.map(s => ({
+      const toWasmStroke = (s: (typeof pageStrokes)[number]) => ({
         id: s.id,
         points: s.points,
         color: s.color,
@@ -269,22 +605,64 @@ export function Viewer({
         opacity: s.opacity,
         tool: s.tool,
         fill_color: s.fillColor || s.backgroundColor,
-      }))
-      wasmEngine.setStrokes(wasmStrokes)
+        rotation: s.rotation,
+        pressure_curve: s.pressureCurve ? { gamma: s.pressureCurve.gamma, min_width: s.pressureCurve.minWidth } : undefined,
+        line_style: s.lineStyle ? {
+          dash: s.lineStyle.dash,
+          cap: s.lineStyle.cap,
+          join: s.lineStyle.join,
+          start_marker: s.lineStyle.startMarker,
+          end_marker: s.lineStyle.endMarker,
+        } : undefined,
+        text_box: s.textBox ? {
+          content: s.textBox.content,
+          width: s.textBox.width,
+          font_family: s.textBox.fontFamily,
+          bold: s.textBox.bold,
+          italic: s.textBox.italic,
+          underline: s.textBox.underline,
+          align: s.textBox.align,
+          line_spacing: s.textBox.lineSpacing,
+          border_color: s.textBox.borderColor,
+        } : undefined,
+      })
+      const synced = syncedStrokesRef.current
+      if (synced && synced.page === currentPage) {
+        wasmEngine.syncStrokes(synced.strokes, pageStrokes, toWasmStroke)
+      } else {
+        wasmEngine.setStrokes(pageStrokes.map(toWasmStroke))
+      }
+      syncedStrokesRef.current = { page: currentPage, strokes: pageStrokes }
       wasmEngine.setSelectedIds(selectedStrokeIds)
       needsRenderRef.current = true
     })
   }, [wasmReady, strokes, currentPage, getPageStrokes, selectedStrokeIds])
 
+  const syncedCurrentStrokeRef = useRef<Point[]>([])
+
   useEffect(() => {
     if (!wasmReady) return
     
-    const style = currentStroke.length > 0 ? {
-      color: getToolSettings(activeTool).color,
-      thickness: getToolSettings(activeTool).thickness,
-      opacity: getToolSettings(activeTool).opacity,
-    } : null
-    wasmEngine.setCurrentStroke(currentStroke, style)
+    const synced = syncedCurrentStrokeRef.current
+    syncedCurrentStrokeRef.current = currentStroke
+    if (currentStroke.length === 0) {
+      wasmEngine.clearCurrentStroke()
+    } else if (synced !== currentStroke && synced.length > 0 && synced.length <= currentStroke.length && currentStroke[synced.length - 1] === synced[synced.length - 1]) {
+      // Pointer moves only append, so ship just the new points.
+      wasmEngine.appendCurrentPoints(currentStroke.slice(synced.length))
+    } else {
+      const settings = getToolSettings(activeTool)
+      const preset = settings.pressureCurve ?? "off"
+      wasmEngine.setCurrentStyle({
+        color: settings.color,
+        thickness: settings.thickness,
+        opacity: settings.opacity,
+        pressure_curve: preset !== "off" ? wasmEngine.PRESSURE_CURVES[preset] : undefined,
+        tool: activeTool,
+      })
+      if (wasmEngine.hasPressure(currentStroke)) wasmEngine.setCurrentSamples(currentStroke)
+      else wasmEngine.setCurrentPoints(wasmEngine.toCoords(currentStroke))
+    }
     needsRenderRef.current = true
   }, [wasmReady, currentStroke, activeTool, getToolSettings])
 
@@ -301,12 +679,71 @@ export function Viewer({
         thickness: settings.thickness,
         opacity: settings.opacity,
         fill_color: settings.backgroundColor !== "transparent" ? settings.backgroundColor : undefined,
+        line_style: shapeLineStyle(settings, activeShape),
+        points: pathAnchors
+          ? pathShapePoints(activeShape, pathAnchors, pathHover(pathAnchors, shapeEnd))
+          : boxShapePoints(activeShape, shapeStart, shapeEnd),
       })
     } else {
       wasmEngine.setShapePreview(null)
     }
     needsRenderRef.current = true
-  }, [wasmReady, shapeStart, shapeEnd, isDrawing, activeTool, activeShape, getToolSettings])
+  }, [wasmReady, shapeStart, shapeEnd, isDrawing, activeTool, activeShape, pathAnchors, getToolSettings])
+
+  const cancelPath = useCallback(() => {
+    setPathAnchors(null)
+    setShapeStart(null)
+    setShapeEnd(null)
+    setIsDrawing(false)
+    pathHandleDragRef.current = false
+  }, [])
+
+  const addShapeStroke = useCallback((points: Point[]) => {
+    const shapeSettings = getToolSettings("shapes")
+    const lineStyle = shapeLineStyle(shapeSettings, activeShape)
+    addStroke({
+      points,
+      color: shapeSettings.borderColor || shapeSettings.color,
+      thickness: shapeSettings.thickness,
+      opacity: shapeSettings.opacity,
+      tool: `shape-${activeShape}`,
+      pageId: currentPage,
+      backgroundColor: shapeSettings.backgroundColor !== "transparent" ? shapeSettings.backgroundColor : undefined,
+      lineStyle: {
+        dash: lineStyle.dash,
+        cap: lineStyle.cap,
+        join: lineStyle.join,
+        startMarker: lineStyle.start_marker,
+        endMarker: lineStyle.end_marker,
+      },
+    })
+  }, [getToolSettings, activeShape, addStroke, currentPage])
+
+  // Recognized sketches keep the pen's colour and width.
+  const addRecognizedShape = useCallback((shape: wasmEngine.RecognizedShape) => {
+    const penSettings = getToolSettings("pen")
+    addStroke({
+      points: shape.points,
+      color: penSettings.color,
+      thickness: penSettings.thickness,
+      opacity: penSettings.opacity,
+      tool: shape.tool,
+      pageId: currentPage,
+      rotation: shape.rotation || undefined,
+    })
+  }, [getToolSettings, addStroke, currentPage])
+
+  const finishPath = useCallback((anchors: PathAnchor[]) => {
+    if (anchors.length >= (CLOSED_PATH_SHAPES.includes(activeShape) ? 3 : 2)) {
+      addShapeStroke(pathShapePoints(activeShape, anchors))
+    }
+    cancelPath()
+  }, [activeShape, addShapeStroke, cancelPath])
+
+  // A path in progress is dropped when the tool or shape changes.
+  useEffect(() => {
+    cancelPath()
+  }, [activeTool, activeShape, cancelPath])
 
   useEffect(() => {
     if (!wasmReady) return
@@ -327,13 +764,12 @@ export function Viewer({
   }, [wasmReady, symbolStart, symbolEnd, isDrawing, pendingSymbol, getToolSettings])
 
   const pdfCanvasRef = useRef<HTMLCanvasElement | null>(null)
-  const lastPdfImageRef = useRef<HTMLImageElement | null>(null)
   const needsRenderRef = useRef(true)
   const isActiveRef = useRef(false)
   
   useEffect(() => {
-    isActiveRef.current = isDrawing || isPanning || isDragging || isResizing
-  }, [isDrawing, isPanning, isDragging, isResizing])
+    isActiveRef.current = isDrawing || isPanning || isDragging || isResizing || transformHandle !== null || editingVertex !== null
+  }, [isDrawing, isPanning, isDragging, isResizing, transformHandle, editingVertex])
   
   useEffect(() => {
     needsRenderRef.current = true
@@ -351,16 +787,15 @@ export function Viewer({
     if (!pdfCanvasRef.current) {
       pdfCanvasRef.current = document.createElement("canvas")
     }
+    // The page bitmap is kept at the pixel size it was rendered at.
     const pdfCanvas = pdfCanvasRef.current
-    pdfCanvas.width = canvasWidth
-    pdfCanvas.height = canvasHeight
+    pdfCanvas.width = pdfImage?.naturalWidth || canvasWidth
+    pdfCanvas.height = pdfImage?.naturalHeight || canvasHeight
     const pdfCtx = pdfCanvas.getContext("2d", { alpha: false })
-    
-    if (pdfImage && pdfCtx && pdfImage !== lastPdfImageRef.current) {
-      pdfCtx.drawImage(pdfImage, 0, 0, canvasWidth, canvasHeight)
-      lastPdfImageRef.current = pdfImage
-      needsRenderRef.current = true
+    if (pdfImage && pdfCtx) {
+      pdfCtx.drawImage(pdfImage, 0, 0)
     }
+    needsRenderRef.current = true
     
     let lastTime = 0
     const targetFrameTime = 1000 / 60
@@ -374,9 +809,10 @@ export function Viewer({
         lastTime = timestamp
         wasmEngine.recordFrame(timestamp)
         
-        if (needsRenderRef.current || isActiveRef.current) {
+        if (needsRenderRef.current || isActiveRef.current || wasmEngine.hasPendingFullFrame()) {
+          ctx.setTransform(pixelRatio, 0, 0, pixelRatio, 0, 0)
           if (pdfImage) {
-            ctx.drawImage(pdfCanvas, 0, 0)
+            ctx.drawImage(pdfCanvas, 0, 0, canvasWidth, canvasHeight)
           }
           wasmEngine.render(ctx, !!pdfImage)
           needsRenderRef.current = false
@@ -392,7 +828,33 @@ export function Viewer({
       running = false
       if (rafIdRef.current) cancelAnimationFrame(rafIdRef.current)
     }
-  }, [wasmReady, pdfImage, canvasWidth, canvasHeight])
+  }, [wasmReady, pdfImage, canvasWidth, canvasHeight, pixelRatio])
+
+  useEffect(() => {
+    if (!wasmReady) return
+
+    // Mirrors the centred, scaled and panned layout of the canvas wrapper.
+    const updateViewport = () => {
+      const container = containerRef.current
+      if (!container) return
+      const margin = 64
+      const halfWidth = container.clientWidth / 2 / scale
+      const halfHeight = container.clientHeight / 2 / scale
+      const centerX = canvasWidth / 2 - panOffset.x / scale
+      const centerY = canvasHeight / 2 - panOffset.y / scale
+      const x = centerX - halfWidth - margin
+      const y = centerY - halfHeight - margin
+      const width = (halfWidth + margin) * 2
+      const height = (halfHeight + margin) * 2
+      const coversPage = x <= 0 && y <= 0 && x + width >= canvasWidth && y + height >= canvasHeight
+      wasmEngine.setViewport(coversPage ? null : { x, y, width, height })
+      needsRenderRef.current = true
+    }
+
+    updateViewport()
+    window.addEventListener("resize", updateViewport)
+    return () => window.removeEventListener("resize", updateViewport)
+  }, [wasmReady, scale, panOffset, canvasWidth, canvasHeight])
 
   const handleZoomIn = useCallback(() => {
     onZoomChange(Math.min(zoom + 25, 400))
@@ -425,6 +887,21 @@ export function Viewer({
     return 0.5
   }, [])
 
+  // Ink points keep the stylus state when the tool has a pressure curve;
+  // mouse and touch input stays plain so it draws at uniform width.
+  const getInkPoint = useCallback((e: React.PointerEvent | React.MouseEvent): Point => {
+    const point = getCanvasPoint(e)
+    if (!('pointerType' in e) || e.pointerType !== 'pen') return point
+    if ((getToolSettings(activeTool).pressureCurve ?? "off") === "off") return point
+    return {
+      ...point,
+      pressure: getPointerPressure(e),
+      tiltX: e.tiltX,
+      tiltY: e.tiltY,
+      time: Math.round(e.timeStamp - strokeStartTimeRef.current),
+    }
+  }, [getCanvasPoint, getPointerPressure, getToolSettings, activeTool])
+
   const getStrokeBounds = useCallback((stroke: any): { minX: number, minY: number, maxX: number, maxY: number } | null => {
     if (!stroke || stroke.points.length < 1) return null
     
@@ -456,7 +933,7 @@ export function Viewer({
     const eraserRadius = eraserMode ? 15 : 10
     
     if (wasmReady) {
-      const idx = wasmEngine.hitTest(point.x * scale, point.y * scale, eraserRadius)
+      const idx = wasmEngine.hitTest(point.x, point.y, eraserRadius)
       if (idx >= 0 && idx < pageStrokes.length) {
         return pageStrokes[idx].id
       }
@@ -489,7 +966,7 @@ export function Viewer({
       }
     }
     return null
-  }, [getPageStrokes, currentPage, getStrokeBounds, wasmReady, scale])
+  }, [getPageStrokes, currentPage, getStrokeBounds, wasmReady])
 
   const getResizeCorner = useCallback((point: Point, stroke: any): 'tl' | 'tr' | 'bl' | 'br' | null => {
     const bounds = getStrokeBounds(stroke)
@@ -517,6 +994,8 @@ export function Viewer({
   }, [getStrokeBounds])
 
   const findStrokesInRect = useCallback((start: Point, end: Point): string[] => {
+    if (wasmReady) return wasmEngine.strokesInRect(start, end, selectFullyRef.current)
+
     const pageStrokes = getPageStrokes(currentPage)
     const rectMinX = Math.min(start.x, end.x)
     const rectMinY = Math.min(start.y, end.y)
@@ -562,7 +1041,19 @@ export function Viewer({
     }
     
     return result
-  }, [getPageStrokes, currentPage, getStrokeBounds])
+  }, [getPageStrokes, currentPage, getStrokeBounds, wasmReady])
+
+  // The double click's second press already added a vertex on top of the last one.
+  // With the select tool it opens the text box under the pointer instead.
+  const handleDoubleClick = useCallback((e: React.MouseEvent) => {
+    if (pathAnchors) {
+      finishPath(pathAnchors.slice(0, -1))
+      return
+    }
+    if (activeTool !== "select") return
+    const hitId = findStrokeAtPoint(getCanvasPoint(e))
+    if (hitId && getStrokeById(hitId)?.tool === "textbox") editTextBox(hitId)
+  }, [pathAnchors, finishPath, activeTool, findStrokeAtPoint, getCanvasPoint, getStrokeById, editTextBox])
 
   const startDrawing = useCallback((e: React.PointerEvent | React.MouseEvent) => {
     const point = getCanvasPoint(e)
@@ -571,12 +1062,30 @@ export function Viewer({
     const hasShiftKey = e.shiftKey
     
     if ('pointerType' in e && e.pointerType === 'pen') {
-      setCurrentPressure(e.pressure || 0.5)
       activePointerId.current = e.pointerId
     }
 
+    if (textInput && activeTool !== "text") handleTextSubmit()
+
     if (activeTool === "select") {
-      if (selectedStrokeId) {
+      // The engine owns the selection frame: corners and edges resize, the
+      // knob rotates and anything inside moves the whole selection.
+      if (wasmReady && selectedStrokeIds.length === 1 && !hasShiftKey) {
+        const vertex = wasmEngine.hitTestVertex(point, 8 / scale)
+        if (vertex >= 0 && wasmEngine.beginVertexEdit(vertex)) {
+          setEditingVertex(vertex)
+          return
+        }
+      }
+      if (wasmReady && selectedStrokeIds.length > 0 && !hasShiftKey) {
+        const handle = wasmEngine.hitTestHandle(point, 8 / scale)
+        if (handle && wasmEngine.beginTransform(handle, point)) {
+          setTransformHandle(handle)
+          return
+        }
+      }
+
+      if (!wasmReady && selectedStrokeId) {
         const stroke = getStrokeById(selectedStrokeId)
         const corner = getResizeCorner(point, stroke)
         if (corner) {
@@ -593,6 +1102,13 @@ export function Viewer({
         } else {
           selectStroke(strokeId)
         }
+        if (wasmReady) {
+          wasmEngine.setSelectedIds(hasShiftKey ? [...selectedStrokeIds, strokeId] : [strokeId])
+          if (wasmEngine.beginTransform("move", point)) {
+            setTransformHandle("move")
+          }
+          return
+        }
         const stroke = getStrokeById(strokeId)
         if (stroke && stroke.points.length > 0) {
           setDragOffset({
@@ -605,6 +1121,13 @@ export function Viewer({
         if (!hasShiftKey) {
           clearSelection()
         }
+        // Alt drags a freehand lasso; Ctrl/Cmd only takes strokes wholly inside.
+        selectFullyRef.current = e.ctrlKey || e.metaKey
+        if (e.altKey && wasmReady) {
+          lassoPointsRef.current = [point]
+          wasmEngine.setLasso([point])
+          setIsLassoing(true)
+        }
         setIsRubberBanding(true)
         setRubberBandStart(point)
         setRubberBandEnd(point)
@@ -621,18 +1144,10 @@ export function Viewer({
     if (activeTool === "text") {
       selectStroke(null)
       
-      if (textInput && textInput.value.trim()) {
-        const textSettings = getToolSettings("text")
-        addStroke({
-          points: [textInput.position],
-          color: textSettings.color,
-          thickness: textSettings.thickness,
-          opacity: textSettings.opacity,
-          tool: `text:${textInput.value}`,
-          pageId: currentPage,
-        })
+      if (textInput) {
+        handleTextSubmit()
+        return
       }
-      setTextInput(null)
       
       if (pendingSymbol) {
         setIsDrawing(true)
@@ -640,14 +1155,46 @@ export function Viewer({
         setSymbolEnd(point)
         return
       }
+
+      const hitId = findStrokeAtPoint(point)
+      if (hitId && getStrokeById(hitId)?.tool === "textbox") {
+        editTextBox(hitId)
+        return
+      }
       
-      setTextInput({ position: point, value: "" })
-      setTimeout(() => textInputRef.current?.focus(), 10)
+      const textSettings = getToolSettings("text")
+      openTextBox({
+        position: point,
+        value: "",
+        width: DEFAULT_TEXT_BOX_WIDTH,
+        rotation: 0,
+        color: textSettings.color,
+        fontSize: Math.max(14, textSettings.thickness * 4),
+        style: textBoxStyle(textSettings),
+      })
       return
     }
 
     selectStroke(null)
 
+    // Each click adds a vertex; dragging while placing a curve anchor pulls out
+    // its handles. Double click or Enter finishes, and clicking the first
+    // vertex closes a polygon.
+    if (activeTool === "shapes" && PATH_SHAPES.includes(activeShape)) {
+      const anchors = pathAnchors ?? []
+      const first = anchors[0]?.point
+      if (CLOSED_PATH_SHAPES.includes(activeShape) && anchors.length >= 3 && first && Math.hypot(point.x - first.x, point.y - first.y) <= 8 / scale) {
+        finishPath(anchors)
+        return
+      }
+      setPathAnchors([...anchors, { point, handle: { x: 0, y: 0 } }])
+      pathHandleDragRef.current = activeShape === "bezier"
+      setIsDrawing(true)
+      if (anchors.length === 0) setShapeStart(point)
+      setShapeEnd(point)
+      return
+    }
+
     if (activeTool === "shapes") {
       setIsDrawing(true)
       setShapeStart(point)
@@ -659,7 +1206,7 @@ export function Viewer({
       const strokeId = findStrokeAtPoint(point)
       if (strokeId) {
         const stroke = getStrokeById(strokeId)
-        if (stroke && stroke.tool.startsWith("shape-")) {
+        if (stroke && (stroke.tool.startsWith("shape-") || stroke.tool === "textbox")) {
           updateStroke(strokeId, { fillColor: getToolSettings("fill").color })
         }
       }
@@ -669,17 +1216,22 @@ export function Viewer({
     if (activeTool !== "pen" && activeTool !== "highlighter" && activeTool !== "eraser") return
 
     setIsDrawing(true)
-    setCurrentStroke([point])
-  }, [activeTool, getCanvasPoint, findStrokeAtPoint, selectStroke, getStrokeById, getResizeCorner, selectedStrokeId, textInput, pendingSymbol, onSymbolPlaced, addStroke, getToolSettings, currentPage, addToSelection, clearSelection, updateStroke])
+    strokeStartTimeRef.current = e.timeStamp
+    const inkSettings = getToolSettings(activeTool)
+    wasmEngine.setStabilizer(activeTool === "eraser" ? "off" : inkSettings.stabilizer ?? "off", inkSettings.stabilizerStrength)
+    const inkPoint = getInkPoint(e)
+    lastInkPointRef.current = inkPoint
+    if (activeTool === "eraser" && isPartialErase()) {
+      wasmEngine.beginErase()
+      if (wasmEngine.erasePath([point], inkSettings.thickness / 2) > 0) needsRenderRef.current = true
+    }
+    setCurrentStroke([wasmEngine.stabilizePoint(inkPoint, e.timeStamp) ?? inkPoint])
+  }, [activeTool, activeShape, getCanvasPoint, getInkPoint, findStrokeAtPoint, selectStroke, getStrokeById, getResizeCorner, selectedStrokeId, selectedStrokeIds, scale, textInput, handleTextSubmit, editTextBox, openTextBox, pendingSymbol, onSymbolPlaced, addStroke, getToolSettings, currentPage, addToSelection, clearSelection, updateStroke, pathAnchors, finishPath, wasmReady])
 
   const draw = useCallback((e: React.PointerEvent | React.MouseEvent) => {
     const clientX = e.clientX
     const clientY = e.clientY
     
-    if ('pointerType' in e && e.pointerType === 'pen') {
-      setCurrentPressure(e.pressure || 0.5)
-    }
-    
     if (isPanning) {
       const dx = clientX - lastPanPoint.x
       const dy = clientY - lastPanPoint.y
@@ -691,6 +1243,23 @@ export function Viewer({
     if (isRubberBanding && rubberBandStart) {
       const point = getCanvasPoint(e)
       setRubberBandEnd(point)
+      if (isLassoing) {
+        lassoPointsRef.current.push(point)
+        wasmEngine.appendLassoPoint(point)
+        needsRenderRef.current = true
+      }
+      return
+    }
+
+    if (transformHandle) {
+      wasmEngine.updateTransform(getCanvasPoint(e), e.shiftKey)
+      needsRenderRef.current = true
+      return
+    }
+
+    if (editingVertex !== null) {
+      wasmEngine.updateVertexEdit(getCanvasPoint(e))
+      needsRenderRef.current = true
       return
     }
 
@@ -760,11 +1329,25 @@ export function Viewer({
       return
     }
 
-    if (!isDrawing) return
+    if (!isDrawing) {
+      if (activeTool === "select" && wasmReady) {
+        const hover = getCanvasPoint(e)
+        const onVertex = selectedStrokeIds.length === 1 && wasmEngine.hitTestVertex(hover, 8 / scale) >= 0
+        const handle = onVertex ? "move" : selectedStrokeIds.length > 0 ? wasmEngine.hitTestHandle(hover, 8 / scale) : null
+        if (handle !== hoverHandle) setHoverHandle(handle)
+      }
+      return
+    }
 
     const point = getCanvasPoint(e)
 
     if (activeTool === "shapes") {
+      if (pathAnchors && pathHandleDragRef.current) {
+        const last = pathAnchors[pathAnchors.length - 1]
+        const handle = { x: point.x - last.point.x, y: point.y - last.point.y }
+        setPathAnchors([...pathAnchors.slice(0, -1), { point: last.point, handle }])
+        return
+      }
       setShapeEnd(point)
       return
     }
@@ -774,6 +1357,13 @@ export function Viewer({
       return
     }
 
+    if (activeTool === "eraser" && isPartialErase()) {
+      const last = lastInkPointRef.current ?? point
+      if (wasmEngine.erasePath([last, point], getToolSettings("eraser").thickness / 2) > 0) needsRenderRef.current = true
+      lastInkPointRef.current = point
+      return
+    }
+
     if (activeTool === "eraser") {
       const strokeId = findStrokeAtPoint(point, true)
       if (strokeId) {
@@ -782,8 +1372,37 @@ export function Viewer({
       return
     }
 
-    setCurrentStroke(prev => [...prev, point])
-  }, [isDrawing, isPanning, isDragging, isResizing, isRubberBanding, rubberBandStart, resizeCorner, lastPanPoint, getCanvasPoint, activeTool, pendingSymbol, symbolStart, selectedStrokeId, dragOffset, updateStroke, getStrokeById, findStrokeAtPoint, deleteStroke])
+    if (snappedShapeRef.current) return
+
+    const inkPoint = getInkPoint(e)
+    lastInkPointRef.current = inkPoint
+    const stabilized = wasmEngine.stabilizePoint(inkPoint, e.timeStamp)
+    if (stabilized) setCurrentStroke(prev => [...prev, stabilized])
+
+    const anchor = snapAnchorRef.current
+    if (activeTool === "pen" && getToolSettings("pen").shapeSnap === "hold"
+      && (!anchor || Math.hypot(inkPoint.x - anchor.x, inkPoint.y - anchor.y) > SNAP_HOLD_SLOP / scale)) {
+      clearSnapTimer()
+      snapAnchorRef.current = inkPoint
+      snapTimerRef.current = setTimeout(() => {
+        const shape = wasmEngine.recognizeShape(syncedCurrentStrokeRef.current)
+        if (!shape) return
+        const penSettings = getToolSettings("pen")
+        snappedShapeRef.current = shape
+        wasmEngine.setShapePreview({
+          shape_type: shape.tool.replace("shape-", ""),
+          start: shape.points[0],
+          end: shape.points[1],
+          color: penSettings.color,
+          thickness: penSettings.thickness,
+          opacity: penSettings.opacity,
+          rotation: shape.rotation,
+          points: shape.points,
+        })
+        setCurrentStroke([])
+      }, SNAP_HOLD_MS)
+    }
+  }, [getInkPoint, isDrawing, isPanning, isDragging, isResizing, isRubberBanding, isLassoing, transformHandle, editingVertex, pathAnchors, hoverHandle, rubberBandStart, resizeCorner, lastPanPoint, getCanvasPoint, activeTool, pendingSymbol, symbolStart, selectedStrokeId, selectedStrokeIds, scale, dragOffset, updateStroke, getStrokeById, findStrokeAtPoint, deleteStroke, getToolSettings, wasmReady])
 
   const stopDrawing = useCallback(() => {
     if (isRubberBanding) {
@@ -791,18 +1410,47 @@ export function Viewer({
         const width = Math.abs(rubberBandEnd.x - rubberBandStart.x)
         const height = Math.abs(rubberBandEnd.y - rubberBandStart.y)
         if (width > 5 || height > 5) {
-          const selectedIds = findStrokesInRect(rubberBandStart, rubberBandEnd)
+          const selectedIds = isLassoing
+            ? wasmEngine.strokesInLasso(lassoPointsRef.current, selectFullyRef.current)
+            : findStrokesInRect(rubberBandStart, rubberBandEnd)
           if (selectedIds.length > 0) {
             selectStrokes(selectedIds)
           }
         }
       }
+      if (isLassoing) {
+        wasmEngine.clearLasso()
+        lassoPointsRef.current = []
+        setIsLassoing(false)
+        needsRenderRef.current = true
+      }
       setIsRubberBanding(false)
       setRubberBandStart(null)
       setRubberBandEnd(null)
       return
     }
 
+    if (transformHandle) {
+      transformStrokes(wasmEngine.endTransform().map(s => ({
+        id: s.id,
+        points: s.points,
+        thickness: s.thickness,
+        rotation: s.rotation,
+        textBox: s.text_box && fromWasmTextBox(s.text_box),
+      })))
+      setTransformHandle(null)
+      return
+    }
+
+    if (editingVertex !== null) {
+      const edited = wasmEngine.endVertexEdit()
+      if (edited) {
+        transformStrokes([{ id: edited.id, points: edited.points, thickness: edited.thickness, rotation: edited.rotation }])
+      }
+      setEditingVertex(null)
+      return
+    }
+
     if (isResizing) {
       setIsResizing(false)
       setResizeCorner(null)
@@ -823,17 +1471,17 @@ export function Viewer({
       return
     }
 
+    // Releasing the pointer only ends a Bézier handle drag; the path goes on.
+    if (pathAnchors) {
+      pathHandleDragRef.current = false
+      return
+    }
+
     if (activeTool === "shapes" && shapeStart && shapeEnd) {
-      const shapeSettings = getToolSettings("shapes")
-      addStroke({
-        points: [shapeStart, shapeEnd],
-        color: shapeSettings.borderColor || shapeSettings.color,
-        thickness: shapeSettings.thickness,
-        opacity: shapeSettings.opacity,
-        tool: `shape-${activeShape}`,
-        pageId: currentPage,
-        backgroundColor: shapeSettings.backgroundColor !== "transparent" ? shapeSettings.backgroundColor : undefined,
-      })
+      const size = Math.hypot(shapeEnd.x - shapeStart.x, shapeEnd.y - shapeStart.y)
+      if (!MARKUP_SHAPES.includes(activeShape) || size >= MIN_MARKUP_SIZE) {
+        addShapeStroke(boxShapePoints(activeShape, shapeStart, shapeEnd))
+      }
       setShapeStart(null)
       setShapeEnd(null)
       setIsDrawing(false)
@@ -859,38 +1507,66 @@ export function Viewer({
       return
     }
 
+    clearSnapTimer()
+    if (snappedShapeRef.current) {
+      addRecognizedShape(snappedShapeRef.current)
+      snappedShapeRef.current = null
+      wasmEngine.setShapePreview(null)
+      needsRenderRef.current = true
+      setIsDrawing(false)
+      return
+    }
+
     if (currentStroke.length === 0) {
       setIsDrawing(false)
       return
     }
 
     if (activeTool === "eraser") {
+      if (isPartialErase()) {
+        splitStrokes(wasmEngine.endErase().map(change => ({
+          id: change.id,
+          pieces: change.pieces.map(piece => ({ id: piece.id, points: piece.points })),
+        })))
+      }
       setCurrentStroke([])
       setIsDrawing(false)
       return
     }
 
     const currentToolSettings = getToolSettings(activeTool)
+    const tail = wasmEngine.finishStabilizer(lastInkPointRef.current)
+    const strokePoints = tail ? [...currentStroke, tail] : currentStroke
     
-    const simplifiedPoints = wasmReady && currentStroke.length > 10
-      ? wasmEngine.simplifyPoints(currentStroke, 1.5)
-      : currentStroke
+    const recognized = activeTool === "pen" && currentToolSettings.shapeSnap === "release" ? wasmEngine.recognizeShape(strokePoints) : null
+    if (recognized) {
+      addRecognizedShape(recognized)
+      setCurrentStroke([])
+      setIsDrawing(false)
+      return
+    }
+
+    const simplifiedPoints = wasmReady && strokePoints.length > 10
+      ? wasmEngine.simplifyPoints(strokePoints, 1.5)
+      : strokePoints
+    const fittedPoints = wasmEngine.fitPoints(simplifiedPoints, currentToolSettings.curveFit ?? "none")
     
-    const pressureMultiplier = 0.5 + currentPressure
-    const finalThickness = Math.round(currentToolSettings.thickness * pressureMultiplier)
+    const preset = currentToolSettings.pressureCurve ?? "off"
+    const curve = preset !== "off" && wasmEngine.hasPressure(simplifiedPoints) ? wasmEngine.PRESSURE_CURVES[preset] : undefined
     
     addStroke({
-      points: simplifiedPoints,
+      points: fittedPoints,
       color: currentToolSettings.color,
-      thickness: finalThickness,
+      thickness: currentToolSettings.thickness,
       opacity: currentToolSettings.opacity,
       tool: activeTool,
       pageId: currentPage,
+      pressureCurve: curve ? { gamma: curve.gamma, minWidth: curve.min_width } : undefined,
     })
 
     setCurrentStroke([])
     setIsDrawing(false)
-  }, [isDrawing, isPanning, isDragging, isResizing, currentStroke, activeTool, getToolSettings, currentPage, addStroke, shapeStart, shapeEnd, activeShape, pendingSymbol, symbolStart, symbolEnd, onSymbolPlaced, wasmReady])
+  }, [isDrawing, isPanning, isDragging, isResizing, isRubberBanding, isLassoing, transformHandle, editingVertex, pathAnchors, addShapeStroke, addRecognizedShape, transformStrokes, splitStrokes, rubberBandStart, rubberBandEnd, findStrokesInRect, selectStrokes, currentStroke, activeTool, getToolSettings, currentPage, addStroke, shapeStart, shapeEnd, activeShape, pendingSymbol, symbolStart, symbolEnd, onSymbolPlaced, wasmReady])
 
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
@@ -955,7 +1631,28 @@ export function Viewer({
         selectStrokes(pageStrokes.map(s => s.id))
         return
       }
+      if (key === "enter" && pathAnchors) {
+        e.preventDefault()
+        finishPath(pathAnchors)
+        return
+      }
       if (key === "escape") {
+        if (transformHandle) {
+          wasmEngine.cancelTransform()
+          setTransformHandle(null)
+          needsRenderRef.current = true
+          return
+        }
+        if (editingVertex !== null) {
+          wasmEngine.cancelVertexEdit()
+          setEditingVertex(null)
+          needsRenderRef.current = true
+          return
+        }
+        if (pathAnchors) {
+          cancelPath()
+          return
+        }
         clearSelection()
         return
       }
@@ -963,7 +1660,7 @@ export function Viewer({
     
     window.addEventListener("keydown", handleKeyDown, true)
     return () => window.removeEventListener("keydown", handleKeyDown, true)
-  }, [undo, redo, copySelected, cutSelected, paste, duplicateSelected, deleteSelectedStrokes, currentPage, selectedStrokeIds, activeTool, getPageStrokes, selectStrokes, clearSelection])
+  }, [undo, redo, copySelected, cutSelected, paste, duplicateSelected, deleteSelectedStrokes, currentPage, selectedStrokeIds, activeTool, getPageStrokes, selectStrokes, clearSelection, transformHandle, editingVertex, pathAnchors, finishPath, cancelPath])
 
   const handleWheel = useCallback((e: WheelEvent) => {
     if (e.ctrlKey || e.metaKey) {
@@ -995,9 +1692,16 @@ export function Viewer({
       if (resizeCorner === 'tr' || resizeCorner === 'bl') return "nesw-resize"
     }
     if (isDragging) return "move"
+    const handle = transformHandle ?? (activeTool === "select" ? hoverHandle : null)
+    if (handle) {
+      if (handle === "rotate") {
+        return transformHandle ? "url('/cursors/cursor-grabbing.svg') 12 12, grabbing" : "url('/cursors/cursor-grab.svg') 12 12, grab"
+      }
+      return handle === "move" ? "move" : `${handle}-resize`
+    }
     switch (activeTool) {
       case "select":
-        return selectedStrokeId ? "move" : "url('/cursors/cursor-default.svg') 2 2, auto"
+        return selectedStrokeId && !wasmReady ? "move" : "url('/cursors/cursor-default.svg') 2 2, auto"
       case "pan":
         return isPanning ? "url('/cursors/cursor-grabbing.svg') 12 12, grabbing" : "url('/cursors/cursor-grab.svg') 12 12, grab"
       case "pen":
@@ -1013,7 +1717,7 @@ export function Viewer({
       default:
         return "url('/cursors/cursor-default.svg') 2 2, auto"
     }
-  }, [activeTool, isPanning, pendingSymbol, isDragging, selectedStrokeId, isResizing, resizeCorner, isZooming])
+  }, [activeTool, isPanning, pendingSymbol, isDragging, selectedStrokeId, isResizing, resizeCorner, isZooming, transformHandle, hoverHandle, wasmReady])
 
   return (
     <TooltipProvider delayDuration={0}>
@@ -1132,6 +1836,7 @@ export function Viewer({
           onPointerDown={startDrawing}
           onPointerMove={draw}
           onPointerUp={stopDrawing}
+          onDoubleClick={handleDoubleClick}
           onPointerLeave={stopDrawing}
           onPointerCancel={stopDrawing}
         >
@@ -1144,18 +1849,18 @@ export function Viewer({
           >
             <canvas
               ref={canvasRef}
-              width={canvasWidth}
-              height={canvasHeight}
+              width={Math.round(canvasWidth * pixelRatio)}
+              height={Math.round(canvasHeight * pixelRatio)}
               className={cn(
                 "shadow-2xl transition-shadow duration-300",
                 "dark:shadow-black/50"
               )}
-              style={{ cursor: getCursor() }}
+              style={{ width: canvasWidth, height: canvasHeight, cursor: getCursor() }}
             />
 
             <div className="pointer-events-none absolute -inset-4 rounded-sm border-2 border-dashed border-violet-500/20" />
 
-            {isRubberBanding && rubberBandStart && rubberBandEnd && 
+            {isRubberBanding && !isLassoing && rubberBandStart && rubberBandEnd && 
               (Math.abs(rubberBandEnd.x - rubberBandStart.x) > 5 || Math.abs(rubberBandEnd.y - rubberBandStart.y) > 5) && (
               <div
                 className="pointer-events-none absolute rounded-sm border border-dashed border-sky-500 bg-sky-500/15"
@@ -1174,32 +1879,113 @@ export function Viewer({
             </div>
 
             {textInput && (
-              <input
-                ref={textInputRef}
-                type="text"
-                value={textInput.value}
-                onChange={(e) => setTextInput({ ...textInput, value: e.target.value })}
-                onKeyDown={(e) => {
-                  e.stopPropagation()
-                  if (e.key === "Enter") {
-                    handleTextSubmit()
-                  }
-                  if (e.key === "Escape") {
-                    setTextInput(null)
-                  }
-                }}
-                onMouseDown={(e) => e.stopPropagation()}
-                className="absolute border-0 bg-transparent outline-none caret-current"
+              <div
+                className="absolute"
                 style={{
                   left: textInput.position.x,
-                  top: textInput.position.y - 8,
-                  fontSize: Math.max(16, getToolSettings("text").thickness * 5),
-                  color: getToolSettings("text").color,
-                  minWidth: 20,
-                  width: Math.max(20, textInput.value.length * 12 + 20),
+                  top: textInput.position.y,
+                  transform: `rotate(${textInput.rotation}rad)`,
+                  transformOrigin: "top left",
                 }}
-                autoFocus
-              />
+                onPointerDown={(e) => e.stopPropagation()}
+                onDoubleClick={(e) => e.stopPropagation()}
+              >
+                <div
+                  className="absolute bottom-full left-0 mb-2 flex items-center gap-0.5 rounded-full border bg-background/95 p-1 shadow-lg backdrop-blur-xl"
+                  onMouseDown={(e) => e.preventDefault()}
+                >
+                  {(Object.keys(TEXT_BOX_FONTS) as wasmEngine.FontFamily[]).map((family) => (
+                    <button
+                      key={family}
+                      onClick={() => setTextInput({ ...textInput, style: { ...textInput.style, fontFamily: family } })}
+                      className={cn(
+                        "rounded-full px-2 py-1 text-xs capitalize transition-colors",
+                        textInput.style.fontFamily === family ? "bg-violet-500 text-white" : "hover:bg-muted"
+                      )}
+                      style={{ fontFamily: TEXT_BOX_FONTS[family] }}
+                    >
+                      {family === "handwriting" ? "Hand" : family}
+                    </button>
+                  ))}
+                  <div className="mx-1 h-4 w-px bg-border" />
+                  {([
+                    ["bold", Bold],
+                    ["italic", Italic],
+                    ["underline", Underline],
+                  ] as const).map(([key, Icon]) => (
+                    <button
+                      key={key}
+                      onClick={() => setTextInput({ ...textInput, style: { ...textInput.style, [key]: !textInput.style[key] } })}
+                      className={cn(
+                        "rounded-full p-1.5 transition-colors",
+                        textInput.style[key] ? "bg-violet-500 text-white" : "hover:bg-muted"
+                      )}
+                    >
+                      <Icon className="h-3.5 w-3.5" />
+                    </button>
+                  ))}
+                  <div className="mx-1 h-4 w-px bg-border" />
+                  {([
+                    ["left", AlignLeft],
+                    ["center", AlignCenter],
+                    ["right", AlignRight],
+                  ] as const).map(([align, Icon]) => (
+                    <button
+                      key={align}
+                      onClick={() => setTextInput({ ...textInput, style: { ...textInput.style, align } })}
+                      className={cn(
+                        "rounded-full p-1.5 transition-colors",
+                        textInput.style.align === align ? "bg-violet-500 text-white" : "hover:bg-muted"
+                      )}
+                    >
+                      <Icon className="h-3.5 w-3.5" />
+                    </button>
+                  ))}
+                  <div className="mx-1 h-4 w-px bg-border" />
+                  {LINE_SPACINGS.map((spacing) => (
+                    <button
+                      key={spacing}
+                      onClick={() => setTextInput({ ...textInput, style: { ...textInput.style, lineSpacing: spacing } })}
+                      className={cn(
+                        "rounded-full px-2 py-1 text-xs transition-colors",
+                        textInput.style.lineSpacing === spacing ? "bg-violet-500 text-white" : "hover:bg-muted"
+                      )}
+                    >
+                      {spacing}×
+                    </button>
+                  ))}
+                </div>
+                <textarea
+                  ref={textInputRef}
+                  value={textInput.value}
+                  rows={Math.max(1, textInput.value.split("\n").length)}
+                  onChange={(e) => setTextInput({ ...textInput, value: e.target.value })}
+                  onKeyDown={(e) => {
+                    e.stopPropagation()
+                    if (e.key === "Enter" && (e.ctrlKey || e.metaKey)) {
+                      e.preventDefault()
+                      handleTextSubmit()
+                    }
+                    if (e.key === "Escape") {
+                      cancelTextBox()
+                    }
+                  }}
+                  className="block resize-none overflow-hidden rounded-sm border border-dashed border-violet-500/60 bg-transparent outline-none caret-current"
+                  style={{
+                    width: textInput.width,
+                    padding: TEXT_BOX_PADDING,
+                    fontFamily: TEXT_BOX_FONTS[textInput.style.fontFamily],
+                    fontSize: textInput.fontSize,
+                    fontWeight: textInput.style.bold ? "bold" : "normal",
+                    fontStyle: textInput.style.italic ? "italic" : "normal",
+                    textDecoration: textInput.style.underline ? "underline" : "none",
+                    textAlign: textInput.style.align,
+                    lineHeight: textInput.style.lineSpacing,
+                    color: textInput.color,
+                  }}
+                  autoFocus
+                />
+              </div>
             )}
           </div>
 
@@ -2,6 +2,7 @@
 
 import { create } from "zustand"
 import { persist } from "zustand/middleware"
+import type { DashStyle, FontFamily, LineCap, LineJoin, LineMarker, TextAlign } from "@/lib/wasm-engine"
 
 interface PdfPageMeta {
   pageNumber: number
@@ -32,9 +33,12 @@ interface PdfStore {
   setLoading: (loading: boolean) => void
   setLoadingPage: (page: number | null) => void
   setError: (error: string | null) => void
+  clearRenderedPages: () => void
   clearPdf: () => void
 }
 
+const MAX_RENDERED_PAGES = 12
+
 export const usePdfStore = create<PdfStore>()((set, get) => ({
   pdfPath: null,
   pagesMeta: [],
@@ -47,7 +51,14 @@ export const usePdfStore = create<PdfStore>()((set, get) => ({
   setPagesMeta: (pages) => set({ pagesMeta: pages, totalPages: pages.length }),
   setRenderedPage: (pageNumber, imageData) => {
     const newMap = new Map(get().renderedPages)
+    newMap.delete(pageNumber)
     newMap.set(pageNumber, imageData)
+    // The backend keeps the larger page cache; only hold a few decoded pages here.
+    while (newMap.size > MAX_RENDERED_PAGES) {
+      const oldest = newMap.keys().next().value
+      if (oldest === undefined) break
+      newMap.delete(oldest)
+    }
     set({ renderedPages: newMap })
   },
   getRenderedPage: (pageNumber) => get().renderedPages.get(pageNumber),
@@ -55,9 +66,66 @@ export const usePdfStore = create<PdfStore>()((set, get) => ({
   setLoading: (loading) => set({ isLoading: loading }),
   setLoadingPage: (page) => set({ loadingPage: page }),
   setError: (error) => set({ error }),
+  clearRenderedPages: () => set({ renderedPages: new Map() }),
   clearPdf: () => set({ pdfPath: null, pagesMeta: [], renderedPages: new Map(), totalPages: 0, error: null }),
 }))
 
+export type PaperTemplate =
+  | "blank"
+  | "ruled"
+  | "college-ruled"
+  | "grid"
+  | "dot-grid"
+  | "isometric"
+  | "music-staff"
+  | "cornell"
+
+export interface PageSizePreset {
+  name: string
+  width: number
+  height: number
+}
+
+// Sizes in PDF points (1/72 inch), portrait.
+export const PAGE_SIZE_PRESETS: PageSizePreset[] = [
+  { name: "A4", width: 595, height: 842 },
+  { name: "A5", width: 420, height: 595 },
+  { name: "Letter", width: 612, height: 792 },
+  { name: "Legal", width: 612, height: 1008 },
+]
+
+export const PAPER_TEMPLATES: { value: PaperTemplate; label: string }[] = [
+  { value: "blank", label: "Blank" },
+  { value: "ruled", label: "Ruled" },
+  { value: "college-ruled", label: "College Ruled" },
+  { value: "grid", label: "Grid" },
+  { value: "dot-grid", label: "Dot Grid" },
+  { value: "isometric", label: "Isometric" },
+  { value: "music-staff", label: "Music Staff" },
+  { value: "cornell", label: "Cornell Notes" },
+]
+
+export interface NotebookSettings {
+  page_width: number
+  page_height: number
+  page_count: number
+  template: PaperTemplate
+}
+
+interface NotebookStore extends NotebookSettings {
+  setNotebook: (settings: NotebookSettings) => void
+  setTemplate: (template: PaperTemplate) => void
+}
+
+export const useNotebookStore = create<NotebookStore>()((set) => ({
+  page_width: 595,
+  page_height: 842,
+  page_count: 1,
+  template: "grid",
+  setNotebook: (settings) => set(settings),
+  setTemplate: (template) => set({ template }),
+}))
+
 interface ColorStore {
   customColors: string[]
   addCustomColor: (color: string) => void
@@ -91,11 +159,15 @@ export const useColorStore = create<ColorStore>()(
   )
 )
 
+type PageRenderMode = "normal" | "inverted" | "grayscale" | "sepia" | "high-contrast"
+
 interface SettingsStore {
   sidebarCollapsed: boolean
   inspectorCollapsed: boolean
+  pageRenderMode: PageRenderMode
   setSidebarCollapsed: (collapsed: boolean) => void
   setInspectorCollapsed: (collapsed: boolean) => void
+  setPageRenderMode: (mode: PageRenderMode) => void
 }
 
 export const useSettingsStore = create<SettingsStore>()(
@@ -103,8 +175,10 @@ export const useSettingsStore = create<SettingsStore>()(
     (set) => ({
       sidebarCollapsed: false,
       inspectorCollapsed: false,
+      pageRenderMode: "normal",
       setSidebarCollapsed: (collapsed: boolean) => set({ sidebarCollapsed: collapsed }),
       setInspectorCollapsed: (collapsed: boolean) => set({ inspectorCollapsed: collapsed }),
+      setPageRenderMode: (mode: PageRenderMode) => set({ pageRenderMode: mode }),
     }),
     {
       name: "annotate-studio-settings",
@@ -115,6 +189,20 @@ export const useSettingsStore = create<SettingsStore>()(
 interface Point {
   x: number
   y: number
+  pressure?: number
+  tiltX?: number
+  tiltY?: number
+  time?: number
+}
+
+export interface TextBoxStyle {
+  fontFamily: FontFamily
+  bold: boolean
+  italic: boolean
+  underline: boolean
+  align: TextAlign
+  lineSpacing: number
+  borderColor?: string
 }
 
 interface Stroke {
@@ -128,6 +216,10 @@ interface Stroke {
   timestamp: number
   fillColor?: string
   backgroundColor?: string
+  rotation?: number
+  pressureCurve?: { gamma: number; minWidth: number }
+  lineStyle?: { dash: DashStyle; cap: LineCap; join: LineJoin; startMarker: LineMarker; endMarker: LineMarker }
+  textBox?: TextBoxStyle & { content: string; width: number }
 }
 
 interface CanvasStore {
@@ -140,7 +232,9 @@ interface CanvasStore {
   clipboard: Stroke[]
   
   addStroke: (stroke: Omit<Stroke, "id" | "timestamp">) => void
-  updateStroke: (id: string, updates: Partial<Pick<Stroke, "points" | "thickness" | "color" | "fillColor" | "backgroundColor">>) => void
+  updateStroke: (id: string, updates: Partial<Pick<Stroke, "points" | "thickness" | "color" | "fillColor" | "backgroundColor" | "textBox">>) => void
+  transformStrokes: (updates: Array<Pick<Stroke, "id" | "points" | "thickness" | "rotation" | "textBox">>) => void
+  splitStrokes: (changes: Array<{ id: string; pieces: Array<Pick<Stroke, "id" | "points">> }>) => void
   deleteStroke: (id: string) => void
   deleteSelectedStrokes: () => void
   selectStroke: (id: string | null) => void
@@ -195,6 +289,34 @@ export const useCanvasStore = create<CanvasStore>()((set, get) => ({
     }))
   },
 
+  transformStrokes: (updates) => {
+    if (updates.length === 0) return
+    const byId = new Map(updates.map((u) => [u.id, u]))
+    set((state) => ({
+      strokes: state.strokes.map((s) => {
+        const update = byId.get(s.id)
+        return update ? { ...s, points: update.points, thickness: update.thickness, rotation: update.rotation || undefined, textBox: update.textBox ?? s.textBox } : s
+      }),
+      undoStack: [...state.undoStack, state.strokes],
+      redoStack: [],
+    }))
+  },
+
+  splitStrokes: (changes) => {
+    if (changes.length === 0) return
+    const byId = new Map(changes.map((c) => [c.id, c.pieces]))
+    set((state) => ({
+      strokes: state.strokes.flatMap((s) => {
+        const pieces = byId.get(s.id)
+        return pieces ? pieces.map((p) => ({ ...s, id: p.id, points: p.points })) : [s]
+      }),
+      undoStack: [...state.undoStack, state.strokes],
+      redoStack: [],
+      selectedStrokeId: state.selectedStrokeId && byId.has(state.selectedStrokeId) ? null : state.selectedStrokeId,
+      selectedStrokeIds: state.selectedStrokeIds.filter((sid) => !byId.has(sid)),
+    }))
+  },
+
   deleteStroke: (id) => {
     set((state) => ({
       strokes: state.strokes.filter((s) => s.id !== id),
@@ -1,12 +1,26 @@
 "use client"
 
+import type { NotebookSettings } from "@/lib/store"
+
 export interface PdfPageInfo {
   page_number: number
   width: number
   height: number
+  pixel_width: number
+  pixel_height: number
+  scale: number
+  dpi: number
   image_data: string
 }
 
+export type RenderMode = "normal" | "inverted" | "grayscale" | "sepia" | "high-contrast"
+
+export interface RenderOptions {
+  zoom?: number
+  device_pixel_ratio?: number
+  mode?: RenderMode
+}
+
 export interface PdfPageMeta {
   page_number: number
   width: number
@@ -17,6 +31,7 @@ export interface PdfOpenedEvent {
   path: string
   page_count: number
   pages_meta: PdfPageMeta[]
+  document_kind: "pdf" | "image"
 }
 
 export async function isTauri(): Promise<boolean> {
@@ -36,10 +51,18 @@ export async function openPdfDialog(): Promise<string | null> {
   const selected = await open({
     multiple: false,
     filters: [
+      {
+        name: "Documents",
+        extensions: ["pdf", "png", "jpg", "jpeg", "webp", "tif", "tiff"],
+      },
       {
         name: "PDF Files",
         extensions: ["pdf"],
       },
+      {
+        name: "Images",
+        extensions: ["png", "jpg", "jpeg", "webp", "tif", "tiff"],
+      },
     ],
   })
 
@@ -71,7 +94,8 @@ export async function openPdf(path: string): Promise<PdfOpenedEvent | null> {
 export async function renderPdfPage(
   path: string,
   pageNumber: number,
-  width?: number
+  width?: number,
+  options?: RenderOptions
 ): Promise<PdfPageInfo | null> {
   const tauriAvailable = await isTauri()
   if (!tauriAvailable) {
@@ -86,6 +110,7 @@ export async function renderPdfPage(
       path,
       pageNumber,
       width: width || null,
+      options: options || null,
     })
     return result
   } catch (error) {
@@ -98,6 +123,7 @@ export interface ProjectData {
   version: string
   pdf_path: string | null
   strokes: string
+  notebook?: NotebookSettings | null
 }
 
 export async function saveProjectDialog(): Promise<string | null> {
@@ -117,12 +143,17 @@ export async function saveProjectDialog(): Promise<string | null> {
   return selected
 }
 
-export async function saveProject(path: string, pdfPath: string | null, strokesJson: string): Promise<void> {
+export async function saveProject(
+  path: string,
+  pdfPath: string | null,
+  strokesJson: string,
+  notebook?: NotebookSettings | null
+): Promise<void> {
   const tauriAvailable = await isTauri()
   if (!tauriAvailable) return
 
   const { invoke } = await import("@tauri-apps/api/core")
-  await invoke("save_project", { path, pdfPath, strokesJson })
+  await invoke("save_project", { path, pdfPath, strokesJson, notebook: notebook ?? null })
 }
 
 export async function loadProject(path: string): Promise<ProjectData | null> {
@@ -165,10 +196,242 @@ export interface ExportPdfPage {
   height: number
 }
 
-export async function exportToPdf(path: string, pages: ExportPdfPage[]): Promise<void> {
+export interface ExportPdfOptions {
+  title?: string
+  author?: string
+  subject?: string
+  keywords?: string[]
+  source_pdf_path?: string | null
+  inherit_metadata?: boolean
+}
+
+export async function exportToPdf(path: string, pages: ExportPdfPage[], options?: ExportPdfOptions): Promise<void> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return
+
+  const { invoke } = await import("@tauri-apps/api/core")
+  await invoke("export_to_pdf", { path, pages, options: options || null })
+}
+
+export interface PdfDocumentMetadata {
+  title: string | null
+  author: string | null
+  subject: string | null
+  keywords: string | null
+  creator: string | null
+  producer: string | null
+  creation_date: string | null
+  modification_date: string | null
+  xmp: string | null
+}
+
+export async function getPdfMetadata(path: string): Promise<PdfDocumentMetadata | null> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return null
+
+  const { invoke } = await import("@tauri-apps/api/core")
+  return await invoke<PdfDocumentMetadata>("get_pdf_metadata", { path })
+}
+
+export interface PdfBackendInfo {
+  name: string
+  fallback_reason: string | null
+}
+
+export async function getPdfBackend(): Promise<PdfBackendInfo | null> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return null
+
+  const { invoke } = await import("@tauri-apps/api/core")
+  return await invoke<PdfBackendInfo>("get_pdf_backend")
+}
+
+export interface ThumbnailBatch {
+  document_hash: string
+  pages: number[]
+  width: number
+}
+
+export interface ThumbnailRendered {
+  document_hash: string
+  page_number: number
+  width: number
+  height: number
+  image_data: string
+  cached: boolean
+}
+
+export interface ThumbnailsComplete {
+  document_hash: string
+  rendered: number
+  failed: number[]
+}
+
+export async function renderThumbnails(
+  path: string,
+  startPage: number,
+  endPage: number,
+  width?: number
+): Promise<ThumbnailBatch | null> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return null
+
+  const { invoke } = await import("@tauri-apps/api/core")
+  return await invoke<ThumbnailBatch>("render_thumbnails", {
+    path,
+    startPage,
+    endPage,
+    width: width || null,
+  })
+}
+
+export async function onThumbnailRendered(
+  handler: (thumbnail: ThumbnailRendered) => void
+): Promise<() => void> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return () => {}
+
+  const { listen } = await import("@tauri-apps/api/event")
+  return await listen<ThumbnailRendered>("thumbnail-rendered", (event) => handler(event.payload))
+}
+
+export async function onThumbnailsComplete(
+  handler: (summary: ThumbnailsComplete) => void
+): Promise<() => void> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return () => {}
+
+  const { listen } = await import("@tauri-apps/api/event")
+  return await listen<ThumbnailsComplete>("thumbnails-complete", (event) => handler(event.payload))
+}
+
+export type RenderPriority = "visible" | "adjacent" | "prefetch"
+
+export interface PageRenderRequest {
+  page_number: number
+  priority: RenderPriority
+}
+
+export interface PageRenderComplete {
+  request_id: number
+  path: string
+  page_number: number
+  width: number
+  height: number
+  pixel_width: number
+  pixel_height: number
+  scale: number
+  dpi: number
+  image_data: string
+}
+
+export interface PageRenderFailed {
+  request_id: number
+  path: string
+  page_number: number
+  error: string
+}
+
+export interface PageRenderCancelled {
+  request_id: number
+  path: string
+  page_number: number
+}
+
+export async function schedulePageRenders(
+  path: string,
+  requests: PageRenderRequest[],
+  width?: number,
+  options?: RenderOptions
+): Promise<number[]> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return []
+
+  const { invoke } = await import("@tauri-apps/api/core")
+  return await invoke<number[]>("schedule_page_renders", {
+    path,
+    requests,
+    width: width || null,
+    options: options || null,
+  })
+}
+
+export async function cancelPageRenders(requestIds: number[]): Promise<number> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable || requestIds.length === 0) return 0
+
+  const { invoke } = await import("@tauri-apps/api/core")
+  return await invoke<number>("cancel_page_renders", { requestIds })
+}
+
+export async function cancelAllPageRenders(path?: string): Promise<number> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return 0
+
+  const { invoke } = await import("@tauri-apps/api/core")
+  return await invoke<number>("cancel_all_page_renders", { path: path || null })
+}
+
+export async function onPageRenderComplete(
+  handler: (result: PageRenderComplete) => void
+): Promise<() => void> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return () => {}
+
+  const { listen } = await import("@tauri-apps/api/event")
+  return await listen<PageRenderComplete>("page-render-complete", (event) => handler(event.payload))
+}
+
+export async function onPageRenderFailed(
+  handler: (result: PageRenderFailed) => void
+): Promise<() => void> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return () => {}
+
+  const { listen } = await import("@tauri-apps/api/event")
+  return await listen<PageRenderFailed>("page-render-failed", (event) => handler(event.payload))
+}
+
+export async function onPageRenderCancelled(
+  handler: (result: PageRenderCancelled) => void
+): Promise<() => void> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return () => {}
+
+  const { listen } = await import("@tauri-apps/api/event")
+  return await listen<PageRenderCancelled>("page-render-cancelled", (event) => handler(event.payload))
+}
+
+export interface PageCacheStats {
+  entries: number
+  used_bytes: number
+  max_bytes: number
+  hits: number
+  misses: number
+  evictions: number
+  hit_rate: number
+}
+
+export async function getPageCacheStats(): Promise<PageCacheStats | null> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return null
+
+  const { invoke } = await import("@tauri-apps/api/core")
+  return await invoke<PageCacheStats>("get_page_cache_stats")
+}
+
+export async function setPageCacheLimit(maxBytes: number): Promise<PageCacheStats | null> {
+  const tauriAvailable = await isTauri()
+  if (!tauriAvailable) return null
+
+  const { invoke } = await import("@tauri-apps/api/core")
+  return await invoke<PageCacheStats>("set_page_cache_limit", { maxBytes })
+}
+
+export async function clearPageCache(): Promise<void> {
   const tauriAvailable = await isTauri()
   if (!tauriAvailable) return
 
   const { invoke } = await import("@tauri-apps/api/core")
-  await invoke("export_to_pdf", { path, pages })
+  await invoke("clear_page_cache")
 }
@@ -1,11 +1,71 @@
 let wasmModule: typeof import("wasm-renderer") | null = null
 let engine: InstanceType<typeof import("wasm-renderer").RenderEngine> | null = null
+let viewport: [number, number, number, number] | null = null
+let fullFrameWaiters: (() => void)[] = []
 
 export interface Point {
   x: number
   y: number
+  pressure?: number
+  tiltX?: number
+  tiltY?: number
+  time?: number
 }
 
+export interface PressureCurve {
+  gamma: number
+  min_width: number
+}
+
+export type PressureCurvePreset = "off" | "soft" | "linear" | "firm"
+
+/** Width response of each pressure preset; "off" records no pressure at all. */
+export const PRESSURE_CURVES: Record<Exclude<PressureCurvePreset, "off">, PressureCurve> = {
+  soft: { gamma: 0.6, min_width: 0.35 },
+  linear: { gamma: 1, min_width: 0.2 },
+  firm: { gamma: 1.8, min_width: 0.1 },
+}
+
+export type StabilizerMode = "off" | "moving_average" | "one_euro" | "lazy_brush"
+export type CurveFit = "none" | "catmull_rom" | "chaikin"
+export type DashStyle = "solid" | "dashed" | "dotted" | "dash_dot"
+export type LineCap = "round" | "butt" | "square"
+export type LineJoin = "round" | "miter" | "bevel"
+export type LineMarker = "none" | "open_arrow" | "triangle" | "circle" | "square" | "bar"
+
+export interface LineStyle {
+  dash: DashStyle
+  cap: LineCap
+  join: LineJoin
+  start_marker: LineMarker
+  end_marker: LineMarker
+}
+
+export type FontFamily = "sans" | "serif" | "mono" | "handwriting"
+export type TextAlign = "left" | "center" | "right"
+
+// Content and formatting of a "textbox" stroke, whose first point is the
+// box's top-left corner. Font size comes from thickness, background from
+// fill_color.
+export interface TextBox {
+  content: string
+  width: number
+  font_family: FontFamily
+  bold: boolean
+  italic: boolean
+  underline: boolean
+  align: TextAlign
+  line_spacing: number
+  border_color?: string
+}
+
+// "partial" cuts ink along the eraser path, "stroke" deletes whole strokes.
+export type EraserMode = "partial" | "stroke"
+
+// When a pen sketch is swapped for a clean shape: after lifting the pen, or
+// after holding it still at the end of the stroke.
+export type ShapeSnap = "off" | "release" | "hold"
+
 export interface Stroke {
   id: string
   points: Point[]
@@ -14,8 +74,14 @@ export interface Stroke {
   opacity: number
   tool: string
   fill_color?: string
+  rotation?: number
+  pressure_curve?: PressureCurve
+  text_box?: TextBox
+  line_style?: LineStyle
 }
 
+export type TransformHandle = "move" | "rotate" | "nw" | "n" | "ne" | "e" | "se" | "s" | "sw" | "w"
+
 export interface ShapePreview {
   shape_type: string
   start: Point
@@ -24,6 +90,11 @@ export interface ShapePreview {
   thickness: number
   opacity: number
   fill_color?: string
+  line_style?: LineStyle
+  rotation?: number
+  // Every point of a path shape, or a callout/bubble box plus its tail;
+  // replaces start/end.
+  points?: Point[]
 }
 
 export interface SymbolPreview {
@@ -38,6 +109,8 @@ export interface StrokeStyle {
   color: string
   thickness: number
   opacity: number
+  pressure_curve?: PressureCurve
+  tool?: string
 }
 
 export async function initWasm(): Promise<boolean> {
@@ -70,6 +143,68 @@ export function setStrokes(strokes: Stroke[]): void {
   engine.set_strokes(JSON.stringify(strokes))
 }
 
+export function addStroke(stroke: Stroke): void {
+  if (!engine) return
+  engine.add_stroke(JSON.stringify(stroke))
+}
+
+export function updateStroke(stroke: Stroke): void {
+  if (!engine) return
+  engine.update_stroke(JSON.stringify(stroke))
+}
+
+export function removeStroke(id: string): void {
+  if (!engine) return
+  engine.remove_stroke(id)
+}
+
+export function moveStrokes(ids: string[], dx: number, dy: number): void {
+  if (!engine) return
+  engine.move_strokes(JSON.stringify(ids), dx, dy)
+}
+
+export function reorderStroke(id: string, index: number): void {
+  if (!engine) return
+  engine.reorder_stroke(id, index)
+}
+
+/**
+ * Brings the engine from `previous` to `next` by sending only the strokes that
+ * were added, removed or replaced. Unchanged strokes are detected by identity,
+ * so callers must pass immutable stroke objects.
+ */
+export function syncStrokes<T extends { id: string }>(
+  previous: readonly T[],
+  next: readonly T[],
+  toStroke: (stroke: T) => Stroke
+): void {
+  if (!engine) return
+
+  const previousById = new Map(previous.map(s => [s.id, s]))
+  const nextIds = new Set(next.map(s => s.id))
+  const order = previous.map(s => s.id).filter(id => nextIds.has(id))
+
+  for (const stroke of previous) {
+    if (!nextIds.has(stroke.id)) engine.remove_stroke(stroke.id)
+  }
+  for (const stroke of next) {
+    const before = previousById.get(stroke.id)
+    if (!before) {
+      engine.add_stroke(JSON.stringify(toStroke(stroke)))
+      order.push(stroke.id)
+    } else if (before !== stroke) {
+      engine.update_stroke(JSON.stringify(toStroke(stroke)))
+    }
+  }
+
+  next.forEach((stroke, index) => {
+    if (order[index] === stroke.id) return
+    engine!.reorder_stroke(stroke.id, index)
+    order.splice(order.indexOf(stroke.id), 1)
+    order.splice(index, 0, stroke.id)
+  })
+}
+
 export function setCurrentStroke(points: Point[], style: StrokeStyle | null): void {
   if (!engine) return
   engine.set_current_stroke(
@@ -78,6 +213,105 @@ export function setCurrentStroke(points: Point[], style: StrokeStyle | null): vo
   )
 }
 
+/** Packs points into interleaved `[x0, y0, x1, y1, ...]` coordinates. */
+export function toCoords(points: readonly Point[]): Float64Array {
+  const coords = new Float64Array(points.length * 2)
+  points.forEach((p, i) => {
+    coords[i * 2] = p.x
+    coords[i * 2 + 1] = p.y
+  })
+  return coords
+}
+
+/** Packs pen points into interleaved `[x0, y0, pressure0, ...]` samples. */
+export function toSamples(points: readonly Point[]): Float64Array {
+  const samples = new Float64Array(points.length * 3)
+  points.forEach((p, i) => {
+    samples[i * 3] = p.x
+    samples[i * 3 + 1] = p.y
+    samples[i * 3 + 2] = p.pressure ?? 0.5
+  })
+  return samples
+}
+
+export function hasPressure(points: readonly Point[]): boolean {
+  return points.some(p => p.pressure !== undefined)
+}
+
+export function fromCoords(coords: ArrayLike<number>): Point[] {
+  const points: Point[] = []
+  for (let i = 0; i + 1 < coords.length; i += 2) {
+    points.push({ x: coords[i], y: coords[i + 1] })
+  }
+  return points
+}
+
+export function setCurrentPoints(coords: Float64Array | Float32Array): void {
+  if (!engine) return
+  if (coords instanceof Float32Array) engine.set_current_points_f32(coords)
+  else engine.set_current_points(coords)
+}
+
+export function appendCurrentPoint(point: Point): void {
+  if (engine) engine.append_current_point(point.x, point.y)
+}
+
+export function appendCurrentPoints(points: readonly Point[]): void {
+  if (!engine) return
+  if (hasPressure(points)) engine.append_current_samples(toSamples(points))
+  else if (points.length === 1) engine.append_current_point(points[0].x, points[0].y)
+  else engine.append_current_points(toCoords(points))
+}
+
+export function setCurrentSamples(points: readonly Point[]): void {
+  if (engine) engine.set_current_samples(toSamples(points))
+}
+
+export function setCurrentStyle(style: StrokeStyle): void {
+  if (!engine) return
+  engine.set_current_style(style.color, style.thickness, style.opacity)
+  if (style.pressure_curve) {
+    engine.set_current_pressure_curve(style.pressure_curve.gamma, style.pressure_curve.min_width)
+  }
+  if (style.tool) engine.set_current_tool(style.tool)
+}
+
+/**
+ * Configures live stabilization for the next stroke. `strength` runs from 1
+ * (light) to 10 (heavy) and is translated into each filter's parameters.
+ */
+export function setStabilizer(mode: StabilizerMode, strength: number = 5): void {
+  if (!engine) return
+  const s = Math.min(10, Math.max(1, strength))
+  const config =
+    mode === "moving_average" ? { mode, window: s + 1 }
+    : mode === "one_euro" ? { mode, min_cutoff: 2 / s, beta: 0.05 / s }
+    : mode === "lazy_brush" ? { mode, radius: s * 3 }
+    : { mode: "off" }
+  engine.set_stabilizer(JSON.stringify(config))
+}
+
+/**
+ * Runs a raw input point through the stabilizer. Returns the point to append,
+ * keeping the raw point's pressure and tilt, or null while the pen is held back.
+ */
+export function stabilizePoint(point: Point, time: number): Point | null {
+  if (!engine) return point
+  const filtered = engine.stabilize_point(point.x, point.y, time)
+  return filtered.length === 2 ? { ...point, x: filtered[0], y: filtered[1] } : null
+}
+
+/** Returns a last point that brings a lagging filter up to `last`, if needed. */
+export function finishStabilizer(last: Point | undefined): Point | null {
+  if (!engine) return null
+  const tail = engine.finish_stabilizer()
+  return tail.length === 2 ? { ...last, x: tail[0], y: tail[1] } : null
+}
+
+export function clearCurrentStroke(): void {
+  if (engine) engine.clear_current_stroke()
+}
+
 export function setShapePreview(preview: ShapePreview | null): void {
   if (!engine) return
   engine.set_shape_preview(preview ? JSON.stringify(preview) : "")
@@ -98,6 +332,66 @@ export function setSelectedIds(ids: string[]): void {
   engine.set_selected_ids(JSON.stringify(ids))
 }
 
+export function setAnnotationColorMode(mode: "normal" | "inverted"): void {
+  if (!engine) return
+  engine.set_annotation_color_mode(mode)
+}
+
+export function setPaperTemplate(template: string): void {
+  if (!engine) return
+  engine.set_paper_template(template)
+}
+
+/** Culls drawing to the visible part of the page; `null` draws everything. */
+export function setViewport(rect: { x: number; y: number; width: number; height: number } | null): void {
+  viewport = rect ? [rect.x, rect.y, rect.width, rect.height] : null
+  if (!engine) return
+  if (viewport) engine.set_viewport(...viewport)
+  else engine.clear_viewport()
+}
+
+/**
+ * Resolves once the next frame has been drawn without viewport culling, so
+ * the canvas can be captured for export.
+ */
+export function renderFullFrame(): Promise<void> {
+  if (!engine) return Promise.resolve()
+  return new Promise(resolve => fullFrameWaiters.push(resolve))
+}
+
+export function hasPendingFullFrame(): boolean {
+  return fullFrameWaiters.length > 0
+}
+
+/** Ids of strokes in the marquee; `fully` requires them to be wholly inside. */
+export function strokesInRect(start: Point, end: Point, fully: boolean = false): string[] {
+  if (!engine) return []
+  return engine.strokes_in_rect(
+    Math.min(start.x, end.x),
+    Math.min(start.y, end.y),
+    Math.abs(end.x - start.x),
+    Math.abs(end.y - start.y),
+    fully
+  )
+}
+
+export function strokesInLasso(points: readonly Point[], fully: boolean = false): string[] {
+  if (!engine || points.length < 3) return []
+  return engine.strokes_in_lasso(toCoords(points), fully)
+}
+
+export function setLasso(points: readonly Point[]): void {
+  if (engine) engine.set_lasso(toCoords(points))
+}
+
+export function appendLassoPoint(point: Point): void {
+  if (engine) engine.append_lasso_point(point.x, point.y)
+}
+
+export function clearLasso(): void {
+  if (engine) engine.clear_lasso()
+}
+
 export function recordFrame(time: number): void {
   if (engine) engine.record_frame(time)
 }
@@ -109,7 +403,17 @@ export function getFps(): number {
 
 export function render(ctx: CanvasRenderingContext2D, hasPdf: boolean): void {
   if (!engine) return
+  if (fullFrameWaiters.length === 0) {
+    engine.render(ctx, hasPdf)
+    return
+  }
+
+  const waiters = fullFrameWaiters
+  fullFrameWaiters = []
+  engine.clear_viewport()
   engine.render(ctx, hasPdf)
+  if (viewport) engine.set_viewport(...viewport)
+  waiters.forEach(resolve => resolve())
 }
 
 export function hitTest(x: number, y: number, radius: number = 10): number {
@@ -117,11 +421,124 @@ export function hitTest(x: number, y: number, radius: number = 10): number {
   return engine.hit_test(x, y, radius)
 }
 
+export function hitTestHandle(point: Point, radius: number = 8): TransformHandle | null {
+  if (!engine) return null
+  return (engine.hit_test_handle(point.x, point.y, radius) || null) as TransformHandle | null
+}
+
+export function beginTransform(handle: TransformHandle, point: Point): boolean {
+  if (!engine) return false
+  return engine.begin_transform(handle, point.x, point.y)
+}
+
+export function updateTransform(point: Point, constrain: boolean = false): void {
+  if (engine) engine.update_transform(point.x, point.y, constrain)
+}
+
+export function endTransform(): Stroke[] {
+  if (!engine) return []
+  try {
+    return JSON.parse(engine.end_transform())
+  } catch {
+    return []
+  }
+}
+
+export function cancelTransform(): void {
+  if (engine) engine.cancel_transform()
+}
+
+// Index of the selected path shape's point under the pointer, or -1.
+export function hitTestVertex(point: Point, radius: number = 8): number {
+  if (!engine) return -1
+  return engine.hit_test_vertex(point.x, point.y, radius)
+}
+
+export function beginVertexEdit(vertex: number): boolean {
+  if (!engine) return false
+  return engine.begin_vertex_edit(vertex)
+}
+
+export function updateVertexEdit(point: Point): void {
+  if (engine) engine.update_vertex_edit(point.x, point.y)
+}
+
+export function endVertexEdit(): Stroke | null {
+  if (!engine) return null
+  const json = engine.end_vertex_edit()
+  return json ? JSON.parse(json) : null
+}
+
+export function cancelVertexEdit(): void {
+  if (engine) engine.cancel_vertex_edit()
+}
+
+export function setEditingText(id: string | null): void {
+  if (engine) engine.set_editing_text(id ?? "")
+}
+
+export interface EraseChange {
+  id: string
+  pieces: Stroke[]
+}
+
+export function beginErase(): void {
+  if (engine) engine.begin_erase()
+}
+
+// Cuts ink along the eraser path; returns how many strokes changed.
+export function erasePath(points: Point[], radius: number): number {
+  if (!engine) return 0
+  const coords = new Float64Array(points.flatMap(p => [p.x, p.y]))
+  const prefix = `stroke-${Date.now()}-${Math.random().toString(36).substr(2, 9)}`
+  return engine.erase_path(coords, radius, prefix)
+}
+
+export function endErase(): EraseChange[] {
+  if (!engine) return []
+  try {
+    return JSON.parse(engine.end_erase())
+  } catch {
+    return []
+  }
+}
+
+export function fitPoints(points: Point[], mode: CurveFit): Point[] {
+  if (!wasmModule || mode === "none" || points.length < 3) return points
+  try {
+    if (hasPressure(points)) {
+      return JSON.parse(wasmModule.RenderEngine.fit_points(JSON.stringify(points), mode))
+    }
+    return fromCoords(wasmModule.RenderEngine.fit_coords(toCoords(points), mode))
+  } catch {
+    return points
+  }
+}
+
+export interface RecognizedShape {
+  tool: string
+  points: Point[]
+  rotation: number
+}
+
+export function recognizeShape(points: Point[]): RecognizedShape | null {
+  if (!wasmModule || points.length < 3) return null
+  try {
+    const json = wasmModule.RenderEngine.recognize_shape(toCoords(points))
+    return json ? JSON.parse(json) : null
+  } catch {
+    return null
+  }
+}
+
 export function simplifyPoints(points: Point[], tolerance: number = 1.5): Point[] {
   if (!wasmModule || points.length < 3) return points
   try {
-    const result = wasmModule.RenderEngine.simplify_points(JSON.stringify(points), tolerance)
-    return JSON.parse(result)
+    // Typed arrays only carry positions, so pen samples go through JSON.
+    if (hasPressure(points)) {
+      return JSON.parse(wasmModule.RenderEngine.simplify_points(JSON.stringify(points), tolerance))
+    }
+    return fromCoords(wasmModule.RenderEngine.simplify_coords(toCoords(points), tolerance))
   } catch {
     return points
   }
@@ -5,15 +5,61 @@ declare module "wasm-renderer" {
     constructor(width: number, height: number)
     resize(width: number, height: number): void
     set_strokes(strokes_json: string): void
+    add_stroke(stroke_json: string): boolean
+    update_stroke(stroke_json: string): boolean
+    remove_stroke(id: string): boolean
+    move_strokes(ids_json: string, dx: number, dy: number): number
+    reorder_stroke(id: string, index: number): boolean
+    stroke_count(): number
     set_current_stroke(points_json: string, style_json: string): void
+    set_current_points(coords: Float64Array): void
+    set_current_points_f32(coords: Float32Array): void
+    append_current_point(x: number, y: number): void
+    append_current_points(coords: Float64Array): void
+    set_current_samples(samples: Float64Array): void
+    append_current_samples(samples: Float64Array): void
+    set_current_style(color: string, thickness: number, opacity: number): void
+    set_current_pressure_curve(gamma: number, min_width: number): void
+    set_current_tool(tool: string): void
+    set_stabilizer(config_json: string): void
+    stabilize_point(x: number, y: number, time: number): Float64Array
+    finish_stabilizer(): Float64Array
+    clear_current_stroke(): void
     set_shape_preview(preview_json: string): void
     set_symbol_preview(preview_json: string): void
     set_selected(id: string): void
     set_selected_ids(ids_json: string): void
+    set_annotation_color_mode(mode: string): void
+    set_paper_template(template: string): void
+    set_viewport(x: number, y: number, width: number, height: number): void
+    clear_viewport(): void
+    strokes_in_rect(x: number, y: number, width: number, height: number, fully: boolean): string[]
+    strokes_in_lasso(coords: Float64Array, fully: boolean): string[]
+    set_lasso(coords: Float64Array): void
+    append_lasso_point(x: number, y: number): void
+    clear_lasso(): void
     record_frame(time: number): void
     get_fps(): number
     render(ctx: CanvasRenderingContext2D, has_pdf: boolean): void
     hit_test(x: number, y: number, radius: number): number
+    hit_test_handle(x: number, y: number, radius: number): string
+    begin_transform(handle: string, x: number, y: number): boolean
+    update_transform(x: number, y: number, constrain: boolean): void
+    end_transform(): string
+    cancel_transform(): void
+    hit_test_vertex(x: number, y: number, radius: number): number
+    begin_vertex_edit(vertex: number): boolean
+    update_vertex_edit(x: number, y: number): void
+    end_vertex_edit(): string
+    cancel_vertex_edit(): void
+    set_editing_text(id: string): void
+    begin_erase(): void
+    erase_path(coords: Float64Array, radius: number, id_prefix: string): number
+    end_erase(): string
     static simplify_points(points_json: string, tolerance: number): string
+    static simplify_coords(coords: Float64Array, tolerance: number): Float64Array
+    static fit_points(points_json: string, mode: string): string
+    static fit_coords(coords: Float64Array, mode: string): Float64Array
+    static recognize_shape(coords: Float64Array): string
   }
 }
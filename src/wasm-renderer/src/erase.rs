@@ -0,0 +1,191 @@
+use crate::hit::distance_to_polyline;
+use crate::smoothing::lerp;
+use crate::{Point, Stroke};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const BISECT_STEPS: usize = 12;
+const MIN_PIECE_LENGTH: f64 = 0.5;
+
+/// Cuts a polyline where it comes within `reach` of the eraser path.
+/// Returns `None` when nothing is erased, otherwise the surviving pieces in
+/// order (empty when the whole line is gone).
+pub fn split_points(points: &[Point], eraser: &[Point], reach: f64) -> Option<Vec<Vec<Point>>> {
+    let inside = |p: &Point| distance_to_polyline(*p, eraser) <= reach;
+    if points.len() < 2 {
+        return points.first().filter(|p| inside(p)).map(|_| Vec::new());
+    }
+
+    // Sampling at half the reach cannot step over an eraser disc that
+    // crosses the segment.
+    let step = (reach / 2.0).max(0.5);
+    let mut pieces = Vec::new();
+    let mut current = Vec::new();
+    let mut was_inside = inside(&points[0]);
+    let mut cut = was_inside;
+    if !was_inside {
+        current.push(points[0]);
+    }
+
+    for pair in points.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        let length = ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+        let samples = ((length / step).ceil() as usize).max(1);
+        let mut previous_t = 0.0;
+        for k in 1..=samples {
+            let t = k as f64 / samples as f64;
+            let now_inside = inside(&lerp(a, b, t));
+            if now_inside != was_inside {
+                current.push(boundary(a, b, previous_t, t, was_inside, &inside));
+                if now_inside {
+                    pieces.push(std::mem::take(&mut current));
+                }
+                was_inside = now_inside;
+                cut = true;
+            }
+            previous_t = t;
+        }
+        if !was_inside {
+            current.push(*b);
+        }
+    }
+    pieces.push(current);
+
+    if !cut {
+        return None;
+    }
+    pieces.retain(|piece| polyline_length(piece) >= MIN_PIECE_LENGTH);
+    Some(pieces)
+}
+
+/// Bisects between two parameters on `a`-`b` that fall on opposite sides of
+/// the eraser's edge.
+fn boundary(a: &Point, b: &Point, mut from: f64, mut to: f64, from_inside: bool, inside: &impl Fn(&Point) -> bool) -> Point {
+    for _ in 0..BISECT_STEPS {
+        let mid = (from + to) / 2.0;
+        if inside(&lerp(a, b, mid)) == from_inside {
+            from = mid;
+        } else {
+            to = mid;
+        }
+    }
+    lerp(a, b, (from + to) / 2.0)
+}
+
+fn polyline_length(points: &[Point]) -> f64 {
+    points
+        .windows(2)
+        .map(|w| ((w[1].x - w[0].x).powi(2) + (w[1].y - w[0].y).powi(2)).sqrt())
+        .sum()
+}
+
+/// Adds points along the eraser path so point hit tests don't skip over
+/// strokes between two distant pointer samples.
+pub fn densify(path: &[Point], step: f64) -> Vec<Point> {
+    let mut out = path.first().copied().into_iter().collect::<Vec<_>>();
+    for pair in path.windows(2) {
+        let length = ((pair[1].x - pair[0].x).powi(2) + (pair[1].y - pair[0].y).powi(2)).sqrt();
+        let samples = ((length / step.max(0.5)).ceil() as usize).max(1);
+        out.extend((1..=samples).map(|k| lerp(&pair[0], &pair[1], k as f64 / samples as f64)));
+    }
+    out
+}
+
+/// Tracks which original strokes an eraser gesture removed and which pieces
+/// descend from each, so the whole gesture can be reported as one change.
+#[derive(Default)]
+pub struct EraseSession {
+    roots: HashMap<String, String>,
+    removed: Vec<String>,
+}
+
+impl EraseSession {
+    /// Records that `id` was replaced by `pieces`.
+    pub fn record(&mut self, id: &str, pieces: &[String]) {
+        let root = match self.roots.get(id) {
+            Some(root) => root.clone(),
+            None => {
+                self.removed.push(id.to_string());
+                id.to_string()
+            }
+        };
+        for piece in pieces {
+            self.roots.insert(piece.clone(), root.clone());
+        }
+    }
+
+    /// One change per original stroke, with the pieces of it still present.
+    pub fn changes<'a>(&self, strokes: &'a [Stroke]) -> Vec<EraseChange<'a>> {
+        self.removed
+            .iter()
+            .map(|root| EraseChange {
+                id: root.clone(),
+                pieces: strokes.iter().filter(|s| self.roots.get(&s.id) == Some(root)).collect(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+pub struct EraseChange<'a> {
+    pub id: String,
+    pub pieces: Vec<&'a Stroke>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pressed(x: f64, y: f64, pressure: f64) -> Point {
+        Point { pressure: Some(pressure), ..Point::new(x, y) }
+    }
+
+    fn line() -> Vec<Point> {
+        vec![pressed(0.0, 0.0, 0.2), pressed(100.0, 0.0, 0.6)]
+    }
+
+    #[test]
+    fn crossing_the_middle_leaves_two_pieces() {
+        let eraser = [Point::new(50.0, -20.0), Point::new(50.0, 20.0)];
+
+        let pieces = split_points(&line(), &eraser, 5.0).unwrap();
+
+        assert_eq!(pieces.len(), 2);
+        let (left, right) = (&pieces[0], &pieces[1]);
+        assert_eq!(left[0].x, 0.0);
+        assert!((left.last().unwrap().x - 45.0).abs() < 0.01, "left ends at {:?}", left.last());
+        assert!((right[0].x - 55.0).abs() < 0.01, "right starts at {:?}", right[0]);
+        assert_eq!(right.last().unwrap().x, 100.0);
+    }
+
+    #[test]
+    fn covering_the_whole_stroke_leaves_nothing() {
+        let eraser = [Point::new(-10.0, 0.0), Point::new(110.0, 0.0)];
+
+        assert_eq!(split_points(&line(), &eraser, 5.0), Some(Vec::new()));
+        assert_eq!(split_points(&[Point::new(0.0, 0.0)], &eraser, 5.0), Some(Vec::new()));
+    }
+
+    #[test]
+    fn missing_the_stroke_erases_nothing() {
+        let eraser = [Point::new(0.0, 50.0), Point::new(100.0, 50.0)];
+
+        assert_eq!(split_points(&line(), &eraser, 5.0), None);
+        assert_eq!(split_points(&[Point::new(0.0, 0.0)], &eraser, 5.0), None);
+    }
+
+    #[test]
+    fn split_points_keep_interpolated_pressure() {
+        let eraser = [Point::new(50.0, -20.0), Point::new(50.0, 20.0)];
+
+        let pieces = split_points(&line(), &eraser, 5.0).unwrap();
+
+        assert_eq!(pieces[0][0].pressure, Some(0.2));
+        assert_eq!(pieces[1].last().unwrap().pressure, Some(0.6));
+        // The cuts land at x = 45 and 55, so their pressure is interpolated there.
+        let cut = pieces[0].last().unwrap().pressure.unwrap();
+        assert!((cut - 0.38).abs() < 0.001, "pressure at the cut is {}", cut);
+        let cut = pieces[1][0].pressure.unwrap();
+        assert!((cut - 0.42).abs() < 0.001, "pressure at the cut is {}", cut);
+    }
+}
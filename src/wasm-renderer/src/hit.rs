@@ -0,0 +1,315 @@
+use crate::{line_style, markup, path, text_box};
+use crate::{Point, Stroke};
+use std::f64::consts::PI;
+
+const ELLIPSE_SEGMENTS: usize = 64;
+const CURVE_SEGMENTS: usize = 16;
+
+/// Measured extent of a text run, relative to its baseline origin.
+#[derive(Clone, Copy, Debug)]
+pub struct TextExtent {
+    pub width: f64,
+    pub ascent: f64,
+    pub descent: f64,
+}
+
+impl TextExtent {
+    /// Used until the text has been drawn and measured once.
+    pub fn estimate(text: &str, font_size: f64) -> Self {
+        Self {
+            width: text.chars().count() as f64 * font_size * 0.6,
+            ascent: font_size * 0.8,
+            descent: font_size * 0.2,
+        }
+    }
+}
+
+pub fn distance_to_segment(p: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    ((p.x - a.x - t * dx).powi(2) + (p.y - a.y - t * dy).powi(2)).sqrt()
+}
+
+/// Distance from `p` to an open polyline.
+pub fn distance_to_polyline(p: Point, points: &[Point]) -> f64 {
+    match points {
+        [] => f64::INFINITY,
+        [only] => ((p.x - only.x).powi(2) + (p.y - only.y).powi(2)).sqrt(),
+        _ => points
+            .windows(2)
+            .map(|w| distance_to_segment(p, w[0], w[1]))
+            .fold(f64::INFINITY, f64::min),
+    }
+}
+
+fn distance_to_polygon_outline(p: Point, polygon: &[Point]) -> f64 {
+    let closing = match (polygon.first(), polygon.last()) {
+        (Some(&first), Some(&last)) => distance_to_segment(p, last, first),
+        _ => f64::INFINITY,
+    };
+    distance_to_polyline(p, polygon).min(closing)
+}
+
+/// Even-odd point-in-polygon test.
+pub fn polygon_contains(polygon: &[Point], p: Point) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Rotates `p` by `angle` radians (clockwise on screen) around `pivot`.
+pub fn rotate_about(p: Point, pivot: Point, angle: f64) -> Point {
+    if angle == 0.0 {
+        return p;
+    }
+    let (sin, cos) = angle.sin_cos();
+    let (dx, dy) = (p.x - pivot.x, p.y - pivot.y);
+    Point { x: pivot.x + dx * cos - dy * sin, y: pivot.y + dx * sin + dy * cos, ..p }
+}
+
+/// The text a text stroke shows: a `text:` run or a text box's content.
+pub fn text_content(stroke: &Stroke) -> Option<&str> {
+    match &stroke.text_box {
+        Some(text_box) if text_box::is_text_box(stroke) => Some(&text_box.content),
+        _ => stroke.tool.strip_prefix("text:"),
+    }
+}
+
+/// The point shapes and text rotate around: a shape's centre, the text's
+/// baseline origin or a text box's top-left corner.
+pub fn rotation_pivot(stroke: &Stroke) -> Option<Point> {
+    if text_content(stroke).is_some() {
+        return stroke.points.first().copied();
+    }
+    let (start, end) = (stroke.points.first()?, stroke.points.get(1)?);
+    Some(Point::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0))
+}
+
+/// Maps a world-space point into the stroke's unrotated space.
+fn unrotate(stroke: &Stroke, p: Point) -> Point {
+    match rotation_pivot(stroke) {
+        Some(pivot) => rotate_about(p, pivot, -stroke.rotation),
+        None => p,
+    }
+}
+
+fn cubic(from: Point, c1: Point, c2: Point, to: Point, out: &mut Vec<Point>) {
+    for step in 1..=CURVE_SEGMENTS {
+        let t = step as f64 / CURVE_SEGMENTS as f64;
+        let mt = 1.0 - t;
+        let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+        out.push(Point::new(
+            a * from.x + b * c1.x + c * c2.x + d * to.x,
+            a * from.y + b * c1.y + c * c2.y + d * to.y,
+        ));
+    }
+}
+
+/// The closed outline `draw_shape` traces for a shape, or `None` for open
+/// shapes (lines and arrows).
+pub fn shape_outline(shape_type: &str, start: Point, end: Point) -> Option<Vec<Point>> {
+    let min_x = start.x.min(end.x);
+    let min_y = start.y.min(end.y);
+    let width = (end.x - start.x).abs();
+    let height = (end.y - start.y).abs();
+    let center_x = (start.x + end.x) / 2.0;
+    let center_y = (start.y + end.y) / 2.0;
+
+    let outline = match shape_type {
+        "rectangle" => vec![
+            Point::new(min_x, min_y),
+            Point::new(min_x + width, min_y),
+            Point::new(min_x + width, min_y + height),
+            Point::new(min_x, min_y + height),
+        ],
+        "circle" => (0..ELLIPSE_SEGMENTS)
+            .map(|i| {
+                let angle = i as f64 / ELLIPSE_SEGMENTS as f64 * PI * 2.0;
+                Point::new(center_x + width / 2.0 * angle.cos(), center_y + height / 2.0 * angle.sin())
+            })
+            .collect(),
+        "triangle" => vec![
+            Point::new(center_x, min_y),
+            Point::new(min_x + width, min_y + height),
+            Point::new(min_x, min_y + height),
+        ],
+        "diamond" => vec![
+            Point::new(center_x, min_y),
+            Point::new(min_x + width, center_y),
+            Point::new(center_x, min_y + height),
+            Point::new(min_x, center_y),
+        ],
+        "star" => {
+            let outer_r = width.min(height) / 2.0;
+            let inner_r = outer_r * 0.4;
+            (0..10)
+                .map(|i| {
+                    let angle = -PI / 2.0 + i as f64 * PI / 5.0;
+                    let r = if i % 2 == 0 { outer_r } else { inner_r };
+                    Point::new(center_x + r * angle.cos(), center_y + r * angle.sin())
+                })
+                .collect()
+        }
+        "heart" => {
+            let top = Point::new(center_x, min_y + height * 0.15);
+            let left = Point::new(min_x, min_y + height * 0.3);
+            let bottom = Point::new(center_x, min_y + height);
+            let right = Point::new(min_x + width, min_y + height * 0.3);
+            let mut outline = vec![top];
+            cubic(top, Point::new(center_x, min_y), Point::new(min_x, min_y), left, &mut outline);
+            cubic(left, Point::new(min_x, min_y + height * 0.8), bottom, bottom, &mut outline);
+            cubic(bottom, bottom, Point::new(min_x + width, min_y + height * 0.8), right, &mut outline);
+            cubic(right, Point::new(min_x + width, min_y), Point::new(center_x, min_y), top, &mut outline);
+            outline
+        }
+        _ => return None,
+    };
+    Some(outline)
+}
+
+/// A polygon's closed outline, or an open path with its end markers.
+fn path_shape_lines(stroke: &Stroke) -> Vec<Vec<Point>> {
+    let outline = path::outline(stroke);
+    if path::is_closed(&stroke.tool) {
+        vec![outline]
+    } else {
+        line_style::open_line_geometry(&outline, &line_style::effective_style(stroke), stroke.thickness)
+    }
+}
+
+/// Open polylines, with their end markers, for shapes without an interior.
+fn shape_lines(stroke: &Stroke, start: Point, end: Point) -> Vec<Vec<Point>> {
+    line_style::open_line_geometry(&[start, end], &line_style::effective_style(stroke), stroke.thickness)
+}
+
+/// A callout's leader line with its end markers.
+fn leader_lines(stroke: &Stroke) -> Vec<Vec<Point>> {
+    match (stroke.tool.as_str(), stroke.points.as_slice()) {
+        ("shape-callout", [start, end, anchor, ..]) => markup::leader(*start, *end, *anchor)
+            .map(|leader| line_style::open_line_geometry(&leader, &line_style::effective_style(stroke), stroke.thickness))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a pen or highlighter stroke passes within `radius` of `p`.
+pub fn hits_path(stroke: &Stroke, p: Point, radius: f64) -> bool {
+    distance_to_polyline(p, &stroke.points) <= radius + stroke.thickness / 2.0
+}
+
+/// Whether `p` is within `radius` of a shape's outline, or inside it when the
+/// shape is filled.
+pub fn hits_shape(stroke: &Stroke, p: Point, radius: f64) -> bool {
+    if path::is_path_shape(&stroke.tool) {
+        let tolerance = radius + stroke.thickness / 2.0;
+        let lines = path_shape_lines(stroke);
+        return (path::is_closed(&stroke.tool) && stroke.fill_color.is_some() && polygon_contains(&lines[0], p))
+            || lines.iter().any(|line| distance_to_polyline(p, line) <= tolerance);
+    }
+    let (Some(&start), Some(&end)) = (stroke.points.first(), stroke.points.get(1)) else {
+        return false;
+    };
+    let shape_type = stroke.tool.trim_start_matches("shape-");
+    let tolerance = radius + stroke.thickness / 2.0;
+    let p = unrotate(stroke, p);
+
+    match markup::outline(stroke).or_else(|| shape_outline(shape_type, start, end)) {
+        Some(outline) => {
+            (stroke.fill_color.is_some() && polygon_contains(&outline, p))
+                || distance_to_polygon_outline(p, &outline) <= tolerance
+                || leader_lines(stroke).iter().any(|line| distance_to_polyline(p, line) <= tolerance)
+        }
+        None => shape_lines(stroke, start, end)
+            .iter()
+            .any(|line| distance_to_polyline(p, line) <= tolerance),
+    }
+}
+
+pub fn hits_text(origin: Point, rotation: f64, extent: TextExtent, p: Point, radius: f64) -> bool {
+    let p = rotate_about(p, origin, -rotation);
+    p.x >= origin.x - radius
+        && p.x <= origin.x + extent.width + radius
+        && p.y >= origin.y - extent.ascent - radius
+        && p.y <= origin.y + extent.descent + radius
+}
+
+/// The polylines a stroke is drawn with, closed outlines ending where they
+/// start. Text is represented by its measured box.
+pub fn stroke_geometry(stroke: &Stroke, text_extent: impl Fn(&str) -> TextExtent) -> Vec<Vec<Point>> {
+    if path::is_path_shape(&stroke.tool) {
+        path_shape_lines(stroke)
+    } else if let Some(shape_type) = stroke.tool.strip_prefix("shape-") {
+        let (Some(&start), Some(&end)) = (stroke.points.first(), stroke.points.get(1)) else {
+            return Vec::new();
+        };
+        let pivot = Point::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0);
+        match markup::outline(stroke).or_else(|| shape_outline(shape_type, start, end)) {
+            Some(mut outline) => {
+                outline.extend(outline.first().copied());
+                for p in &mut outline {
+                    *p = rotate_about(*p, pivot, stroke.rotation);
+                }
+                let mut lines = vec![outline];
+                lines.extend(leader_lines(stroke));
+                lines
+            }
+            None => shape_lines(stroke, start, end),
+        }
+    } else if let Some(text) = text_content(stroke) {
+        let Some(&origin) = stroke.points.first() else {
+            return Vec::new();
+        };
+        let extent = text_extent(text);
+        let (top, bottom) = (origin.y - extent.ascent, origin.y + extent.descent);
+        let right = origin.x + extent.width;
+        let corners = [
+            Point::new(origin.x, top),
+            Point::new(right, top),
+            Point::new(right, bottom),
+            Point::new(origin.x, bottom),
+            Point::new(origin.x, top),
+        ];
+        vec![corners.iter().map(|&p| rotate_about(p, origin, stroke.rotation)).collect()]
+    } else {
+        vec![stroke.points.clone()]
+    }
+}
+
+fn segments_intersect(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let cross = |o: Point, p: Point, q: Point| (p.x - o.x) * (q.y - o.y) - (p.y - o.y) * (q.x - o.x);
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    (d1 > 0.0) != (d2 > 0.0) && (d3 > 0.0) != (d4 > 0.0)
+}
+
+/// Whether a selection polygon takes in `geometry`: every point inside when
+/// `fully`, otherwise any point inside or any edge crossing the polygon.
+pub fn polygon_selects(polygon: &[Point], geometry: &[Vec<Point>], fully: bool) -> bool {
+    if polygon.len() < 3 || geometry.iter().all(|line| line.is_empty()) {
+        return false;
+    }
+    let mut points = geometry.iter().flatten();
+    if fully {
+        return points.all(|&p| polygon_contains(polygon, p));
+    }
+    if points.any(|&p| polygon_contains(polygon, p)) {
+        return true;
+    }
+    let edges: Vec<(Point, Point)> = polygon.iter().zip(polygon.iter().cycle().skip(1)).map(|(&a, &b)| (a, b)).collect();
+    geometry.iter().any(|line| {
+        line.windows(2)
+            .any(|segment| edges.iter().any(|&(a, b)| segments_intersect(segment[0], segment[1], a, b)))
+    })
+}
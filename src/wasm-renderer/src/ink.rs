@@ -0,0 +1,90 @@
+use crate::Point;
+use serde::{Deserialize, Serialize};
+
+// Flattening steps per smoothing curve; matches the density of the raw input.
+const CURVE_STEPS: usize = 4;
+// Points sampled without pressure (e.g. mouse input mixed into a pen stroke).
+const DEFAULT_PRESSURE: f64 = 0.5;
+
+/// Maps pen pressure to a share of the stroke's thickness:
+/// `min_width + (1 - min_width) * pressure^gamma`. Gamma below 1 makes light
+/// touches heavier, above 1 makes them lighter.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PressureCurve {
+    pub gamma: f64,
+    pub min_width: f64,
+}
+
+impl Default for PressureCurve {
+    fn default() -> Self {
+        Self { gamma: 1.0, min_width: 0.2 }
+    }
+}
+
+impl PressureCurve {
+    pub fn width(&self, thickness: f64, pressure: f64) -> f64 {
+        let min = self.min_width.clamp(0.0, 1.0);
+        let p = pressure.clamp(0.0, 1.0).powf(self.gamma.max(0.05));
+        thickness * (min + (1.0 - min) * p)
+    }
+}
+
+/// Whether any point carries pressure, so the stroke needs variable width.
+pub fn has_pressure(points: &[Point]) -> bool {
+    points.iter().any(|p| p.pressure.is_some())
+}
+
+/// The smoothed centre line of a pressure stroke with the radius at each
+/// sample. Follows the same midpoint quadratics as uniform ink.
+pub fn samples(points: &[Point], thickness: f64, curve: PressureCurve) -> Vec<(Point, f64)> {
+    let radius = |p: &Point| curve.width(thickness, p.pressure.unwrap_or(DEFAULT_PRESSURE)) / 2.0;
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return Vec::new();
+    };
+
+    let mut out = Vec::with_capacity(points.len() * CURVE_STEPS + 1);
+    let mut from = (*first, radius(first));
+    out.push(from);
+    for pair in points.windows(2) {
+        let (control, control_r) = (pair[0], radius(&pair[0]));
+        let to = (
+            Point::new((pair[0].x + pair[1].x) / 2.0, (pair[0].y + pair[1].y) / 2.0),
+            (control_r + radius(&pair[1])) / 2.0,
+        );
+        for step in 1..=CURVE_STEPS {
+            let t = step as f64 / CURVE_STEPS as f64;
+            let (a, b, c) = ((1.0 - t) * (1.0 - t), 2.0 * (1.0 - t) * t, t * t);
+            out.push((
+                Point::new(
+                    a * from.0.x + b * control.x + c * to.0.x,
+                    a * from.0.y + b * control.y + c * to.0.y,
+                ),
+                a * from.1 + b * control_r + c * to.1,
+            ));
+        }
+        from = to;
+    }
+    out.push((*last, radius(last)));
+    out
+}
+
+/// Quadrilaterals joining consecutive samples, wound the same way as a
+/// clockwise `arc` so that filling them together with the sample discs
+/// under the non-zero rule paints their union exactly once.
+pub fn segment_quads(samples: &[(Point, f64)]) -> impl Iterator<Item = [Point; 4]> + '_ {
+    samples.windows(2).filter_map(|pair| {
+        let ((a, ra), (b, rb)) = (pair[0], pair[1]);
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length < 1e-6 {
+            return None;
+        }
+        let (nx, ny) = (-dy / length, dx / length);
+        Some([
+            Point::new(a.x - nx * ra, a.y - ny * ra),
+            Point::new(b.x - nx * rb, b.y - ny * rb),
+            Point::new(b.x + nx * rb, b.y + ny * rb),
+            Point::new(a.x + nx * ra, a.y + ny * ra),
+        ])
+    })
+}
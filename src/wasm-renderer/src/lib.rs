@@ -1,17 +1,62 @@
 use wasm_bindgen::prelude::*;
 use web_sys::CanvasRenderingContext2d;
 use serde::{Deserialize, Serialize};
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+
+mod erase;
+mod hit;
+mod ink;
+mod line_style;
+mod markup;
+mod paper;
+mod path;
+mod recognize;
+mod smoothing;
+mod spatial;
+mod text_box;
+mod transform;
+
+use erase::EraseSession;
+use hit::TextExtent;
+use ink::PressureCurve;
+use line_style::LineStyle;
+use paper::PaperTemplate;
+use smoothing::{Stabilizer, StabilizerState};
+use spatial::{stroke_bounds, Rect, SpatialIndex};
+use text_box::{TextAlign, TextBox};
+use transform::{Frame, Gesture, Handle};
 
 #[cfg(feature = "console_error_panic_hook")]
 pub use console_error_panic_hook::set_once as set_panic_hook;
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug)]
+/// A canvas position, with the stylus state it was sampled with when the
+/// input device reports one.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub struct Point {
     pub x: f64,
     pub y: f64,
+    /// Normalised 0..1 pen pressure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressure: Option<f64>,
+    /// Pen tilt in degrees, -90..90.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tilt_x: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tilt_y: Option<f64>,
+    /// Milliseconds since the stroke started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<f64>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+impl Point {
+    pub const fn new(x: f64, y: f64) -> Self {
+        Self { x, y, pressure: None, tilt_x: None, tilt_y: None, time: None }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct Stroke {
     pub id: String,
     pub points: Vec<Point>,
@@ -21,6 +66,20 @@ pub struct Stroke {
     pub tool: String,
     #[serde(default)]
     pub fill_color: Option<String>,
+    /// Radians, clockwise. Closed shapes turn about their centre and text
+    /// about its baseline origin; other strokes are transformed point by point.
+    #[serde(default)]
+    pub rotation: f64,
+    /// How point pressure maps to width; strokes without pressure ignore it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressure_curve: Option<PressureCurve>,
+    /// Dashes, caps, joins and end markers; arrows without one get an open
+    /// arrowhead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_style: Option<LineStyle>,
+    /// Content and formatting of a "textbox" stroke.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_box: Option<TextBox>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -32,6 +91,14 @@ pub struct ShapePreview {
     pub thickness: f64,
     pub opacity: f64,
     pub fill_color: Option<String>,
+    #[serde(default)]
+    pub line_style: Option<LineStyle>,
+    #[serde(default)]
+    pub rotation: f64,
+    /// Every point of a path shape preview, or the box plus tail of a callout
+    /// or speech bubble; `start` and `end` are ignored when set.
+    #[serde(default)]
+    pub points: Vec<Point>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -48,6 +115,17 @@ pub struct CurrentStrokeStyle {
     pub color: String,
     pub thickness: f64,
     pub opacity: f64,
+    #[serde(default)]
+    pub pressure_curve: Option<PressureCurve>,
+    #[serde(default)]
+    pub tool: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AnnotationColorMode {
+    #[default]
+    Normal,
+    Inverted,
 }
 
 #[wasm_bindgen]
@@ -57,10 +135,29 @@ pub struct RenderEngine {
     strokes: Vec<Stroke>,
     current_stroke: Vec<Point>,
     current_style: Option<CurrentStrokeStyle>,
+    stabilizer: StabilizerState,
     shape_preview: Option<ShapePreview>,
     symbol_preview: Option<SymbolPreview>,
     selected_id: Option<String>,
     selected_ids: Vec<String>,
+    color_mode: AnnotationColorMode,
+    paper: PaperTemplate,
+    index: SpatialIndex,
+    viewport: Option<Rect>,
+    lasso: Vec<Point>,
+    // The handle drag in progress and the selected strokes as they were before it.
+    transform: Option<(Gesture, Vec<Stroke>)>,
+    erase: Option<EraseSession>,
+    // The point being dragged on the selected path shape and the shape before the drag.
+    vertex_edit: Option<(usize, Stroke)>,
+    // Measured on first draw or hit test, so geometry agrees with what is drawn.
+    text_extents: RefCell<HashMap<(String, u64), TextExtent>>,
+    // Wrapped lines and their widths per (font, wrap width, content), likewise.
+    text_layouts: RefCell<HashMap<(String, u64, String), text_box::Lines>>,
+    // A detached canvas for measuring text that has not been drawn yet.
+    measuring_context: OnceCell<Option<CanvasRenderingContext2d>>,
+    // The text box open in the editor, drawn without its text.
+    editing_text: Option<String>,
     frame_times: Vec<f64>,
     last_frame_time: f64,
 }
@@ -78,10 +175,23 @@ impl RenderEngine {
             strokes: Vec::new(),
             current_stroke: Vec::new(),
             current_style: None,
+            stabilizer: StabilizerState::default(),
             shape_preview: None,
             symbol_preview: None,
             selected_id: None,
             selected_ids: Vec::new(),
+            color_mode: AnnotationColorMode::Normal,
+            paper: PaperTemplate::default(),
+            index: SpatialIndex::default(),
+            viewport: None,
+            lasso: Vec::new(),
+            transform: None,
+            erase: None,
+            vertex_edit: None,
+            text_extents: RefCell::new(HashMap::new()),
+            text_layouts: RefCell::new(HashMap::new()),
+            measuring_context: OnceCell::new(),
+            editing_text: None,
             frame_times: Vec::with_capacity(60),
             last_frame_time: 0.0,
         }
@@ -97,9 +207,92 @@ impl RenderEngine {
     pub fn set_strokes(&mut self, strokes_json: &str) {
         if let Ok(strokes) = serde_json::from_str::<Vec<Stroke>>(strokes_json) {
             self.strokes = strokes;
+            self.index.rebuild(self.strokes.iter().map(index_bounds).collect());
+            self.text_extents.borrow_mut().clear();
+            self.text_layouts.borrow_mut().clear();
+        }
+    }
+
+    /// Appends a stroke, or replaces the stroke that already has its id.
+    #[wasm_bindgen]
+    pub fn add_stroke(&mut self, stroke_json: &str) -> bool {
+        let Ok(stroke) = serde_json::from_str::<Stroke>(stroke_json) else {
+            return false;
+        };
+        let index = self.stroke_index(&stroke.id).unwrap_or(self.strokes.len());
+        self.index.set(index, index_bounds(&stroke));
+        if index == self.strokes.len() {
+            self.strokes.push(stroke);
+        } else {
+            self.strokes[index] = stroke;
+        }
+        true
+    }
+
+    #[wasm_bindgen]
+    pub fn update_stroke(&mut self, stroke_json: &str) -> bool {
+        let Ok(stroke) = serde_json::from_str::<Stroke>(stroke_json) else {
+            return false;
+        };
+        match self.stroke_index(&stroke.id) {
+            Some(index) => {
+                self.index.set(index, index_bounds(&stroke));
+                self.strokes[index] = stroke;
+                true
+            }
+            None => false,
         }
     }
 
+    #[wasm_bindgen]
+    pub fn remove_stroke(&mut self, id: &str) -> bool {
+        match self.stroke_index(id) {
+            Some(index) => {
+                self.strokes.remove(index);
+                self.index.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Offsets every point of the given strokes and returns how many moved.
+    #[wasm_bindgen]
+    pub fn move_strokes(&mut self, ids_json: &str, dx: f64, dy: f64) -> u32 {
+        let Ok(ids) = serde_json::from_str::<Vec<String>>(ids_json) else {
+            return 0;
+        };
+        let mut moved = 0;
+        for (index, stroke) in self.strokes.iter_mut().enumerate().filter(|(_, s)| ids.contains(&s.id)) {
+            for p in &mut stroke.points {
+                p.x += dx;
+                p.y += dy;
+            }
+            self.index.set(index, index_bounds(stroke));
+            moved += 1;
+        }
+        moved
+    }
+
+    /// Moves a stroke to `index` in paint order; indices past the end move it
+    /// to the top.
+    #[wasm_bindgen]
+    pub fn reorder_stroke(&mut self, id: &str, index: usize) -> bool {
+        let Some(from) = self.stroke_index(id) else {
+            return false;
+        };
+        let stroke = self.strokes.remove(from);
+        let to = index.min(self.strokes.len());
+        self.strokes.insert(to, stroke);
+        self.index.reorder(from, to);
+        true
+    }
+
+    #[wasm_bindgen]
+    pub fn stroke_count(&self) -> usize {
+        self.strokes.len()
+    }
+
     #[wasm_bindgen]
     pub fn set_current_stroke(&mut self, points_json: &str, style_json: &str) {
         if let Ok(points) = serde_json::from_str::<Vec<Point>>(points_json) {
@@ -112,6 +305,98 @@ impl RenderEngine {
         }
     }
 
+    /// Replaces the live stroke with interleaved `[x0, y0, x1, y1, ...]`
+    /// coordinates from a `Float64Array`.
+    #[wasm_bindgen]
+    pub fn set_current_points(&mut self, coords: &[f64]) {
+        self.current_stroke = points_from_coords(coords);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_current_points_f32(&mut self, coords: &[f32]) {
+        self.current_stroke = points_from_coords(coords);
+    }
+
+    #[wasm_bindgen]
+    pub fn append_current_point(&mut self, x: f64, y: f64) {
+        self.current_stroke.push(Point::new(x, y));
+    }
+
+    #[wasm_bindgen]
+    pub fn append_current_points(&mut self, coords: &[f64]) {
+        self.current_stroke.extend(points_from_coords(coords));
+    }
+
+    /// Replaces the live stroke with interleaved `[x0, y0, pressure0, ...]`
+    /// pen samples.
+    #[wasm_bindgen]
+    pub fn set_current_samples(&mut self, samples: &[f64]) {
+        self.current_stroke = points_from_samples(samples);
+    }
+
+    #[wasm_bindgen]
+    pub fn append_current_samples(&mut self, samples: &[f64]) {
+        self.current_stroke.extend(points_from_samples(samples));
+    }
+
+    #[wasm_bindgen]
+    pub fn set_current_style(&mut self, color: &str, thickness: f64, opacity: f64) {
+        self.current_style = Some(CurrentStrokeStyle {
+            color: color.to_string(),
+            thickness,
+            opacity,
+            pressure_curve: None,
+            tool: String::new(),
+        });
+    }
+
+    /// Configures the filter applied by `stabilize_point`, e.g.
+    /// `{"mode":"one_euro","min_cutoff":1.0,"beta":0.01}`, and resets it.
+    #[wasm_bindgen]
+    pub fn set_stabilizer(&mut self, config_json: &str) {
+        let config = serde_json::from_str::<Stabilizer>(config_json).unwrap_or_default();
+        self.stabilizer = StabilizerState::new(config);
+    }
+
+    /// Filters a raw pointer position sampled at `time` ms. Returns `[x, y]`
+    /// to append to the stroke, or an empty array while the pen is held back.
+    #[wasm_bindgen]
+    pub fn stabilize_point(&mut self, x: f64, y: f64, time: f64) -> Vec<f64> {
+        self.stabilizer
+            .push(Point::new(x, y), time)
+            .map_or(Vec::new(), |p| vec![p.x, p.y])
+    }
+
+    /// Ends the stroke: returns a final `[x, y]` that catches the stroke up
+    /// with the pointer, if the filter lags, and resets the filter.
+    #[wasm_bindgen]
+    pub fn finish_stabilizer(&mut self) -> Vec<f64> {
+        self.stabilizer.finish().map_or(Vec::new(), |p| vec![p.x, p.y])
+    }
+
+    /// Sets the pressure curve of the live stroke's style.
+    #[wasm_bindgen]
+    pub fn set_current_pressure_curve(&mut self, gamma: f64, min_width: f64) {
+        if let Some(style) = &mut self.current_style {
+            style.pressure_curve = Some(PressureCurve { gamma, min_width });
+        }
+    }
+
+    /// Sets the tool of the live stroke, so a highlighter previews with the
+    /// same blending it is drawn with.
+    #[wasm_bindgen]
+    pub fn set_current_tool(&mut self, tool: &str) {
+        if let Some(style) = &mut self.current_style {
+            style.tool = tool.to_string();
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_current_stroke(&mut self) {
+        self.current_stroke.clear();
+        self.current_style = None;
+    }
+
     #[wasm_bindgen]
     pub fn set_shape_preview(&mut self, preview_json: &str) {
         if preview_json.is_empty() {
@@ -149,6 +434,67 @@ impl RenderEngine {
         }
     }
 
+    #[wasm_bindgen]
+    pub fn set_annotation_color_mode(&mut self, mode: &str) {
+        self.color_mode = match mode {
+            "inverted" => AnnotationColorMode::Inverted,
+            _ => AnnotationColorMode::Normal,
+        };
+    }
+
+    #[wasm_bindgen]
+    pub fn set_paper_template(&mut self, template: &str) {
+        self.paper = PaperTemplate::parse(template).unwrap_or_default();
+    }
+
+    /// Limits drawing to strokes that intersect this canvas-space rectangle.
+    #[wasm_bindgen]
+    pub fn set_viewport(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        self.viewport = Some(Rect::new(x, y, width, height));
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_viewport(&mut self) {
+        self.viewport = None;
+    }
+
+    /// Ids of strokes inside a marquee rectangle: wholly inside when `fully`,
+    /// otherwise touching it.
+    #[wasm_bindgen]
+    pub fn strokes_in_rect(&self, x: f64, y: f64, width: f64, height: f64, fully: bool) -> Vec<String> {
+        let r = Rect::new(x, y, width, height);
+        let polygon = [
+            Point::new(r.min_x, r.min_y),
+            Point::new(r.max_x, r.min_y),
+            Point::new(r.max_x, r.max_y),
+            Point::new(r.min_x, r.max_y),
+        ];
+        self.strokes_in_polygon(&polygon, fully)
+    }
+
+    /// Lasso form of `strokes_in_rect`, taking the closed freehand path as
+    /// interleaved `[x0, y0, x1, y1, ...]` coordinates.
+    #[wasm_bindgen]
+    pub fn strokes_in_lasso(&self, coords: &[f64], fully: bool) -> Vec<String> {
+        self.strokes_in_polygon(&points_from_coords(coords), fully)
+    }
+
+    /// Shows the lasso path as a dashed preview; an empty buffer hides it.
+    #[wasm_bindgen]
+    pub fn set_lasso(&mut self, coords: &[f64]) {
+        self.lasso = points_from_coords(coords);
+    }
+
+    #[wasm_bindgen]
+    pub fn append_lasso_point(&mut self, x: f64, y: f64) {
+        self.lasso.push(Point::new(x, y));
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_lasso(&mut self) {
+        self.lasso.clear();
+    }
+
     #[wasm_bindgen]
     pub fn record_frame(&mut self, time: f64) {
         if self.last_frame_time > 0.0 {
@@ -176,23 +522,32 @@ impl RenderEngine {
     #[wasm_bindgen]
     pub fn render(&self, ctx: &CanvasRenderingContext2d, has_pdf: bool) {
         if !has_pdf {
-            ctx.set_fill_style_str("#ffffff");
+            ctx.set_fill_style_str(&self.display_color("#ffffff"));
             ctx.fill_rect(0.0, 0.0, self.width as f64, self.height as f64);
-            self.draw_grid(ctx);
+            paper::draw_paper(ctx, self.paper, self.width as f64, self.height as f64, |color| self.display_color(color));
         }
         
-        ctx.set_stroke_style_str("#d4d4d8");
+        ctx.set_stroke_style_str(&self.display_color("#d4d4d8"));
         ctx.set_line_width(1.0);
         ctx.stroke_rect(0.0, 0.0, self.width as f64, self.height as f64);
         
-        for stroke in &self.strokes {
-            let is_selected = self.selected_ids.contains(&stroke.id);
-            self.draw_stroke(ctx, stroke, is_selected);
+        let visible = self.viewport.unwrap_or_else(|| Rect::new(0.0, 0.0, self.width as f64, self.height as f64));
+        for index in self.index.query(&visible) {
+            self.draw_stroke(ctx, &self.strokes[index]);
+        }
+
+        if let Some(frame) = self.selection_frame() {
+            self.draw_selection_frame(ctx, &frame);
+        }
+        if let Some(index) = self.selected_editable() {
+            self.draw_vertex_handles(ctx, &self.strokes[index]);
         }
         
         if !self.current_stroke.is_empty() {
             if let Some(ref style) = self.current_style {
-                self.draw_pen_stroke(ctx, &self.current_stroke, &style.color, style.thickness, style.opacity);
+                self.set_ink_blend(ctx, &style.tool);
+                self.draw_pen_stroke(ctx, &self.current_stroke, &style.color, style.thickness, style.opacity, style.pressure_curve);
+                ctx.set_global_composite_operation("source-over").ok();
             }
         }
         
@@ -203,51 +558,208 @@ impl RenderEngine {
         if let Some(ref preview) = self.symbol_preview {
             self.draw_symbol_preview(ctx, preview);
         }
+
+        if self.lasso.len() >= 2 {
+            self.draw_lasso(ctx);
+        }
     }
 
-    fn draw_grid(&self, ctx: &CanvasRenderingContext2d) {
-        ctx.set_stroke_style_str("#e4e4e7");
-        ctx.set_line_width(0.5);
-        
-        ctx.begin_path();
-        
-        let mut x = 0.0;
-        let h = self.height as f64;
-        let w = self.width as f64;
-        
-        while x <= w {
-            ctx.move_to(x, 0.0);
-            ctx.line_to(x, h);
-            x += 20.0;
+    fn strokes_in_polygon(&self, polygon: &[Point], fully: bool) -> Vec<String> {
+        let Some(area) = Rect::from_points(polygon) else {
+            return Vec::new();
+        };
+        self.index
+            .query(&area)
+            .into_iter()
+            .map(|i| &self.strokes[i])
+            .filter(|stroke| {
+                let geometry = hit::stroke_geometry(stroke, |_| self.stroke_text_extent(stroke));
+                hit::polygon_selects(polygon, &geometry, fully)
+            })
+            .map(|stroke| stroke.id.clone())
+            .collect()
+    }
+
+    fn stroke_index(&self, id: &str) -> Option<usize> {
+        self.strokes.iter().position(|s| s.id == id)
+    }
+
+    /// The index of the selected stroke when it is the only one and has points
+    /// that can be dragged on their own.
+    fn selected_editable(&self) -> Option<usize> {
+        let [id] = self.selected_ids.as_slice() else {
+            return None;
+        };
+        self.stroke_index(id).filter(|&i| !path::editable_points(&self.strokes[i]).is_empty())
+    }
+
+    /// The box handles are drawn on: a lone shape or text keeps its own
+    /// rotation, anything else gets the axis-aligned box around the selection.
+    fn selection_frame(&self) -> Option<Frame> {
+        let selected: Vec<&Stroke> = self.strokes.iter().filter(|s| self.selected_ids.contains(&s.id)).collect();
+        if let [stroke] = selected.as_slice() {
+            if transform::has_rotation(stroke) {
+                return transform::stroke_frame(stroke, |_| self.stroke_text_extent(stroke));
+            }
         }
-        
-        let mut y = 0.0;
-        while y <= h {
-            ctx.move_to(0.0, y);
-            ctx.line_to(w, y);
-            y += 20.0;
+        let mut bounds: Option<Rect> = None;
+        for stroke in selected {
+            let geometry = hit::stroke_geometry(stroke, |_| self.stroke_text_extent(stroke));
+            let points: Vec<Point> = geometry.into_iter().flatten().collect();
+            let inflate = if hit::text_content(stroke).is_some() { 0.0 } else { stroke.thickness / 2.0 };
+            if let Some(rect) = Rect::from_points(&points).map(|r| r.inflate(inflate)) {
+                bounds = Some(bounds.map_or(rect, |b| b.union(&rect)));
+            }
         }
-        
-        ctx.stroke();
+        let bounds = bounds?;
+        Some(Frame {
+            center: Point::new((bounds.min_x + bounds.max_x) / 2.0, (bounds.min_y + bounds.max_y) / 2.0),
+            half_width: (bounds.max_x - bounds.min_x) / 2.0,
+            half_height: (bounds.max_y - bounds.min_y) / 2.0,
+            rotation: 0.0,
+        })
+    }
+
+    fn text_extent(&self, text: &str, font_size: f64) -> TextExtent {
+        if let Some(extent) = self.text_extents.borrow().get(&(text.to_string(), font_size.to_bits())) {
+            return *extent;
+        }
+        match self.measuring_context() {
+            Some(ctx) => {
+                ctx.set_font(&text_font(font_size));
+                self.measure_text(ctx, text, font_size)
+            }
+            None => TextExtent::estimate(text, font_size),
+        }
+    }
+
+    /// A text run's measured extent, or a text box's whole box below its
+    /// top-left origin.
+    fn stroke_text_extent(&self, stroke: &Stroke) -> TextExtent {
+        let font_size = text_font_size(stroke);
+        match (&stroke.text_box, hit::text_content(stroke)) {
+            (Some(text_box), Some(_)) => TextExtent {
+                width: text_box.width,
+                ascent: 0.0,
+                descent: text_box.height(self.text_box_lines(text_box, font_size).len(), font_size),
+            },
+            (_, Some(text)) => self.text_extent(text, font_size),
+            _ => TextExtent::estimate("", font_size),
+        }
+    }
+
+    fn text_box_lines(&self, text_box: &TextBox, font_size: f64) -> text_box::Lines {
+        let key = (text_box.font(font_size), text_box.text_width().to_bits(), text_box.content.clone());
+        if let Some(lines) = self.text_layouts.borrow().get(&key) {
+            return lines.clone();
+        }
+        if let Some(ctx) = self.measuring_context() {
+            ctx.set_font(&key.0);
+            return self.layout_text_box(ctx, text_box, font_size);
+        }
+        let estimate = |run: &str| TextExtent::estimate(run, font_size).width;
+        text_box::wrap(&text_box.content, text_box.text_width(), estimate)
+            .into_iter()
+            .map(|line| {
+                let width = estimate(&line);
+                (line, width)
+            })
+            .collect()
     }
 
-    fn draw_stroke(&self, ctx: &CanvasRenderingContext2d, stroke: &Stroke, is_selected: bool) {
+    fn measuring_context(&self) -> Option<&CanvasRenderingContext2d> {
+        self.measuring_context.get_or_init(detached_context).as_ref()
+    }
+
+    /// Wraps a text box with the canvas, whose font must already be set.
+    fn layout_text_box(&self, ctx: &CanvasRenderingContext2d, text_box: &TextBox, font_size: f64) -> text_box::Lines {
+        let key = (text_box.font(font_size), text_box.text_width().to_bits(), text_box.content.clone());
+        if let Some(lines) = self.text_layouts.borrow().get(&key) {
+            return lines.clone();
+        }
+        let measure = |run: &str| ctx.measure_text(run).map_or(TextExtent::estimate(run, font_size).width, |m| m.width());
+        let lines: text_box::Lines = text_box::wrap(&text_box.content, text_box.text_width(), measure)
+            .into_iter()
+            .map(|line| {
+                let width = measure(&line);
+                (line, width)
+            })
+            .collect();
+        self.text_layouts.borrow_mut().insert(key, lines.clone());
+        lines
+    }
+
+    fn measure_text(&self, ctx: &CanvasRenderingContext2d, text: &str, font_size: f64) -> TextExtent {
+        let key = (text.to_string(), font_size.to_bits());
+        if let Some(extent) = self.text_extents.borrow().get(&key) {
+            return *extent;
+        }
+        let extent = match ctx.measure_text(text) {
+            Ok(metrics) => TextExtent {
+                width: metrics.width(),
+                ascent: metrics.actual_bounding_box_ascent().max(font_size * 0.8),
+                descent: metrics.actual_bounding_box_descent().max(font_size * 0.2),
+            },
+            Err(_) => TextExtent::estimate(text, font_size),
+        };
+        self.text_extents.borrow_mut().insert(key, extent);
+        extent
+    }
+
+    fn display_color(&self, color: &str) -> String {
+        match self.color_mode {
+            AnnotationColorMode::Normal => color.to_string(),
+            AnnotationColorMode::Inverted => invert_color_lightness(color),
+        }
+    }
+
+    fn draw_stroke(&self, ctx: &CanvasRenderingContext2d, stroke: &Stroke) {
         if stroke.tool.starts_with("shape-") {
-            self.draw_shape(ctx, stroke, is_selected);
+            self.draw_shape(ctx, stroke);
+        } else if text_box::is_text_box(stroke) {
+            self.draw_text_box(ctx, stroke);
         } else if stroke.tool.starts_with("text:") {
-            self.draw_text(ctx, stroke, is_selected);
+            self.draw_text(ctx, stroke);
         } else {
-            self.draw_pen_stroke(ctx, &stroke.points, &stroke.color, stroke.thickness, stroke.opacity);
+            self.set_ink_blend(ctx, &stroke.tool);
+            self.draw_pen_stroke(ctx, &stroke.points, &stroke.color, stroke.thickness, stroke.opacity, stroke.pressure_curve);
+            ctx.set_global_composite_operation("source-over").ok();
         }
     }
 
-    fn draw_pen_stroke(&self, ctx: &CanvasRenderingContext2d, points: &[Point], color: &str, thickness: f64, opacity: f64) {
+    /// Highlighter ink tints what lies under it instead of covering it:
+    /// multiply on light pages, screen once annotation colours are inverted
+    /// for a dark page. Each stroke is painted as a single path, so its
+    /// opacity stays uniform where it crosses itself.
+    fn set_ink_blend(&self, ctx: &CanvasRenderingContext2d, tool: &str) {
+        let operation = match (tool, self.color_mode) {
+            ("highlighter", AnnotationColorMode::Normal) => "multiply",
+            ("highlighter", AnnotationColorMode::Inverted) => "screen",
+            _ => "source-over",
+        };
+        ctx.set_global_composite_operation(operation).ok();
+    }
+
+    fn draw_pen_stroke(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        points: &[Point],
+        color: &str,
+        thickness: f64,
+        opacity: f64,
+        pressure_curve: Option<PressureCurve>,
+    ) {
         if points.len() < 2 {
             return;
         }
+        if ink::has_pressure(points) {
+            let samples = ink::samples(points, thickness, pressure_curve.unwrap_or_default());
+            self.draw_pressure_stroke(ctx, &samples, color, opacity);
+            return;
+        }
         
         ctx.set_global_alpha(opacity / 100.0);
-        ctx.set_stroke_style_str(color);
+        ctx.set_stroke_style_str(&self.display_color(color));
         ctx.set_line_width(thickness);
         ctx.set_line_cap("round");
         ctx.set_line_join("round");
@@ -267,7 +779,28 @@ impl RenderEngine {
         ctx.set_global_alpha(1.0);
     }
 
-    fn draw_shape(&self, ctx: &CanvasRenderingContext2d, stroke: &Stroke, is_selected: bool) {
+    /// Fills the union of a disc at every sample and the quads between them,
+    /// giving a variable-width outline without doubled-up alpha.
+    fn draw_pressure_stroke(&self, ctx: &CanvasRenderingContext2d, samples: &[(Point, f64)], color: &str, opacity: f64) {
+        ctx.set_global_alpha(opacity / 100.0);
+        ctx.set_fill_style_str(&self.display_color(color));
+        ctx.begin_path();
+        for (p, r) in samples {
+            ctx.move_to(p.x + r, p.y);
+            ctx.arc(p.x, p.y, *r, 0.0, std::f64::consts::PI * 2.0).ok();
+        }
+        for quad in ink::segment_quads(samples) {
+            ctx.move_to(quad[0].x, quad[0].y);
+            for corner in &quad[1..] {
+                ctx.line_to(corner.x, corner.y);
+            }
+            ctx.close_path();
+        }
+        ctx.fill();
+        ctx.set_global_alpha(1.0);
+    }
+
+    fn draw_shape(&self, ctx: &CanvasRenderingContext2d, stroke: &Stroke) {
         if stroke.points.len() < 2 {
             return;
         }
@@ -276,11 +809,13 @@ impl RenderEngine {
         let start = &stroke.points[0];
         let end = &stroke.points[1];
         
+        let style = line_style::effective_style(stroke);
         ctx.set_global_alpha(stroke.opacity / 100.0);
-        ctx.set_stroke_style_str(&stroke.color);
+        ctx.set_stroke_style_str(&self.display_color(&stroke.color));
         ctx.set_line_width(stroke.thickness);
-        ctx.set_line_cap("round");
-        ctx.set_line_join("round");
+        ctx.set_line_cap(style.cap.as_str());
+        ctx.set_line_join(style.join.as_str());
+        ctx.set_line_dash(&dash_array(&style.dash_pattern(stroke.thickness))).ok();
         
         let center_x = (start.x + end.x) / 2.0;
         let center_y = (start.y + end.y) / 2.0;
@@ -288,9 +823,16 @@ impl RenderEngine {
         let height = (end.y - start.y).abs();
         let min_x = start.x.min(end.x);
         let min_y = start.y.min(end.y);
+
+        ctx.save();
+        if stroke.rotation != 0.0 {
+            ctx.translate(center_x, center_y).ok();
+            ctx.rotate(stroke.rotation).ok();
+            ctx.translate(-center_x, -center_y).ok();
+        }
         
         if let Some(ref fill) = stroke.fill_color {
-            ctx.set_fill_style_str(fill);
+            ctx.set_fill_style_str(&self.display_color(fill));
         }
         
         ctx.begin_path();
@@ -309,26 +851,29 @@ impl RenderEngine {
                 }
                 ctx.stroke();
             }
-            "line" => {
-                ctx.move_to(start.x, start.y);
-                ctx.line_to(end.x, end.y);
-                ctx.stroke();
+            "line" | "arrow" => {
+                self.draw_open_line(ctx, &[*start, *end], &style, stroke);
             }
-            "arrow" => {
-                ctx.move_to(start.x, start.y);
-                ctx.line_to(end.x, end.y);
-                ctx.stroke();
-                
-                let angle = (end.y - start.y).atan2(end.x - start.x);
-                let len = 12.0 + stroke.thickness;
-                let spread = std::f64::consts::PI / 7.0;
-                
-                ctx.begin_path();
-                ctx.move_to(end.x, end.y);
-                ctx.line_to(end.x - len * (angle - spread).cos(), end.y - len * (angle - spread).sin());
-                ctx.move_to(end.x, end.y);
-                ctx.line_to(end.x - len * (angle + spread).cos(), end.y - len * (angle + spread).sin());
-                ctx.stroke();
+            "polyline" | "bezier" => {
+                self.draw_open_line(ctx, &path::outline(stroke), &style, stroke);
+            }
+            "polygon" => self.draw_closed_path(ctx, &stroke.points, stroke),
+            "cloud-polygon" => self.draw_closed_path(ctx, &path::outline(stroke), stroke),
+            "cloud" | "bubble" => {
+                if let Some(outline) = markup::outline(stroke) {
+                    self.draw_closed_path(ctx, &outline, stroke);
+                }
+            }
+            "callout" => {
+                if stroke.fill_color.is_some() {
+                    ctx.fill_rect(min_x, min_y, width, height);
+                }
+                ctx.stroke_rect(min_x, min_y, width, height);
+                if let Some(&anchor) = stroke.points.get(2) {
+                    if let Some(leader) = markup::leader(*start, *end, anchor) {
+                        self.draw_open_line(ctx, &leader, &style, stroke);
+                    }
+                }
             }
             "triangle" => {
                 ctx.move_to(center_x, min_y);
@@ -385,76 +930,236 @@ impl RenderEngine {
         }
         
         ctx.set_global_alpha(1.0);
-        
-        if is_selected {
-            self.draw_selection_box(ctx, min_x, min_y, width, height);
+        ctx.set_line_dash(&js_sys::Array::new()).ok();
+        ctx.restore();
+    }
+
+    fn draw_closed_path(&self, ctx: &CanvasRenderingContext2d, points: &[Point], stroke: &Stroke) {
+        let Some(first) = points.first() else {
+            return;
+        };
+        ctx.begin_path();
+        ctx.move_to(first.x, first.y);
+        for p in &points[1..] {
+            ctx.line_to(p.x, p.y);
+        }
+        ctx.close_path();
+        if stroke.fill_color.is_some() {
+            ctx.fill();
         }
+        ctx.stroke();
     }
 
-    fn draw_text(&self, ctx: &CanvasRenderingContext2d, stroke: &Stroke, is_selected: bool) {
+    /// Strokes a polyline with the current dash, then its end markers solid in
+    /// the stroke colour.
+    fn draw_open_line(&self, ctx: &CanvasRenderingContext2d, points: &[Point], style: &LineStyle, stroke: &Stroke) {
+        let (path, markers) = line_style::open_line(points, style, stroke.thickness);
+        ctx.begin_path();
+        ctx.move_to(path[0].x, path[0].y);
+        for p in &path[1..] {
+            ctx.line_to(p.x, p.y);
+        }
+        ctx.stroke();
+
+        ctx.set_line_dash(&js_sys::Array::new()).ok();
+        ctx.set_fill_style_str(&self.display_color(&stroke.color));
+        for (marker, outline) in markers {
+            ctx.begin_path();
+            ctx.move_to(outline[0].x, outline[0].y);
+            for p in &outline[1..] {
+                ctx.line_to(p.x, p.y);
+            }
+            if line_style::is_filled(marker) {
+                ctx.close_path();
+                ctx.fill();
+            } else {
+                ctx.stroke();
+            }
+        }
+    }
+
+    fn draw_text(&self, ctx: &CanvasRenderingContext2d, stroke: &Stroke) {
         if stroke.points.is_empty() {
             return;
         }
         
         let text = stroke.tool.replace("text:", "");
-        let font_size = (stroke.thickness * 4.0).max(14.0);
+        let font_size = text_font_size(stroke);
         
         ctx.set_global_alpha(stroke.opacity / 100.0);
-        ctx.set_fill_style_str(&stroke.color);
-        ctx.set_font(&format!("{}px Inter, system-ui, sans-serif", font_size));
-        ctx.fill_text(&text, stroke.points[0].x, stroke.points[0].y).ok();
+        ctx.set_fill_style_str(&self.display_color(&stroke.color));
+        ctx.set_font(&text_font(font_size));
+        let origin = stroke.points[0];
+        if stroke.rotation != 0.0 {
+            ctx.save();
+            ctx.translate(origin.x, origin.y).ok();
+            ctx.rotate(stroke.rotation).ok();
+            ctx.fill_text(&text, 0.0, 0.0).ok();
+            ctx.restore();
+        } else {
+            ctx.fill_text(&text, origin.x, origin.y).ok();
+        }
         ctx.set_global_alpha(1.0);
         
-        if is_selected {
-            let metrics = ctx.measure_text(&text).unwrap_or_else(|_| ctx.measure_text("M").unwrap());
-            let text_width = metrics.width();
-            self.draw_selection_box(ctx, stroke.points[0].x - 5.0, stroke.points[0].y - font_size, text_width + 10.0, font_size * 1.2);
+        self.measure_text(ctx, &text, font_size);
+    }
+
+    fn draw_text_box(&self, ctx: &CanvasRenderingContext2d, stroke: &Stroke) {
+        let (Some(text_box), Some(&origin)) = (&stroke.text_box, stroke.points.first()) else {
+            return;
+        };
+        let font_size = text_font_size(stroke);
+        ctx.save();
+        ctx.set_font(&text_box.font(font_size));
+        let lines = self.layout_text_box(ctx, text_box, font_size);
+        let (width, height) = (text_box.width, text_box.height(lines.len(), font_size));
+
+        ctx.translate(origin.x, origin.y).ok();
+        ctx.rotate(stroke.rotation).ok();
+        ctx.set_global_alpha(stroke.opacity / 100.0);
+        if let Some(ref fill) = stroke.fill_color {
+            ctx.set_fill_style_str(&self.display_color(fill));
+            ctx.fill_rect(0.0, 0.0, width, height);
         }
+        if let Some(ref border) = text_box.border_color {
+            ctx.set_stroke_style_str(&self.display_color(border));
+            ctx.set_line_width(text_box::BORDER_WIDTH);
+            ctx.stroke_rect(0.0, 0.0, width, height);
+        }
+
+        if self.editing_text.as_deref() != Some(stroke.id.as_str()) {
+            let line_height = text_box.line_height(font_size);
+            let x = match text_box.align {
+                TextAlign::Left => text_box::PADDING,
+                TextAlign::Center => width / 2.0,
+                TextAlign::Right => width - text_box::PADDING,
+            };
+            let underline = (font_size / 14.0).max(1.0);
+            ctx.set_fill_style_str(&self.display_color(&stroke.color));
+            ctx.set_text_align(text_box.align.as_str());
+            ctx.set_text_baseline("alphabetic");
+            for (i, (line, line_width)) in lines.iter().enumerate() {
+                // Each line sits centred in its line height.
+                let baseline = text_box::PADDING + i as f64 * line_height + (line_height + font_size * 0.7) / 2.0;
+                ctx.fill_text(line, x, baseline).ok();
+                if text_box.underline && !line.is_empty() {
+                    let left = match text_box.align {
+                        TextAlign::Left => x,
+                        TextAlign::Center => x - line_width / 2.0,
+                        TextAlign::Right => x - line_width,
+                    };
+                    ctx.fill_rect(left, baseline + font_size * 0.12, *line_width, underline);
+                }
+            }
+        }
+        ctx.restore();
     }
 
-    fn draw_selection_box(&self, ctx: &CanvasRenderingContext2d, x: f64, y: f64, w: f64, h: f64) {
-        let padding = 5.0;
-        let box_x = x - padding;
-        let box_y = y - padding;
-        let box_w = w + padding * 2.0;
-        let box_h = h + padding * 2.0;
-        
+    fn draw_lasso(&self, ctx: &CanvasRenderingContext2d) {
+        ctx.begin_path();
+        ctx.move_to(self.lasso[0].x, self.lasso[0].y);
+        for p in &self.lasso[1..] {
+            ctx.line_to(p.x, p.y);
+        }
+        ctx.close_path();
         ctx.set_fill_style_str("rgba(139, 92, 246, 0.08)");
-        ctx.fill_rect(box_x, box_y, box_w, box_h);
+        ctx.fill();
+
         ctx.set_stroke_style_str("#8b5cf6");
         ctx.set_line_width(1.5);
+        let dash = js_sys::Array::new();
+        dash.push(&JsValue::from(6.0));
+        dash.push(&JsValue::from(4.0));
+        ctx.set_line_dash(&dash).ok();
+        ctx.stroke();
         ctx.set_line_dash(&js_sys::Array::new()).ok();
-        ctx.stroke_rect(box_x, box_y, box_w, box_h);
-        
-        let corner_size = 8.0;
-        ctx.set_fill_style_str("#ffffff");
+    }
+
+    fn draw_selection_frame(&self, ctx: &CanvasRenderingContext2d, frame: &Frame) {
+        let corners = frame.corners();
+        ctx.begin_path();
+        ctx.move_to(corners[0].x, corners[0].y);
+        for corner in &corners[1..] {
+            ctx.line_to(corner.x, corner.y);
+        }
+        ctx.close_path();
+        ctx.set_fill_style_str("rgba(139, 92, 246, 0.08)");
+        ctx.fill();
         ctx.set_stroke_style_str("#8b5cf6");
+        ctx.set_line_width(1.5);
+        ctx.set_line_dash(&js_sys::Array::new()).ok();
+        ctx.stroke();
+
+        let top = frame.handle_position(Handle::Resize { hx: 0, hy: -1 });
+        let knob = frame.handle_position(Handle::Rotate);
+        ctx.begin_path();
+        ctx.move_to(top.x, top.y);
+        ctx.line_to(knob.x, knob.y);
+        ctx.stroke();
+
+        ctx.set_fill_style_str("#ffffff");
         ctx.set_line_width(2.0);
-        
-        let corners = [
-            (box_x - corner_size / 2.0, box_y - corner_size / 2.0),
-            (box_x + box_w - corner_size / 2.0, box_y - corner_size / 2.0),
-            (box_x - corner_size / 2.0, box_y + box_h - corner_size / 2.0),
-            (box_x + box_w - corner_size / 2.0, box_y + box_h - corner_size / 2.0),
-        ];
-        
-        for (cx, cy) in corners {
-            ctx.fill_rect(cx, cy, corner_size, corner_size);
-            ctx.stroke_rect(cx, cy, corner_size, corner_size);
+        ctx.begin_path();
+        ctx.arc(knob.x, knob.y, 5.0, 0.0, std::f64::consts::PI * 2.0).ok();
+        ctx.fill();
+        ctx.stroke();
+
+        let handle_size = 8.0;
+        for handle in frame.resize_handles() {
+            let p = frame.handle_position(handle);
+            ctx.fill_rect(p.x - handle_size / 2.0, p.y - handle_size / 2.0, handle_size, handle_size);
+            ctx.stroke_rect(p.x - handle_size / 2.0, p.y - handle_size / 2.0, handle_size, handle_size);
+        }
+    }
+
+    /// Square handles on every editable point; a Bézier path also shows round
+    /// handles on its control points, tied to their anchors.
+    fn draw_vertex_handles(&self, ctx: &CanvasRenderingContext2d, stroke: &Stroke) {
+        let bezier = stroke.tool == "shape-bezier";
+        ctx.set_stroke_style_str("#8b5cf6");
+        ctx.set_fill_style_str("#ffffff");
+        ctx.set_line_width(1.0);
+        if bezier {
+            ctx.begin_path();
+            for (i, p) in stroke.points.iter().enumerate().filter(|&(i, _)| !path::is_anchor(i)) {
+                let anchor = &stroke.points[if i % 3 == 1 { i - 1 } else { (i + 1).min(stroke.points.len() - 1) }];
+                ctx.move_to(anchor.x, anchor.y);
+                ctx.line_to(p.x, p.y);
+            }
+            ctx.stroke();
+        }
+
+        ctx.set_line_width(1.5);
+        let handle_size = 7.0;
+        for i in path::editable_points(stroke) {
+            let p = &stroke.points[i];
+            if bezier && !path::is_anchor(i) {
+                ctx.begin_path();
+                ctx.arc(p.x, p.y, handle_size / 2.0, 0.0, std::f64::consts::PI * 2.0).ok();
+                ctx.fill();
+                ctx.stroke();
+            } else {
+                ctx.fill_rect(p.x - handle_size / 2.0, p.y - handle_size / 2.0, handle_size, handle_size);
+                ctx.stroke_rect(p.x - handle_size / 2.0, p.y - handle_size / 2.0, handle_size, handle_size);
+            }
         }
     }
 
     fn draw_shape_preview(&self, ctx: &CanvasRenderingContext2d, preview: &ShapePreview) {
         let stroke = Stroke {
             id: String::new(),
-            points: vec![preview.start, preview.end],
+            points: if preview.points.is_empty() { vec![preview.start, preview.end] } else { preview.points.clone() },
             color: preview.color.clone(),
             thickness: preview.thickness,
             opacity: preview.opacity,
             tool: format!("shape-{}", preview.shape_type),
             fill_color: preview.fill_color.clone(),
+            rotation: preview.rotation,
+            pressure_curve: None,
+            line_style: preview.line_style,
+            text_box: None,
         };
-        self.draw_shape(ctx, &stroke, false);
+        self.draw_shape(ctx, &stroke);
     }
 
     fn draw_symbol_preview(&self, ctx: &CanvasRenderingContext2d, preview: &SymbolPreview) {
@@ -462,7 +1167,7 @@ impl RenderEngine {
         let font_size = size.max(14.0);
         
         ctx.set_global_alpha(preview.opacity / 100.0);
-        ctx.set_fill_style_str(&preview.color);
+        ctx.set_fill_style_str(&self.display_color(&preview.color));
         ctx.set_font(&format!("{}px Inter, system-ui, sans-serif", font_size));
         ctx.fill_text(&preview.symbol, preview.start.x, preview.start.y + font_size * 0.8).ok();
         ctx.set_global_alpha(1.0);
@@ -479,37 +1184,237 @@ impl RenderEngine {
 
     #[wasm_bindgen]
     pub fn hit_test(&self, x: f64, y: f64, radius: f64) -> i32 {
-        for (i, stroke) in self.strokes.iter().enumerate().rev() {
-            if stroke.tool == "pen" || stroke.tool == "highlighter" {
-                for p in &stroke.points {
-                    let dist = ((x - p.x).powi(2) + (y - p.y).powi(2)).sqrt();
-                    if dist <= radius + stroke.thickness / 2.0 {
-                        return i as i32;
-                    }
-                }
-            } else if stroke.tool.starts_with("shape-") && stroke.points.len() >= 2 {
-                let min_x = stroke.points[0].x.min(stroke.points[1].x);
-                let min_y = stroke.points[0].y.min(stroke.points[1].y);
-                let max_x = stroke.points[0].x.max(stroke.points[1].x);
-                let max_y = stroke.points[0].y.max(stroke.points[1].y);
-                
-                if x >= min_x - radius && x <= max_x + radius && y >= min_y - radius && y <= max_y + radius {
-                    return i as i32;
-                }
-            } else if stroke.tool.starts_with("text:") && !stroke.points.is_empty() {
-                let font_size = (stroke.thickness * 4.0).max(14.0);
-                let text = stroke.tool.replace("text:", "");
-                let text_width = text.len() as f64 * font_size * 0.6;
-                
-                if x >= stroke.points[0].x - radius && x <= stroke.points[0].x + text_width + radius &&
-                   y >= stroke.points[0].y - font_size - radius && y <= stroke.points[0].y + radius {
-                    return i as i32;
-                }
+        let p = Point::new(x, y);
+        for i in self.index.query(&Rect::around(x, y, radius)).into_iter().rev() {
+            let stroke = &self.strokes[i];
+            // A selected stroke can be grabbed anywhere inside its selection box.
+            let grabbed = self.selected_ids.contains(&stroke.id)
+                && stroke_bounds(stroke).is_some_and(|b| b.inflate(radius).contains(x, y));
+            if grabbed || self.hits_stroke(stroke, p, radius) {
+                return i as i32;
             }
         }
         -1
     }
 
+    fn hits_stroke(&self, stroke: &Stroke, p: Point, radius: f64) -> bool {
+        if stroke.tool == "pen" || stroke.tool == "highlighter" {
+            hit::hits_path(stroke, p, radius)
+        } else if stroke.tool.starts_with("shape-") {
+            hit::hits_shape(stroke, p, radius)
+        } else if let (Some(_), Some(&origin)) = (hit::text_content(stroke), stroke.points.first()) {
+            hit::hits_text(origin, stroke.rotation, self.stroke_text_extent(stroke), p, radius)
+        } else {
+            false
+        }
+    }
+
+    /// Starts an eraser gesture; `erase_path` calls until `end_erase` are
+    /// reported together.
+    #[wasm_bindgen]
+    pub fn begin_erase(&mut self) {
+        self.erase = Some(EraseSession::default());
+    }
+
+    /// Erases along the interleaved eraser path. Pen and highlighter strokes
+    /// are cut where the path crosses them and their surviving pieces get ids
+    /// `{id_prefix}-{n}`; other strokes are removed whole. Returns how many
+    /// strokes changed.
+    #[wasm_bindgen]
+    pub fn erase_path(&mut self, coords: &[f64], radius: f64, id_prefix: &str) -> u32 {
+        let eraser = points_from_coords(coords);
+        let Some(area) = Rect::from_points(&eraser).map(|r| r.inflate(radius)) else {
+            return 0;
+        };
+        let dense = erase::densify(&eraser, radius.max(1.0));
+        let mut changed = 0;
+        let mut next_piece = 0;
+        for i in self.index.query(&area).into_iter().rev() {
+            let stroke = &self.strokes[i];
+            let pieces = if stroke.tool == "pen" || stroke.tool == "highlighter" {
+                match erase::split_points(&stroke.points, &eraser, radius + stroke.thickness / 2.0) {
+                    Some(pieces) => pieces,
+                    None => continue,
+                }
+            } else if dense.iter().any(|&p| self.hits_stroke(stroke, p, radius)) {
+                Vec::new()
+            } else {
+                continue;
+            };
+
+            let original = self.strokes.remove(i);
+            let pieces: Vec<Stroke> = pieces
+                .into_iter()
+                .map(|points| {
+                    next_piece += 1;
+                    Stroke { id: format!("{}-{}", id_prefix, next_piece), points, ..original.clone() }
+                })
+                .collect();
+            let ids: Vec<String> = pieces.iter().map(|s| s.id.clone()).collect();
+            self.strokes.splice(i..i, pieces);
+            self.selected_ids.retain(|id| *id != original.id);
+            if self.selected_id.as_ref() == Some(&original.id) {
+                self.selected_id = None;
+            }
+            if let Some(session) = &mut self.erase {
+                session.record(&original.id, &ids);
+            }
+            changed += 1;
+        }
+        if changed > 0 {
+            self.index.rebuild(self.strokes.iter().map(index_bounds).collect());
+        }
+        changed
+    }
+
+    /// Ends the eraser gesture and returns, for each original stroke it
+    /// touched, `{ id, pieces }` where `pieces` replace it in paint order.
+    #[wasm_bindgen]
+    pub fn end_erase(&mut self) -> String {
+        let Some(session) = self.erase.take() else {
+            return "[]".to_string();
+        };
+        serde_json::to_string(&session.changes(&self.strokes)).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Name of the selection handle under the point ("nw", "n", ..., "rotate",
+    /// or "move" inside the frame), or an empty string.
+    #[wasm_bindgen]
+    pub fn hit_test_handle(&self, x: f64, y: f64, radius: f64) -> String {
+        self.selection_frame()
+            .and_then(|frame| frame.handle_at(Point::new(x, y), radius))
+            .map_or(String::new(), |handle| handle.name().to_string())
+    }
+
+    /// Index of the selected shape's editable point nearest the given point
+    /// within `radius`, or -1. Anchors win over control points lying on them.
+    #[wasm_bindgen]
+    pub fn hit_test_vertex(&self, x: f64, y: f64, radius: f64) -> i32 {
+        let Some(index) = self.selected_editable() else {
+            return -1;
+        };
+        let stroke = &self.strokes[index];
+        let mut best: Option<(usize, f64)> = None;
+        for i in path::editable_points(stroke) {
+            let p = &stroke.points[i];
+            let distance = ((p.x - x).powi(2) + (p.y - y).powi(2)).sqrt();
+            if distance <= radius && best.is_none_or(|(_, d)| distance < d) {
+                best = Some((i, distance));
+            }
+        }
+        best.map_or(-1, |(i, _)| i as i32)
+    }
+
+    /// Starts dragging point `vertex` of the selected shape.
+    #[wasm_bindgen]
+    pub fn begin_vertex_edit(&mut self, vertex: usize) -> bool {
+        let Some(index) = self.selected_editable().filter(|&i| path::editable_points(&self.strokes[i]).contains(&vertex)) else {
+            return false;
+        };
+        self.vertex_edit = Some((vertex, self.strokes[index].clone()));
+        true
+    }
+
+    #[wasm_bindgen]
+    pub fn update_vertex_edit(&mut self, x: f64, y: f64) {
+        let Some((vertex, original)) = &self.vertex_edit else {
+            return;
+        };
+        let Some(index) = self.stroke_index(&original.id) else {
+            return;
+        };
+        let mut stroke = original.clone();
+        path::move_vertex(&mut stroke, *vertex, Point::new(x, y));
+        self.index.set(index, index_bounds(&stroke));
+        self.strokes[index] = stroke;
+    }
+
+    /// Finishes the point drag and returns the edited stroke as JSON, or an
+    /// empty string if nothing moved.
+    #[wasm_bindgen]
+    pub fn end_vertex_edit(&mut self) -> String {
+        let Some((_, original)) = self.vertex_edit.take() else {
+            return String::new();
+        };
+        match self.strokes.iter().find(|s| s.id == original.id) {
+            Some(stroke) if *stroke != original => serde_json::to_string(stroke).unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+
+    /// Hides the text of the text box being edited, so only the editor shows
+    /// it. An empty id shows all text again.
+    #[wasm_bindgen]
+    pub fn set_editing_text(&mut self, id: &str) {
+        self.editing_text = (!id.is_empty()).then(|| id.to_string());
+    }
+
+    #[wasm_bindgen]
+    pub fn cancel_vertex_edit(&mut self) {
+        let Some((_, original)) = self.vertex_edit.take() else {
+            return;
+        };
+        if let Some(index) = self.stroke_index(&original.id) {
+            self.index.set(index, index_bounds(&original));
+            self.strokes[index] = original;
+        }
+    }
+
+    /// Starts dragging `handle` of the selection frame from the given point.
+    #[wasm_bindgen]
+    pub fn begin_transform(&mut self, handle: &str, x: f64, y: f64) -> bool {
+        let (Some(handle), Some(frame)) = (Handle::parse(handle), self.selection_frame()) else {
+            return false;
+        };
+        let originals = self.strokes.iter().filter(|s| self.selected_ids.contains(&s.id)).cloned().collect();
+        self.transform = Some((Gesture { frame, handle, start: Point::new(x, y) }, originals));
+        true
+    }
+
+    /// Re-applies the drag to the selection for the pointer at the given point.
+    /// `constrain` locks the aspect ratio or snaps rotation to 15 degrees.
+    #[wasm_bindgen]
+    pub fn update_transform(&mut self, x: f64, y: f64, constrain: bool) {
+        let Some((gesture, originals)) = &self.transform else {
+            return;
+        };
+        let affine = gesture.affine(Point::new(x, y), constrain);
+        for original in originals {
+            if let Some(index) = self.strokes.iter().position(|s| s.id == original.id) {
+                let stroke = transform::transform_stroke(original, &affine);
+                self.index.set(index, index_bounds(&stroke));
+                self.strokes[index] = stroke;
+            }
+        }
+    }
+
+    /// Finishes the drag and returns the strokes it changed as a JSON array.
+    #[wasm_bindgen]
+    pub fn end_transform(&mut self) -> String {
+        let Some((_, originals)) = self.transform.take() else {
+            return "[]".to_string();
+        };
+        let changed: Vec<&Stroke> = self
+            .strokes
+            .iter()
+            .filter(|s| originals.iter().any(|o| o.id == s.id && o != *s))
+            .collect();
+        serde_json::to_string(&changed).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn cancel_transform(&mut self) {
+        let Some((_, originals)) = self.transform.take() else {
+            return;
+        };
+        for original in originals {
+            if let Some(index) = self.stroke_index(&original.id) {
+                self.index.set(index, index_bounds(&original));
+                self.strokes[index] = original;
+            }
+        }
+    }
+
     #[wasm_bindgen]
     pub fn simplify_points(points_json: &str, tolerance: f64) -> String {
         let points: Vec<Point> = match serde_json::from_str(points_json) {
@@ -524,6 +1429,140 @@ impl RenderEngine {
         let simplified = douglas_peucker(&points, tolerance);
         serde_json::to_string(&simplified).unwrap_or_else(|_| "[]".to_string())
     }
+
+    /// Typed-array form of `simplify_points`: takes and returns interleaved
+    /// `[x0, y0, x1, y1, ...]` coordinates.
+    #[wasm_bindgen]
+    pub fn simplify_coords(coords: &[f64], tolerance: f64) -> Vec<f64> {
+        let points = points_from_coords(coords);
+        let simplified = if points.len() < 3 { points } else { douglas_peucker(&points, tolerance) };
+        simplified.iter().flat_map(|p| [p.x, p.y]).collect()
+    }
+
+    /// Fits a finished stroke with "catmull_rom" or "chaikin", keeping any
+    /// pressure, tilt and time interpolated along the curve.
+    #[wasm_bindgen]
+    pub fn fit_points(points_json: &str, mode: &str) -> String {
+        let points: Vec<Point> = match serde_json::from_str(points_json) {
+            Ok(p) => p,
+            Err(_) => return "[]".to_string(),
+        };
+        serde_json::to_string(&smoothing::fit(&points, mode)).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen]
+    pub fn fit_coords(coords: &[f64], mode: &str) -> Vec<f64> {
+        smoothing::fit(&points_from_coords(coords), mode)
+            .iter()
+            .flat_map(|p| [p.x, p.y])
+            .collect()
+    }
+
+    /// Matches a freehand sketch against the basic shapes. Returns the
+    /// replacement's `{ tool, points, rotation }` as JSON, or an empty string
+    /// when nothing fits closely enough.
+    #[wasm_bindgen]
+    pub fn recognize_shape(coords: &[f64]) -> String {
+        recognize::recognize(&points_from_coords(coords))
+            .and_then(|shape| serde_json::to_string(&shape).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn text_font_size(stroke: &Stroke) -> f64 {
+    (stroke.thickness * 4.0).max(14.0)
+}
+
+fn text_font(font_size: f64) -> String {
+    format!("{}px Inter, system-ui, sans-serif", font_size)
+}
+
+/// A 2D context on a canvas that is never attached, or None outside a browser.
+fn detached_context() -> Option<CanvasRenderingContext2d> {
+    if !cfg!(target_arch = "wasm32") {
+        return None;
+    }
+    web_sys::window()?
+        .document()?
+        .create_element("canvas")
+        .ok()?
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .ok()?
+        .get_context("2d")
+        .ok()??
+        .dyn_into()
+        .ok()
+}
+
+/// Bounds stored in the spatial index, which also cover the selection box.
+fn index_bounds(stroke: &Stroke) -> Option<Rect> {
+    stroke_bounds(stroke).map(|b| b.inflate(spatial::SELECTION_MARGIN))
+}
+
+fn dash_array(pattern: &[f64]) -> js_sys::Array {
+    pattern.iter().map(|&d| JsValue::from(d)).collect()
+}
+
+fn points_from_coords<T: Copy + Into<f64>>(coords: &[T]) -> Vec<Point> {
+    coords
+        .chunks_exact(2)
+        .map(|c| Point::new(c[0].into(), c[1].into()))
+        .collect()
+}
+
+fn points_from_samples(samples: &[f64]) -> Vec<Point> {
+    samples
+        .chunks_exact(3)
+        .map(|c| Point { pressure: Some(c[2]), ..Point::new(c[0], c[1]) })
+        .collect()
+}
+
+fn parse_css_color(color: &str) -> Option<(u8, u8, u8, f64)> {
+    let color = color.trim();
+
+    if let Some(hex) = color.strip_prefix('#') {
+        let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+        let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return match hex.len() {
+            3 => Some((digit(0)? * 17, digit(1)? * 17, digit(2)? * 17, 1.0)),
+            4 => Some((digit(0)? * 17, digit(1)? * 17, digit(2)? * 17, digit(3)? as f64 / 15.0)),
+            6 => Some((byte(0)?, byte(2)?, byte(4)?, 1.0)),
+            8 => Some((byte(0)?, byte(2)?, byte(4)?, byte(6)? as f64 / 255.0)),
+            _ => None,
+        };
+    }
+
+    let args = color
+        .strip_prefix("rgba(")
+        .or_else(|| color.strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+    if parts.len() < 3 {
+        return None;
+    }
+    let channel = |s: &str| s.parse::<f64>().ok().map(|v| v.round().clamp(0.0, 255.0) as u8);
+    let alpha = match parts.get(3) {
+        Some(a) => a.parse::<f64>().ok()?.clamp(0.0, 1.0),
+        None => 1.0,
+    };
+    Some((channel(parts[0])?, channel(parts[1])?, channel(parts[2])?, alpha))
+}
+
+/// Flips HSL lightness while keeping hue and saturation, matching the
+/// backend's inverted page rendering so ink stays readable on dark pages.
+fn invert_color_lightness(color: &str) -> String {
+    let Some((r, g, b, a)) = parse_css_color(color) else {
+        return color.to_string();
+    };
+
+    let shift = 255 - r.max(g).max(b) as i32 - r.min(g).min(b) as i32;
+    let invert = |c: u8| (c as i32 + shift).clamp(0, 255);
+
+    if a >= 1.0 {
+        format!("#{:02x}{:02x}{:02x}", invert(r), invert(g), invert(b))
+    } else {
+        format!("rgba({}, {}, {}, {})", invert(r), invert(g), invert(b), a)
+    }
 }
 
 fn douglas_peucker(points: &[Point], tolerance: f64) -> Vec<Point> {
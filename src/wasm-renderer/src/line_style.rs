@@ -0,0 +1,214 @@
+use crate::{Point, Stroke};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+// Half-angle between an arrowhead's sides and the line.
+const ARROW_SPREAD: f64 = PI / 7.0;
+// Polygon resolution of circle markers for hit testing.
+const CIRCLE_MARKER_SEGMENTS: usize = 16;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DashStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+    DashDot,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LineCap {
+    #[default]
+    Round,
+    Butt,
+    Square,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LineJoin {
+    #[default]
+    Round,
+    Miter,
+    Bevel,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Marker {
+    #[default]
+    None,
+    OpenArrow,
+    Triangle,
+    Circle,
+    Square,
+    Bar,
+}
+
+/// How a stroke's outline is drawn. Markers only apply to open lines.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct LineStyle {
+    pub dash: DashStyle,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub start_marker: Marker,
+    pub end_marker: Marker,
+}
+
+impl LineCap {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Round => "round",
+            Self::Butt => "butt",
+            Self::Square => "square",
+        }
+    }
+}
+
+impl LineJoin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Round => "round",
+            Self::Miter => "miter",
+            Self::Bevel => "bevel",
+        }
+    }
+}
+
+impl LineStyle {
+    /// The canvas dash segments, scaled with the line so thick dashes keep
+    /// their proportions. A zero-length dash under a round or square cap is a dot.
+    pub fn dash_pattern(&self, thickness: f64) -> Vec<f64> {
+        let t = thickness.max(1.0);
+        let dot = if self.cap == LineCap::Butt { t } else { 0.0 };
+        match self.dash {
+            DashStyle::Solid => Vec::new(),
+            DashStyle::Dashed => vec![t * 4.0, t * 2.5],
+            DashStyle::Dotted => vec![dot, t * 2.0],
+            DashStyle::DashDot => vec![t * 4.0, t * 2.0, dot, t * 2.0],
+        }
+    }
+}
+
+/// The stroke's style; arrows saved before line styles existed keep their
+/// open arrowhead.
+pub fn effective_style(stroke: &Stroke) -> LineStyle {
+    stroke.line_style.unwrap_or(LineStyle {
+        end_marker: if stroke.tool == "shape-arrow" { Marker::OpenArrow } else { Marker::None },
+        ..LineStyle::default()
+    })
+}
+
+pub fn has_markers(stroke: &Stroke) -> bool {
+    let style = effective_style(stroke);
+    style.start_marker != Marker::None || style.end_marker != Marker::None
+}
+
+pub fn marker_size(thickness: f64) -> f64 {
+    12.0 + thickness
+}
+
+/// A marker's outline at `tip`, for a line arriving from direction `angle`.
+/// Open arrows and bars are open polylines, the other markers closed polygons.
+pub fn marker_outline(marker: Marker, tip: Point, angle: f64, thickness: f64) -> Vec<Point> {
+    let len = marker_size(thickness);
+    let at = |distance: f64, direction: f64| {
+        Point::new(tip.x - distance * direction.cos(), tip.y - distance * direction.sin())
+    };
+    match marker {
+        Marker::None => Vec::new(),
+        Marker::OpenArrow => vec![at(len, angle - ARROW_SPREAD), tip, at(len, angle + ARROW_SPREAD)],
+        Marker::Triangle => vec![tip, at(len, angle - ARROW_SPREAD), at(len, angle + ARROW_SPREAD)],
+        Marker::Circle => {
+            let r = len / 3.0;
+            (0..CIRCLE_MARKER_SEGMENTS)
+                .map(|i| {
+                    let a = i as f64 / CIRCLE_MARKER_SEGMENTS as f64 * PI * 2.0;
+                    Point::new(tip.x + r * a.cos(), tip.y + r * a.sin())
+                })
+                .collect()
+        }
+        Marker::Square => {
+            let half = len / 3.0;
+            [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .iter()
+                .map(|&(u, v)| {
+                    let (dx, dy) = (u * half, v * half);
+                    Point::new(tip.x + dx * angle.cos() - dy * angle.sin(), tip.y + dx * angle.sin() + dy * angle.cos())
+                })
+                .collect()
+        }
+        Marker::Bar => {
+            let half = len / 2.0;
+            let (nx, ny) = (-angle.sin() * half, angle.cos() * half);
+            vec![Point::new(tip.x - nx, tip.y - ny), Point::new(tip.x + nx, tip.y + ny)]
+        }
+    }
+}
+
+pub fn is_filled(marker: Marker) -> bool {
+    matches!(marker, Marker::Triangle | Marker::Circle | Marker::Square)
+}
+
+/// How far the line stops short of its end so it doesn't show through, or
+/// poke out of, a filled marker.
+fn marker_inset(marker: Marker, thickness: f64) -> f64 {
+    let len = marker_size(thickness);
+    match marker {
+        Marker::Triangle => len * ARROW_SPREAD.cos(),
+        Marker::Circle | Marker::Square => len / 3.0,
+        _ => 0.0,
+    }
+}
+
+/// Direction the line travels into each end point, skipping repeated points.
+fn end_angles(points: &[Point]) -> Option<(f64, f64)> {
+    let distinct = |a: &Point, b: &Point| (a.x - b.x).abs() > 1e-6 || (a.y - b.y).abs() > 1e-6;
+    let first = *points.first()?;
+    let last = *points.last()?;
+    let after_first = points.iter().find(|p| distinct(p, &first))?;
+    let before_last = points.iter().rev().find(|p| distinct(p, &last))?;
+    Some((
+        (first.y - after_first.y).atan2(first.x - after_first.x),
+        (last.y - before_last.y).atan2(last.x - before_last.x),
+    ))
+}
+
+/// Splits an open line into the path to stroke, trimmed under filled
+/// markers, and the markers to draw at each end.
+pub fn open_line(points: &[Point], style: &LineStyle, thickness: f64) -> (Vec<Point>, Vec<(Marker, Vec<Point>)>) {
+    let mut path = points.to_vec();
+    let Some((start_angle, end_angle)) = end_angles(points) else {
+        return (path, Vec::new());
+    };
+    let ends = [(style.start_marker, 0, start_angle), (style.end_marker, points.len() - 1, end_angle)];
+    let mut markers = Vec::new();
+    for (marker, index, angle) in ends {
+        if marker == Marker::None {
+            continue;
+        }
+        let tip = points[index];
+        markers.push((marker, marker_outline(marker, tip, angle, thickness)));
+        let inset = marker_inset(marker, thickness);
+        if inset > 0.0 {
+            path[index] = Point { x: tip.x - inset * angle.cos(), y: tip.y - inset * angle.sin(), ..tip };
+        }
+    }
+    (path, markers)
+}
+
+/// The line plus each marker, as polylines for hit testing and selection.
+pub fn open_line_geometry(points: &[Point], style: &LineStyle, thickness: f64) -> Vec<Vec<Point>> {
+    let (_, markers) = open_line(points, style, thickness);
+    let mut lines = vec![points.to_vec()];
+    lines.extend(markers.into_iter().map(|(marker, mut outline)| {
+        if is_filled(marker) {
+            outline.push(outline[0]);
+        }
+        outline
+    }));
+    lines
+}
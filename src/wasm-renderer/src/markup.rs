@@ -0,0 +1,172 @@
+use crate::{Point, Stroke};
+use std::f64::consts::PI;
+
+// Each cloud bump spans about this much of the outline, plus twice the
+// line thickness so heavy clouds don't turn into scallops.
+const CLOUD_BUMP: f64 = 16.0;
+const BUMP_STEPS: usize = 8;
+// Corner radius of speech bubbles, as a share of their shorter side.
+const BUBBLE_ROUNDING: f64 = 0.2;
+const CORNER_STEPS: usize = 6;
+// Width of a speech bubble's tail where it leaves the box.
+const TAIL_WIDTH: f64 = 0.3;
+
+/// Shapes with a third point, the callout's anchor or the bubble's tail tip,
+/// that is edited on its own.
+pub fn has_tail(tool: &str) -> bool {
+    matches!(tool, "shape-callout" | "shape-bubble")
+}
+
+fn bump_chord(thickness: f64) -> f64 {
+    CLOUD_BUMP + thickness * 2.0
+}
+
+/// How far cloud bumps can bulge past the outline they follow.
+pub fn cloud_bulge(thickness: f64) -> f64 {
+    bump_chord(thickness) / 2.0
+}
+
+fn rect_corners(start: Point, end: Point) -> Vec<Point> {
+    let (min_x, min_y) = (start.x.min(end.x), start.y.min(end.y));
+    let (max_x, max_y) = (start.x.max(end.x), start.y.max(end.y));
+    vec![
+        Point::new(min_x, min_y),
+        Point::new(max_x, min_y),
+        Point::new(max_x, max_y),
+        Point::new(min_x, max_y),
+    ]
+}
+
+/// A revision cloud along a closed polygon: every edge is split into equal
+/// chords, each replaced by a half circle bulging outwards. Degenerate
+/// polygons come back unchanged.
+pub fn cloud_outline(polygon: &[Point], thickness: f64) -> Vec<Point> {
+    if polygon.len() < 2 {
+        return polygon.to_vec();
+    }
+    // Positive area is clockwise on screen, where the outside is to the left.
+    let area: f64 = (0..polygon.len())
+        .map(|i| {
+            let (a, b) = (&polygon[i], &polygon[(i + 1) % polygon.len()]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    let outward = if area >= 0.0 { 1.0 } else { -1.0 };
+
+    let mut out = Vec::new();
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        let length = ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+        if length < 1e-6 {
+            continue;
+        }
+        let bumps = (length / bump_chord(thickness)).round().max(1.0) as usize;
+        let heading = (b.y - a.y).atan2(b.x - a.x);
+        let radius = length / bumps as f64 / 2.0;
+        for bump in 0..bumps {
+            let t = (bump as f64 + 0.5) / bumps as f64;
+            let center = Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+            for step in 0..BUMP_STEPS {
+                let angle = heading + PI + outward * PI * step as f64 / BUMP_STEPS as f64;
+                out.push(Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin()));
+            }
+        }
+    }
+    // Nothing long enough to bump, as for a cloud that was only clicked.
+    if out.is_empty() {
+        return polygon.to_vec();
+    }
+    out
+}
+
+/// A rounded box with a tail reaching out to `tip`, as one closed outline.
+/// The tail leaves from the side facing the tip.
+pub fn bubble_outline(start: Point, end: Point, tip: Point) -> Vec<Point> {
+    let corners = rect_corners(start, end);
+    let (min_x, min_y, max_x, max_y) = (corners[0].x, corners[0].y, corners[2].x, corners[2].y);
+    let (width, height) = (max_x - min_x, max_y - min_y);
+    let r = width.min(height) * BUBBLE_ROUNDING;
+    let center = Point::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+
+    // Sides clockwise from the top, each with its straight span after rounding.
+    let sides = [
+        (Point::new(min_x + r, min_y), Point::new(max_x - r, min_y)),
+        (Point::new(max_x, min_y + r), Point::new(max_x, max_y - r)),
+        (Point::new(max_x - r, max_y), Point::new(min_x + r, max_y)),
+        (Point::new(min_x, max_y - r), Point::new(min_x, min_y + r)),
+    ];
+    let tail_side = {
+        let (dx, dy) = ((tip.x - center.x) / width.max(1e-6), (tip.y - center.y) / height.max(1e-6));
+        match (dx.abs() > dy.abs(), dx > 0.0, dy > 0.0) {
+            (true, true, _) => 1,
+            (true, false, _) => 3,
+            (false, _, true) => 2,
+            (false, _, false) => 0,
+        }
+    };
+    let inside_box = tip.x > min_x && tip.x < max_x && tip.y > min_y && tip.y < max_y;
+    let corner_centers = [
+        Point::new(max_x - r, min_y + r),
+        Point::new(max_x - r, max_y - r),
+        Point::new(min_x + r, max_y - r),
+        Point::new(min_x + r, min_y + r),
+    ];
+
+    let mut out = Vec::new();
+    for (side, &(from, to)) in sides.iter().enumerate() {
+        out.push(from);
+        if side == tail_side && !inside_box {
+            let span = ((to.x - from.x).powi(2) + (to.y - from.y).powi(2)).sqrt();
+            let base = (span * TAIL_WIDTH / 2.0).min(span / 2.0);
+            let mid = Point::new((from.x + to.x) / 2.0, (from.y + to.y) / 2.0);
+            let (ux, uy) = if span > 1e-6 { ((to.x - from.x) / span, (to.y - from.y) / span) } else { (0.0, 0.0) };
+            out.push(Point::new(mid.x - ux * base, mid.y - uy * base));
+            out.push(tip);
+            out.push(Point::new(mid.x + ux * base, mid.y + uy * base));
+        }
+        out.push(to);
+        let corner = corner_centers[side];
+        let first_angle = -PI / 2.0 + side as f64 * PI / 2.0;
+        for step in 1..CORNER_STEPS {
+            let angle = first_angle + PI / 2.0 * step as f64 / CORNER_STEPS as f64;
+            out.push(Point::new(corner.x + r * angle.cos(), corner.y + r * angle.sin()));
+        }
+    }
+    out
+}
+
+/// The callout's leader, from the middle of the box side facing the anchor
+/// to the anchor itself. `None` while the anchor is inside the box.
+pub fn leader(start: Point, end: Point, anchor: Point) -> Option<[Point; 2]> {
+    let corners = rect_corners(start, end);
+    let (min_x, min_y, max_x, max_y) = (corners[0].x, corners[0].y, corners[2].x, corners[2].y);
+    if anchor.x >= min_x && anchor.x <= max_x && anchor.y >= min_y && anchor.y <= max_y {
+        return None;
+    }
+    let center = Point::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+    let (dx, dy) = (
+        (anchor.x - center.x) / (max_x - min_x).max(1e-6),
+        (anchor.y - center.y) / (max_y - min_y).max(1e-6),
+    );
+    let from = if dx.abs() > dy.abs() {
+        Point::new(if dx > 0.0 { max_x } else { min_x }, center.y)
+    } else {
+        Point::new(center.x, if dy > 0.0 { max_y } else { min_y })
+    };
+    Some([from, anchor])
+}
+
+/// The closed outline of a markup shape, before any rotation: the cloud's
+/// bumps, the bubble with its tail, or the callout's box.
+pub fn outline(stroke: &Stroke) -> Option<Vec<Point>> {
+    let (start, end) = (*stroke.points.first()?, *stroke.points.get(1)?);
+    match stroke.tool.as_str() {
+        "shape-cloud" => Some(cloud_outline(&rect_corners(start, end), stroke.thickness)),
+        "shape-callout" => Some(rect_corners(start, end)),
+        "shape-bubble" => Some(match stroke.points.get(2) {
+            Some(&tip) => bubble_outline(start, end, tip),
+            None => rect_corners(start, end),
+        }),
+        _ => None,
+    }
+}
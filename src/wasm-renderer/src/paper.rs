@@ -0,0 +1,199 @@
+use web_sys::CanvasRenderingContext2d;
+
+// Page sizes are in PDF points (1/72 in), which is also one canvas pixel at 100% zoom.
+const GRID_SPACING: f64 = 20.0;
+const DOT_SPACING: f64 = 14.17;
+const WIDE_RULE_SPACING: f64 = 24.75;
+const COLLEGE_RULE_SPACING: f64 = 20.25;
+const ISOMETRIC_SPACING: f64 = 20.0;
+const STAFF_LINE_GAP: f64 = 7.0;
+const STAFF_GAP: f64 = 44.0;
+
+const GRID_COLOR: &str = "#e4e4e7";
+const RULE_COLOR: &str = "#bfdbfe";
+const MARGIN_COLOR: &str = "#fca5a5";
+const STAFF_COLOR: &str = "#a1a1aa";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PaperTemplate {
+    Blank,
+    Ruled,
+    CollegeRuled,
+    #[default]
+    Grid,
+    DotGrid,
+    Isometric,
+    MusicStaff,
+    Cornell,
+}
+
+impl PaperTemplate {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "blank" => Some(Self::Blank),
+            "ruled" => Some(Self::Ruled),
+            "college-ruled" => Some(Self::CollegeRuled),
+            "grid" => Some(Self::Grid),
+            "dot-grid" => Some(Self::DotGrid),
+            "isometric" => Some(Self::Isometric),
+            "music-staff" => Some(Self::MusicStaff),
+            "cornell" => Some(Self::Cornell),
+            _ => None,
+        }
+    }
+}
+
+/// Draws the paper pattern for a blank notebook page. `color` maps the template's
+/// palette through the engine's annotation colour mode.
+pub fn draw_paper(ctx: &CanvasRenderingContext2d, template: PaperTemplate, width: f64, height: f64, color: impl Fn(&str) -> String) {
+    match template {
+        PaperTemplate::Blank => {}
+        PaperTemplate::Grid => draw_grid(ctx, width, height, &color(GRID_COLOR)),
+        PaperTemplate::DotGrid => draw_dots(ctx, width, height, &color(STAFF_COLOR)),
+        PaperTemplate::Ruled | PaperTemplate::CollegeRuled => {
+            let spacing = if template == PaperTemplate::Ruled { WIDE_RULE_SPACING } else { COLLEGE_RULE_SPACING };
+            let top = header_height(height);
+            draw_rules(ctx, 0.0, width, top, height, spacing, &color(RULE_COLOR));
+
+            let margin = (width * 0.15).min(90.0);
+            ctx.set_stroke_style_str(&color(MARGIN_COLOR));
+            ctx.set_line_width(1.0);
+            ctx.begin_path();
+            ctx.move_to(margin, 0.0);
+            ctx.line_to(margin, height);
+            ctx.stroke();
+        }
+        PaperTemplate::Isometric => draw_isometric(ctx, width, height, &color(GRID_COLOR)),
+        PaperTemplate::MusicStaff => draw_staves(ctx, width, height, &color(STAFF_COLOR)),
+        PaperTemplate::Cornell => {
+            // Title band on top, cue column on the left and a summary block at the bottom.
+            let top = header_height(height);
+            let summary = height - height * 0.2;
+            let cue = width * 0.3;
+            draw_rules(ctx, 0.0, width, top, summary, COLLEGE_RULE_SPACING, &color(RULE_COLOR));
+
+            ctx.set_stroke_style_str(&color(MARGIN_COLOR));
+            ctx.set_line_width(1.0);
+            ctx.begin_path();
+            ctx.move_to(0.0, top);
+            ctx.line_to(width, top);
+            ctx.move_to(0.0, summary);
+            ctx.line_to(width, summary);
+            ctx.move_to(cue, top);
+            ctx.line_to(cue, summary);
+            ctx.stroke();
+        }
+    }
+}
+
+fn header_height(height: f64) -> f64 {
+    (height * 0.1).min(72.0)
+}
+
+fn draw_grid(ctx: &CanvasRenderingContext2d, width: f64, height: f64, color: &str) {
+    ctx.set_stroke_style_str(color);
+    ctx.set_line_width(0.5);
+    ctx.begin_path();
+
+    let mut x = 0.0;
+    while x <= width {
+        ctx.move_to(x, 0.0);
+        ctx.line_to(x, height);
+        x += GRID_SPACING;
+    }
+
+    let mut y = 0.0;
+    while y <= height {
+        ctx.move_to(0.0, y);
+        ctx.line_to(width, y);
+        y += GRID_SPACING;
+    }
+
+    ctx.stroke();
+}
+
+fn draw_dots(ctx: &CanvasRenderingContext2d, width: f64, height: f64, color: &str) {
+    let size = 1.5;
+    ctx.set_fill_style_str(color);
+    ctx.begin_path();
+
+    let mut y = DOT_SPACING;
+    while y < height {
+        let mut x = DOT_SPACING;
+        while x < width {
+            ctx.rect(x - size / 2.0, y - size / 2.0, size, size);
+            x += DOT_SPACING;
+        }
+        y += DOT_SPACING;
+    }
+
+    ctx.fill();
+}
+
+fn draw_rules(ctx: &CanvasRenderingContext2d, left: f64, right: f64, top: f64, bottom: f64, spacing: f64, color: &str) {
+    ctx.set_stroke_style_str(color);
+    ctx.set_line_width(0.75);
+    ctx.begin_path();
+
+    let mut y = top + spacing;
+    while y < bottom {
+        ctx.move_to(left, y);
+        ctx.line_to(right, y);
+        y += spacing;
+    }
+
+    ctx.stroke();
+}
+
+fn draw_isometric(ctx: &CanvasRenderingContext2d, width: f64, height: f64, color: &str) {
+    // Verticals plus lines at ±30° meet in equilateral triangles with sides of ISOMETRIC_SPACING.
+    let column = ISOMETRIC_SPACING * 3f64.sqrt() / 2.0;
+    let rise = width * (std::f64::consts::PI / 6.0).tan();
+
+    ctx.set_stroke_style_str(color);
+    ctx.set_line_width(0.5);
+    ctx.begin_path();
+
+    let mut x = 0.0;
+    while x <= width {
+        ctx.move_to(x, 0.0);
+        ctx.line_to(x, height);
+        x += column;
+    }
+
+    let mut y = -rise;
+    while y <= height + rise {
+        ctx.move_to(0.0, y);
+        ctx.line_to(width, y + rise);
+        ctx.move_to(0.0, y);
+        ctx.line_to(width, y - rise);
+        y += ISOMETRIC_SPACING;
+    }
+
+    ctx.stroke();
+}
+
+fn draw_staves(ctx: &CanvasRenderingContext2d, width: f64, height: f64, color: &str) {
+    let margin = (width * 0.08).min(54.0);
+    let staff_height = STAFF_LINE_GAP * 4.0;
+
+    ctx.set_stroke_style_str(color);
+    ctx.set_line_width(0.75);
+    ctx.begin_path();
+
+    let mut top = header_height(height);
+    while top + staff_height <= height - margin {
+        for line in 0..5 {
+            let y = top + line as f64 * STAFF_LINE_GAP;
+            ctx.move_to(margin, y);
+            ctx.line_to(width - margin, y);
+        }
+        ctx.move_to(margin, top);
+        ctx.line_to(margin, top + staff_height);
+        ctx.move_to(width - margin, top);
+        ctx.line_to(width - margin, top + staff_height);
+        top += staff_height + STAFF_GAP;
+    }
+
+    ctx.stroke();
+}
@@ -0,0 +1,109 @@
+use crate::markup;
+use crate::{Point, Stroke};
+use std::ops::Range;
+
+// Each cubic segment is flattened to about this many pixels per step.
+const CURVE_STEP: f64 = 4.0;
+const MAX_CURVE_STEPS: usize = 64;
+
+/// Shapes defined by any number of points rather than a two-point box.
+/// Their points are transformed one by one, so they never carry a rotation.
+pub fn is_path_shape(tool: &str) -> bool {
+    matches!(tool, "shape-polyline" | "shape-polygon" | "shape-bezier" | "shape-cloud-polygon")
+}
+
+/// Path shapes that enclose an area and can be filled.
+pub fn is_closed(tool: &str) -> bool {
+    matches!(tool, "shape-polygon" | "shape-cloud-polygon")
+}
+
+/// Whether point `index` of a Bézier path is an anchor; the two points
+/// between consecutive anchors are the segment's control points.
+pub fn is_anchor(index: usize) -> bool {
+    index.is_multiple_of(3)
+}
+
+/// The points of a Bézier path that form complete cubic segments:
+/// `[anchor, control, control, anchor, control, control, anchor, ...]`.
+fn complete_segments(points: &[Point]) -> &[Point] {
+    let usable = if points.is_empty() { 0 } else { (points.len() - 1) / 3 * 3 + 1 };
+    &points[..usable]
+}
+
+fn cubic(p0: &Point, p1: &Point, p2: &Point, p3: &Point, t: f64) -> Point {
+    let u = 1.0 - t;
+    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    Point::new(
+        a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+        a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+    )
+}
+
+/// A Bézier path as short line segments, for drawing, hit testing and selection.
+pub fn flatten_bezier(points: &[Point]) -> Vec<Point> {
+    let points = complete_segments(points);
+    let Some(&first) = points.first() else {
+        return Vec::new();
+    };
+    let mut out = vec![first];
+    for i in (0..points.len() - 1).step_by(3) {
+        let (p0, p1, p2, p3) = (&points[i], &points[i + 1], &points[i + 2], &points[i + 3]);
+        // The control polygon is never shorter than the curve.
+        let hull = [p0, p1, p2, p3]
+            .windows(2)
+            .map(|w| ((w[1].x - w[0].x).powi(2) + (w[1].y - w[0].y).powi(2)).sqrt())
+            .sum::<f64>();
+        let steps = ((hull / CURVE_STEP).ceil() as usize).clamp(1, MAX_CURVE_STEPS);
+        out.extend((1..=steps).map(|i| cubic(p0, p1, p2, p3, i as f64 / steps as f64)));
+    }
+    out
+}
+
+/// The line a path shape is drawn along; polygons are closed back to their
+/// first vertex, and cloud polygons follow their vertices with bumps.
+pub fn outline(stroke: &Stroke) -> Vec<Point> {
+    match stroke.tool.as_str() {
+        "shape-bezier" => flatten_bezier(&stroke.points),
+        "shape-cloud-polygon" => {
+            let mut points = markup::cloud_outline(&stroke.points, stroke.thickness);
+            points.extend(points.first().copied());
+            points
+        }
+        "shape-polygon" => {
+            let mut points = stroke.points.clone();
+            points.extend(stroke.points.first().copied());
+            points
+        }
+        _ => stroke.points.clone(),
+    }
+}
+
+/// The points of a shape that can be dragged one at a time: every point of a
+/// path shape, or the tail of a callout or speech bubble.
+pub fn editable_points(stroke: &Stroke) -> Range<usize> {
+    if is_path_shape(&stroke.tool) {
+        0..stroke.points.len()
+    } else if markup::has_tail(&stroke.tool) {
+        2.min(stroke.points.len())..stroke.points.len().min(3)
+    } else {
+        0..0
+    }
+}
+
+/// Moves point `index` of a path shape to `to`. Dragging a Bézier anchor
+/// carries its control points along so the curve keeps its shape.
+pub fn move_vertex(stroke: &mut Stroke, index: usize, to: Point) {
+    let Some(&from) = stroke.points.get(index) else {
+        return;
+    };
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let carried = if stroke.tool == "shape-bezier" && is_anchor(index) {
+        index.saturating_sub(1)..=(index + 1).min(stroke.points.len() - 1)
+    } else {
+        index..=index
+    };
+    for p in &mut stroke.points[carried] {
+        p.x += dx;
+        p.y += dy;
+    }
+}
@@ -0,0 +1,329 @@
+use crate::erase::densify;
+use crate::hit::{self, distance_to_polyline, rotate_about, TextExtent};
+use crate::spatial::Rect;
+use crate::{douglas_peucker, Point, Stroke};
+use serde::Serialize;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, TAU};
+
+// Sketches smaller than this (bounding box diagonal) are left alone.
+const MIN_SIZE: f64 = 16.0;
+// A line may stray this far from its chord, relative to its length.
+const LINE_TOLERANCE: f64 = 0.06;
+// Mean distance between a closed sketch and its shape, relative to the
+// sketch's size, above which the shape is not confident enough.
+const MAX_CLOSED_ERROR: f64 = 0.045;
+// Stricter for ellipses, which otherwise swallow pentagons and the like.
+const MAX_ELLIPSE_ERROR: f64 = 0.03;
+// Ends closer than this, relative to the sketch's size, close the outline.
+const CLOSE_GAP: f64 = 0.2;
+// Corner detection: simplification tolerance relative to size, and the
+// least turn that still counts as a corner.
+const CORNER_TOLERANCE: f64 = 0.08;
+const MIN_CORNER_TURN: f64 = 0.45;
+// Rotations this close to axis-aligned are snapped straight.
+const SNAP_ANGLE: f64 = 0.1;
+
+/// A clean shape standing in for a freehand sketch.
+#[derive(Serialize, Debug)]
+pub struct Recognized {
+    pub tool: String,
+    pub points: Vec<Point>,
+    pub rotation: f64,
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+fn path_length(points: &[Point]) -> f64 {
+    points.windows(2).map(|w| distance(&w[0], &w[1])).sum()
+}
+
+/// Classifies a finished pen stroke as a line, arrow, rectangle, ellipse,
+/// triangle or diamond. Returns `None` unless one fits closely.
+pub fn recognize(points: &[Point]) -> Option<Recognized> {
+    let bounds = Rect::from_points(points)?;
+    let size = (bounds.max_x - bounds.min_x).hypot(bounds.max_y - bounds.min_y);
+    if points.len() < 3 || size < MIN_SIZE {
+        return None;
+    }
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if distance(&first, &last) > CLOSE_GAP * size {
+        return line(points).or_else(|| arrow(points));
+    }
+
+    let mut candidates = vec![ellipse(points)];
+    match corners(points, size).as_slice() {
+        [a, b, c] => {
+            // The upright triangle tool when it fits, otherwise the corners as drawn.
+            let (start, end) = corner_box(&[*a, *b, *c]);
+            let upright = shape("shape-triangle", vec![start, end], 0.0);
+            candidates.push(if fit_error(points, &upright) / size <= MAX_CLOSED_ERROR {
+                upright
+            } else {
+                shape("shape-polygon", vec![*a, *b, *c], 0.0)
+            });
+        }
+        [a, b, c, d] => {
+            let quad = [*a, *b, *c, *d];
+            let (start, end) = corner_box(&quad);
+            candidates.push(shape("shape-diamond", vec![start, end], 0.0));
+            candidates.push(rectangle(&quad));
+        }
+        _ => {}
+    }
+    candidates
+        .into_iter()
+        .map(|candidate| (fit_error(points, &candidate) / size, candidate))
+        .filter(|(error, candidate)| {
+            *error <= if candidate.tool == "shape-circle" { MAX_ELLIPSE_ERROR } else { MAX_CLOSED_ERROR }
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| Recognized { tool: candidate.tool, points: candidate.points, rotation: candidate.rotation })
+}
+
+fn shape(tool: &str, points: Vec<Point>, rotation: f64) -> Stroke {
+    Stroke {
+        id: String::new(),
+        points,
+        color: String::new(),
+        thickness: 1.0,
+        opacity: 100.0,
+        tool: tool.to_string(),
+        fill_color: None,
+        rotation,
+        pressure_curve: None,
+        line_style: None,
+        text_box: None,
+    }
+}
+
+/// Whether every point stays close to the chord between the ends.
+fn is_straight(points: &[Point]) -> bool {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let chord = distance(&first, &last);
+    chord > 0.0 && points.iter().all(|&p| hit::distance_to_segment(p, first, last) <= LINE_TOLERANCE * chord)
+}
+
+fn line(points: &[Point]) -> Option<Recognized> {
+    is_straight(points).then(|| Recognized {
+        tool: "shape-line".to_string(),
+        points: vec![points[0], points[points.len() - 1]],
+        rotation: 0.0,
+    })
+}
+
+/// A straight shaft to the point farthest from the start, then a head drawn
+/// back from that tip without leaving its neighbourhood.
+fn arrow(points: &[Point]) -> Option<Recognized> {
+    let start = points[0];
+    let (tip_index, tip) = points
+        .iter()
+        .enumerate()
+        .max_by(|a, b| distance(&start, a.1).total_cmp(&distance(&start, b.1)))?;
+    let (shaft, head) = (&points[..=tip_index], &points[tip_index..]);
+    let shaft_length = distance(&start, tip);
+    let head_reach = head.iter().map(|p| distance(tip, p)).fold(0.0, f64::max);
+    let is_arrow = head.len() >= 3
+        && is_straight(shaft)
+        && head_reach >= shaft_length * 0.1
+        && head_reach <= shaft_length * 0.5
+        && path_length(head) >= shaft_length * 0.15;
+    is_arrow.then(|| Recognized { tool: "shape-arrow".to_string(), points: vec![start, *tip], rotation: 0.0 })
+}
+
+fn ellipse(points: &[Point]) -> Stroke {
+    // Principal axis from the second moments of the points.
+    let n = points.len() as f64;
+    let center = Point::new(points.iter().map(|p| p.x).sum::<f64>() / n, points.iter().map(|p| p.y).sum::<f64>() / n);
+    let (mut xx, mut yy, mut xy) = (0.0, 0.0, 0.0);
+    for p in points {
+        let (dx, dy) = (p.x - center.x, p.y - center.y);
+        xx += dx * dx;
+        yy += dy * dy;
+        xy += dx * dy;
+    }
+    let mut angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+    // A near-circle has no meaningful axis.
+    let spread = ((xx - yy).powi(2) + 4.0 * xy * xy).sqrt() / (xx + yy).max(1e-9);
+    if spread < 0.1 {
+        angle = 0.0;
+    }
+    upright_box("shape-circle", points, center, angle)
+}
+
+/// The box around `points` in a frame turned by `angle` about `center`, as a
+/// shape rotated by that angle (or axis-aligned when nearly so).
+fn upright_box(tool: &str, points: &[Point], center: Point, angle: f64) -> Stroke {
+    let mut angle = (angle + FRAC_PI_4).rem_euclid(FRAC_PI_2) - FRAC_PI_4;
+    if angle.abs() < SNAP_ANGLE {
+        angle = 0.0;
+    }
+    let local: Vec<Point> = points.iter().map(|&p| rotate_about(p, center, -angle)).collect();
+    let (start, end) = corner_box(&local);
+    // Shapes turn about the middle of their box, so carry that back out.
+    let middle = rotate_about(Point::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0), center, angle);
+    let (half_w, half_h) = ((end.x - start.x) / 2.0, (end.y - start.y) / 2.0);
+    shape(
+        tool,
+        vec![Point::new(middle.x - half_w, middle.y - half_h), Point::new(middle.x + half_w, middle.y + half_h)],
+        angle,
+    )
+}
+
+fn rectangle(corners: &[Point; 4]) -> Stroke {
+    let longest = (0..4)
+        .max_by(|&a, &b| distance(&corners[a], &corners[(a + 1) % 4]).total_cmp(&distance(&corners[b], &corners[(b + 1) % 4])))
+        .unwrap_or(0);
+    let (a, b) = (corners[longest], corners[(longest + 1) % 4]);
+    let center = Point::new(corners.iter().map(|p| p.x).sum::<f64>() / 4.0, corners.iter().map(|p| p.y).sum::<f64>() / 4.0);
+    upright_box("shape-rectangle", corners, center, (b.y - a.y).atan2(b.x - a.x))
+}
+
+fn corner_box(points: &[Point]) -> (Point, Point) {
+    let rect = Rect::from_points(points).unwrap_or(Rect::new(0.0, 0.0, 0.0, 0.0));
+    (Point::new(rect.min_x, rect.min_y), Point::new(rect.max_x, rect.max_y))
+}
+
+/// Corners of a closed sketch: the simplified outline without the vertices
+/// where it barely turns, wherever along an edge the pen started.
+fn corners(points: &[Point], size: f64) -> Vec<Point> {
+    let mut vertices = douglas_peucker(points, CORNER_TOLERANCE * size);
+    if vertices.len() > 1 && distance(&vertices[0], &vertices[vertices.len() - 1]) <= CLOSE_GAP * size {
+        vertices.pop();
+    }
+    loop {
+        let n = vertices.len();
+        if n < 3 {
+            return vertices;
+        }
+        let turn = |i: usize| {
+            let (prev, p, next) = (vertices[(i + n - 1) % n], vertices[i], vertices[(i + 1) % n]);
+            let incoming = (p.y - prev.y).atan2(p.x - prev.x);
+            let outgoing = (next.y - p.y).atan2(next.x - p.x);
+            let delta = (outgoing - incoming).rem_euclid(TAU);
+            delta.min(TAU - delta)
+        };
+        match (0..n).min_by(|&a, &b| turn(a).total_cmp(&turn(b))) {
+            Some(flattest) if turn(flattest) < MIN_CORNER_TURN => {
+                vertices.remove(flattest);
+            }
+            _ => return vertices,
+        }
+    }
+}
+
+/// Mean distance from the sketch to the shape and back, so a shape that only
+/// matches part of the sketch (or the other way round) scores badly.
+fn fit_error(points: &[Point], candidate: &Stroke) -> f64 {
+    let geometry = hit::stroke_geometry(candidate, |text| TextExtent::estimate(text, 14.0));
+    let to_shape = |p: &Point| geometry.iter().map(|line| distance_to_polyline(*p, line)).fold(f64::INFINITY, f64::min);
+    let forward = points.iter().map(to_shape).sum::<f64>() / points.len() as f64;
+    let samples: Vec<Point> = geometry.iter().flat_map(|line| densify(line, 4.0)).collect();
+    let backward = samples.iter().map(|p| distance_to_polyline(*p, points)).sum::<f64>() / samples.len().max(1) as f64;
+    forward.max(backward)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Points every few pixels along the corners in order, with a small
+    /// sideways wobble like a hand-drawn line.
+    fn sketch(corners: &[(f64, f64)]) -> Vec<Point> {
+        let mut points = Vec::new();
+        for pair in corners.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            let length = (x1 - x0).hypot(y1 - y0);
+            let steps = (length / 4.0).ceil() as usize;
+            for k in 0..steps {
+                let t = k as f64 / steps as f64;
+                let wobble = (points.len() as f64 * 0.7).sin() * 0.8;
+                let (nx, ny) = ((y0 - y1) / length, (x1 - x0) / length);
+                points.push(Point::new(x0 + (x1 - x0) * t + nx * wobble, y0 + (y1 - y0) * t + ny * wobble));
+            }
+        }
+        let &(x, y) = corners.last().unwrap();
+        points.push(Point::new(x, y));
+        points
+    }
+
+    fn ellipse_sketch(cx: f64, cy: f64, rx: f64, ry: f64) -> Vec<Point> {
+        (0..=64)
+            .map(|i| {
+                let angle = i as f64 / 64.0 * TAU;
+                Point::new(cx + rx * angle.cos(), cy + ry * angle.sin())
+            })
+            .collect()
+    }
+
+    fn tool(points: &[Point]) -> Option<String> {
+        recognize(points).map(|shape| shape.tool)
+    }
+
+    #[test]
+    fn recognizes_a_line_between_its_ends() {
+        let shape = recognize(&sketch(&[(10.0, 10.0), (210.0, 60.0)])).unwrap();
+
+        assert_eq!(shape.tool, "shape-line");
+        assert_eq!((shape.points[0].x, shape.points[0].y), (10.0, 10.0));
+        assert_eq!((shape.points[1].x, shape.points[1].y), (210.0, 60.0));
+    }
+
+    #[test]
+    fn recognizes_an_axis_aligned_rectangle() {
+        let shape = recognize(&sketch(&[(0.0, 0.0), (200.0, 0.0), (200.0, 100.0), (0.0, 100.0), (0.0, 0.0)])).unwrap();
+
+        assert_eq!(shape.tool, "shape-rectangle");
+        assert_eq!(shape.rotation, 0.0);
+        let (start, end) = (shape.points[0], shape.points[1]);
+        assert!((end.x - start.x - 200.0).abs() < 4.0 && (end.y - start.y - 100.0).abs() < 4.0, "{:?}", shape.points);
+    }
+
+    #[test]
+    fn recognizes_an_ellipse() {
+        let shape = recognize(&ellipse_sketch(100.0, 100.0, 80.0, 40.0)).unwrap();
+
+        assert_eq!(shape.tool, "shape-circle");
+        let (start, end) = (shape.points[0], shape.points[1]);
+        assert!((start.x - 20.0).abs() < 2.0 && (end.x - 180.0).abs() < 2.0, "{:?}", shape.points);
+        assert!((start.y - 60.0).abs() < 2.0 && (end.y - 140.0).abs() < 2.0, "{:?}", shape.points);
+    }
+
+    #[test]
+    fn recognizes_a_triangle() {
+        let triangle = sketch(&[(100.0, 0.0), (200.0, 160.0), (0.0, 160.0), (100.0, 0.0)]);
+
+        assert_eq!(tool(&triangle).as_deref(), Some("shape-triangle"));
+    }
+
+    #[test]
+    fn recognizes_an_arrow_from_tail_to_tip() {
+        let shape = recognize(&sketch(&[(0.0, 100.0), (200.0, 100.0), (170.0, 80.0), (170.0, 120.0)])).unwrap();
+
+        assert_eq!(shape.tool, "shape-arrow");
+        assert_eq!(shape.points[0].x, 0.0);
+        // The tip is the sketched point farthest from the tail.
+        assert!((shape.points[1].x - 200.0).abs() < 1.0 && (shape.points[1].y - 100.0).abs() < 1.0, "{:?}", shape.points);
+    }
+
+    #[test]
+    fn leaves_scribbles_alone() {
+        let scribble: Vec<Point> = (0..120)
+            .map(|i| {
+                let t = i as f64 / 10.0;
+                Point::new(t * 15.0 + (t * 3.1).sin() * 30.0, (t * 1.7).cos() * 50.0 + (t * 4.3).sin() * 25.0)
+            })
+            .collect();
+
+        assert_eq!(tool(&scribble), None);
+    }
+
+    #[test]
+    fn needs_enough_distinct_points() {
+        assert_eq!(tool(&[]), None);
+        assert_eq!(tool(&[Point::new(0.0, 0.0)]), None);
+        assert_eq!(tool(&[Point::new(0.0, 0.0), Point::new(200.0, 0.0)]), None);
+        assert_eq!(tool(&[Point::new(50.0, 50.0); 40]), None);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::Point;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+const CHAIKIN_ITERATIONS: usize = 2;
+// Catmull-Rom spans are flattened to roughly this many pixels per segment.
+const SPLINE_STEP: f64 = 4.0;
+const MAX_SPLINE_SEGMENTS: usize = 12;
+// Derivative cutoff of the One Euro filter, in Hz.
+const ONE_EURO_DERIVATIVE_CUTOFF: f64 = 1.0;
+
+/// How raw pointer positions are filtered while a stroke is being drawn.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Stabilizer {
+    #[default]
+    Off,
+    /// Averages the last `window` positions.
+    MovingAverage { window: usize },
+    /// Speed-adaptive low-pass filter: heavy smoothing when slow, little lag
+    /// when fast. `min_cutoff` is in Hz, `beta` scales with speed in px/s.
+    OneEuro { min_cutoff: f64, beta: f64 },
+    /// The pen trails the pointer on a string of length `radius` and only
+    /// moves once the string is pulled taut.
+    LazyBrush { radius: f64 },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct LowPass {
+    value: f64,
+    derivative: f64,
+}
+
+/// Filter state for the stroke in progress.
+#[derive(Default)]
+pub struct StabilizerState {
+    config: Stabilizer,
+    window: VecDeque<Point>,
+    euro: Option<(LowPass, LowPass, f64)>,
+    brush: Option<Point>,
+    last_raw: Option<Point>,
+    last_emitted: Option<Point>,
+}
+
+impl StabilizerState {
+    pub fn new(config: Stabilizer) -> Self {
+        Self { config, ..Self::default() }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.config);
+    }
+
+    /// Feeds a raw position sampled at `time` milliseconds and returns the
+    /// point to append, or `None` while the filter holds the pen still.
+    pub fn push(&mut self, raw: Point, time: f64) -> Option<Point> {
+        self.last_raw = Some(raw);
+        let filtered = match self.config {
+            Stabilizer::Off => Some(raw),
+            Stabilizer::MovingAverage { window } => {
+                self.window.push_back(raw);
+                while self.window.len() > window.max(1) {
+                    self.window.pop_front();
+                }
+                let n = self.window.len() as f64;
+                let (x, y) = self.window.iter().fold((0.0, 0.0), |(x, y), p| (x + p.x, y + p.y));
+                Some(Point { x: x / n, y: y / n, ..raw })
+            }
+            Stabilizer::OneEuro { min_cutoff, beta } => {
+                let (fx, fy) = match self.euro {
+                    None => (LowPass { value: raw.x, derivative: 0.0 }, LowPass { value: raw.y, derivative: 0.0 }),
+                    Some((fx, fy, last_time)) => {
+                        // Coalesced events can share a timestamp; assume 120 Hz.
+                        let dt = if time > last_time { (time - last_time) / 1000.0 } else { 1.0 / 120.0 };
+                        (one_euro(fx, raw.x, dt, min_cutoff, beta), one_euro(fy, raw.y, dt, min_cutoff, beta))
+                    }
+                };
+                self.euro = Some((fx, fy, time));
+                Some(Point { x: fx.value, y: fy.value, ..raw })
+            }
+            Stabilizer::LazyBrush { radius } => match self.brush {
+                None => {
+                    self.brush = Some(raw);
+                    Some(raw)
+                }
+                Some(brush) => {
+                    let (dx, dy) = (raw.x - brush.x, raw.y - brush.y);
+                    let distance = (dx * dx + dy * dy).sqrt();
+                    if distance <= radius {
+                        None
+                    } else {
+                        let pull = 1.0 - radius / distance;
+                        let moved = Point { x: brush.x + dx * pull, y: brush.y + dy * pull, ..raw };
+                        self.brush = Some(moved);
+                        Some(moved)
+                    }
+                }
+            },
+        };
+        if filtered.is_some() {
+            self.last_emitted = filtered;
+        }
+        filtered
+    }
+
+    /// On pointer up, the lagging filters catch up to the last raw position so
+    /// the stroke ends where the pointer did. The lazy brush stays put.
+    pub fn finish(&mut self) -> Option<Point> {
+        let tail = match self.config {
+            Stabilizer::MovingAverage { .. } | Stabilizer::OneEuro { .. } => {
+                self.last_raw.filter(|raw| self.last_emitted.is_some_and(|e| e.x != raw.x || e.y != raw.y))
+            }
+            Stabilizer::Off | Stabilizer::LazyBrush { .. } => None,
+        };
+        self.reset();
+        tail
+    }
+}
+
+fn smoothing_factor(cutoff: f64, dt: f64) -> f64 {
+    let tau = 1.0 / (2.0 * PI * cutoff.max(1e-3));
+    1.0 / (1.0 + tau / dt)
+}
+
+fn one_euro(state: LowPass, raw: f64, dt: f64, min_cutoff: f64, beta: f64) -> LowPass {
+    let speed = (raw - state.value) / dt;
+    let d_alpha = smoothing_factor(ONE_EURO_DERIVATIVE_CUTOFF, dt);
+    let derivative = state.derivative + d_alpha * (speed - state.derivative);
+    let alpha = smoothing_factor(min_cutoff + beta * derivative.abs(), dt);
+    LowPass { value: state.value + alpha * (raw - state.value), derivative }
+}
+
+fn lerp_option(a: Option<f64>, b: Option<f64>, t: f64) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + (b - a) * t),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Interpolates position and any stylus state the two points share.
+pub fn lerp(a: &Point, b: &Point, t: f64) -> Point {
+    Point {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+        pressure: lerp_option(a.pressure, b.pressure, t),
+        tilt_x: lerp_option(a.tilt_x, b.tilt_x, t),
+        tilt_y: lerp_option(a.tilt_y, b.tilt_y, t),
+        time: lerp_option(a.time, b.time, t),
+    }
+}
+
+/// Corner cutting that keeps both end points.
+pub fn chaikin(points: &[Point]) -> Vec<Point> {
+    let mut current = points.to_vec();
+    for _ in 0..CHAIKIN_ITERATIONS {
+        if current.len() < 3 {
+            break;
+        }
+        let mut next = Vec::with_capacity(current.len() * 2);
+        next.push(current[0]);
+        for pair in current.windows(2) {
+            next.push(lerp(&pair[0], &pair[1], 0.25));
+            next.push(lerp(&pair[0], &pair[1], 0.75));
+        }
+        next.push(current[current.len() - 1]);
+        current = next;
+    }
+    current
+}
+
+/// Centripetal Catmull-Rom spline through every point, flattened to short
+/// segments. Unlike corner cutting it passes through the input points.
+pub fn catmull_rom(points: &[Point]) -> Vec<Point> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut out = vec![points[0]];
+    for i in 0..points.len() - 1 {
+        let (p1, p2) = (&points[i], &points[i + 1]);
+        // The ends are extended by mirroring their neighbour.
+        let p0 = if i == 0 { Point::new(2.0 * p1.x - p2.x, 2.0 * p1.y - p2.y) } else { points[i - 1] };
+        let p3 = points.get(i + 2).copied().unwrap_or(Point::new(2.0 * p2.x - p1.x, 2.0 * p2.y - p1.y));
+
+        let span = ((p2.x - p1.x).powi(2) + (p2.y - p1.y).powi(2)).sqrt();
+        if span < 1e-6 {
+            continue;
+        }
+        let segments = ((span / SPLINE_STEP).ceil() as usize).clamp(1, MAX_SPLINE_SEGMENTS);
+        for step in 1..segments {
+            let t = step as f64 / segments as f64;
+            let position = centripetal(&p0, p1, p2, &p3, t);
+            out.push(Point { x: position.0, y: position.1, ..lerp(p1, p2, t) });
+        }
+        out.push(*p2);
+    }
+    out
+}
+
+/// Barry-Goldman evaluation of the centripetal (alpha = 0.5) spline between
+/// `p1` and `p2` at `t` in 0..1.
+fn centripetal(p0: &Point, p1: &Point, p2: &Point, p3: &Point, t: f64) -> (f64, f64) {
+    let knot = |a: &Point, b: &Point| ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt().sqrt().max(1e-6);
+    let t0 = 0.0;
+    let t1 = t0 + knot(p0, p1);
+    let t2 = t1 + knot(p1, p2);
+    let t3 = t2 + knot(p2, p3);
+    let t = t1 + (t2 - t1) * t;
+
+    let mix = |a: (f64, f64), b: (f64, f64), ta: f64, tb: f64| {
+        let (wa, wb) = ((tb - t) / (tb - ta), (t - ta) / (tb - ta));
+        (a.0 * wa + b.0 * wb, a.1 * wa + b.1 * wb)
+    };
+    let (q0, q1, q2, q3) = ((p0.x, p0.y), (p1.x, p1.y), (p2.x, p2.y), (p3.x, p3.y));
+    let a1 = mix(q0, q1, t0, t1);
+    let a2 = mix(q1, q2, t1, t2);
+    let a3 = mix(q2, q3, t2, t3);
+    let b1 = mix(a1, a2, t0, t2);
+    let b2 = mix(a2, a3, t1, t3);
+    mix(b1, b2, t1, t2)
+}
+
+/// Applies the named curve fit ("catmull_rom" or "chaikin"); anything else
+/// returns the points unchanged.
+pub fn fit(points: &[Point], mode: &str) -> Vec<Point> {
+    match mode {
+        "catmull_rom" => catmull_rom(points),
+        "chaikin" => chaikin(points),
+        _ => points.to_vec(),
+    }
+}
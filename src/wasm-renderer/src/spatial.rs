@@ -0,0 +1,351 @@
+use crate::hit::{rotate_about, rotation_pivot};
+use crate::{line_style, markup, path, text_box};
+use crate::{Point, Stroke};
+use std::collections::HashMap;
+
+const CELL_SIZE: f64 = 128.0;
+// Strokes covering more cells than this are kept in one list and always checked.
+const MAX_CELLS_PER_STROKE: i64 = 1024;
+// Room for the selection box and its handles, which are drawn outside the stroke.
+pub const SELECTION_MARGIN: f64 = 16.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rect {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            min_x: x.min(x + width),
+            min_y: y.min(y + height),
+            max_x: x.max(x + width),
+            max_y: y.max(y + height),
+        }
+    }
+
+    pub fn around(x: f64, y: f64, radius: f64) -> Self {
+        Self::new(x - radius, y - radius, radius * 2.0, radius * 2.0)
+    }
+
+    pub fn from_points(points: &[Point]) -> Option<Self> {
+        let first = points.first()?;
+        let mut rect = Self::new(first.x, first.y, 0.0, 0.0);
+        for p in &points[1..] {
+            rect.min_x = rect.min_x.min(p.x);
+            rect.min_y = rect.min_y.min(p.y);
+            rect.max_x = rect.max_x.max(p.x);
+            rect.max_y = rect.max_y.max(p.y);
+        }
+        Some(rect)
+    }
+
+    pub fn inflate(&self, amount: f64) -> Self {
+        Self {
+            min_x: self.min_x - amount,
+            min_y: self.min_y - amount,
+            max_x: self.max_x + amount,
+            max_y: self.max_y + amount,
+        }
+    }
+
+    pub fn union(&self, other: &Rect) -> Self {
+        Self {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.min_x <= other.max_x && other.min_x <= self.max_x && self.min_y <= other.max_y && other.min_y <= self.max_y
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        self.min_x <= x && x <= self.max_x && self.min_y <= y && y <= self.max_y
+    }
+
+    fn is_finite(&self) -> bool {
+        self.min_x.is_finite() && self.min_y.is_finite() && self.max_x.is_finite() && self.max_y.is_finite()
+    }
+}
+
+/// Conservative bounds of everything `draw_stroke` paints for `stroke`.
+pub fn stroke_bounds(stroke: &Stroke) -> Option<Rect> {
+    let half_width = stroke.thickness.max(0.0) / 2.0;
+    let bounds = if stroke.tool.starts_with("shape-") {
+        // A Bézier curve stays inside the hull of its control points.
+        let rect = if path::is_path_shape(&stroke.tool) || markup::has_tail(&stroke.tool) {
+            Rect::from_points(&stroke.points)?
+        } else {
+            rotated(Rect::from_points(stroke.points.get(..2)?)?, stroke)?
+        };
+        let reach = if line_style::has_markers(stroke) {
+            line_style::marker_size(stroke.thickness) + half_width
+        } else {
+            half_width
+        };
+        if matches!(stroke.tool.as_str(), "shape-cloud" | "shape-cloud-polygon") {
+            rect.inflate(reach + markup::cloud_bulge(stroke.thickness))
+        } else {
+            rect.inflate(reach)
+        }
+    } else if let Some(text_box) = stroke.text_box.as_ref().filter(|_| text_box::is_text_box(stroke)) {
+        let origin = stroke.points.first()?;
+        let font_size = (stroke.thickness * 4.0).max(14.0);
+        // Glyphs as wide as the font is tall wrap to more lines than real text.
+        let lines = text_box::wrap(&text_box.content, text_box.text_width(), |run| run.chars().count() as f64 * font_size);
+        rotated(Rect::new(origin.x, origin.y, text_box.width, text_box.height(lines.len(), font_size)), stroke)?
+            .inflate(text_box::BORDER_WIDTH)
+    } else if let Some(text) = stroke.tool.strip_prefix("text:") {
+        let origin = stroke.points.first()?;
+        let font_size = (stroke.thickness * 4.0).max(14.0);
+        // Wider than any real glyph run, so culling never clips text.
+        let width = text.chars().count() as f64 * font_size;
+        rotated(Rect::new(origin.x, origin.y - font_size, width, font_size * 1.2), stroke)?
+    } else {
+        Rect::from_points(&stroke.points)?.inflate(half_width)
+    };
+    bounds.is_finite().then_some(bounds)
+}
+
+/// Bounds of `rect` once turned by the stroke's rotation.
+fn rotated(rect: Rect, stroke: &Stroke) -> Option<Rect> {
+    if stroke.rotation == 0.0 {
+        return Some(rect);
+    }
+    let pivot = rotation_pivot(stroke)?;
+    let corners = [(rect.min_x, rect.min_y), (rect.max_x, rect.min_y), (rect.max_x, rect.max_y), (rect.min_x, rect.max_y)]
+        .map(|(x, y)| rotate_about(Point::new(x, y), pivot, stroke.rotation));
+    Rect::from_points(&corners)
+}
+
+/// Uniform grid over stroke bounds, keyed by the stroke's index in paint
+/// order. Queries return indices in ascending (bottom to top) order.
+#[derive(Default)]
+pub struct SpatialIndex {
+    bounds: Vec<Option<Rect>>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    oversized: Vec<usize>,
+}
+
+impl SpatialIndex {
+    pub fn rebuild(&mut self, bounds: Vec<Option<Rect>>) {
+        self.bounds = bounds;
+        self.cells.clear();
+        self.oversized.clear();
+        for index in 0..self.bounds.len() {
+            self.insert_cells(index);
+        }
+    }
+
+    /// Sets the bounds of the stroke at `index`, which may be one past the end.
+    pub fn set(&mut self, index: usize, bounds: Option<Rect>) {
+        if index == self.bounds.len() {
+            self.bounds.push(bounds);
+        } else {
+            self.remove_cells(index);
+            self.bounds[index] = bounds;
+        }
+        self.insert_cells(index);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.remove_cells(index);
+        self.bounds.remove(index);
+        self.shift_indices(|i| if i > index { i - 1 } else { i });
+    }
+
+    pub fn reorder(&mut self, from: usize, to: usize) {
+        self.remove_cells(from);
+        let moved = self.bounds.remove(from);
+        self.bounds.insert(to, moved);
+        if from < to {
+            self.shift_indices(|i| if (from + 1..=to).contains(&i) { i - 1 } else { i });
+        } else {
+            self.shift_indices(|i| if (to..from).contains(&i) { i + 1 } else { i });
+        }
+        self.insert_cells(to);
+    }
+
+    /// Indices of strokes whose bounds intersect `area`.
+    pub fn query(&self, area: &Rect) -> Vec<usize> {
+        let mut found = self.oversized.clone();
+        if let Some(((x0, y0), (x1, y1))) = cell_range(area) {
+            if cell_count(((x0, y0), (x1, y1))) > MAX_CELLS_PER_STROKE {
+                found.extend(0..self.bounds.len());
+            } else {
+                for cx in x0..=x1 {
+                    for cy in y0..=y1 {
+                        if let Some(entries) = self.cells.get(&(cx as i32, cy as i32)) {
+                            found.extend(entries);
+                        }
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found.retain(|&index| self.bounds[index].is_some_and(|b| b.intersects(area)));
+        found
+    }
+
+    fn insert_cells(&mut self, index: usize) {
+        let Some(((x0, y0), (x1, y1))) = self.bounds[index].as_ref().and_then(cell_range) else {
+            return;
+        };
+        if cell_count(((x0, y0), (x1, y1))) > MAX_CELLS_PER_STROKE {
+            self.oversized.push(index);
+            return;
+        }
+        for cx in x0..=x1 {
+            for cy in y0..=y1 {
+                self.cells.entry((cx as i32, cy as i32)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Renumbers every stored entry, which keeps cells in place when strokes
+    /// below them are removed or moved.
+    fn shift_indices(&mut self, shift: impl Fn(usize) -> usize) {
+        for index in self.cells.values_mut().flatten().chain(&mut self.oversized) {
+            *index = shift(*index);
+        }
+    }
+
+    fn remove_cells(&mut self, index: usize) {
+        self.oversized.retain(|&i| i != index);
+        let Some(((x0, y0), (x1, y1))) = self.bounds[index].as_ref().and_then(cell_range) else {
+            return;
+        };
+        if cell_count(((x0, y0), (x1, y1))) > MAX_CELLS_PER_STROKE {
+            return;
+        }
+        for cx in x0..=x1 {
+            for cy in y0..=y1 {
+                if let Some(entries) = self.cells.get_mut(&(cx as i32, cy as i32)) {
+                    entries.retain(|&i| i != index);
+                }
+            }
+        }
+    }
+}
+
+fn cell_range(rect: &Rect) -> Option<((i64, i64), (i64, i64))> {
+    if !rect.is_finite() {
+        return None;
+    }
+    let cell = |v: f64| (v / CELL_SIZE).floor().clamp(i32::MIN as f64, i32::MAX as f64) as i64;
+    Some(((cell(rect.min_x), cell(rect.min_y)), (cell(rect.max_x), cell(rect.max_y))))
+}
+
+/// Cells in a range; saturates, as ranges can span the whole i32 grid.
+fn cell_count(((x0, y0), (x1, y1)): ((i64, i64), (i64, i64))) -> i64 {
+    (x1 - x0 + 1).saturating_mul(y1 - y0 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small deterministic generator, so failures reproduce.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 33) as usize % n
+        }
+
+        fn bounds(&mut self) -> Option<Rect> {
+            match self.below(8) {
+                0 => None,
+                // Tall enough to exceed MAX_CELLS_PER_STROKE.
+                1 => Some(Rect::new(self.below(2000) as f64, 0.0, self.below(600) as f64, 200_000.0)),
+                _ => Some(Rect::new(self.below(2000) as f64, self.below(2000) as f64, self.below(400) as f64, self.below(400) as f64)),
+            }
+        }
+
+        fn area(&mut self) -> Rect {
+            Rect::new(self.below(2600) as f64 - 300.0, self.below(2600) as f64 - 300.0, self.below(900) as f64, self.below(900) as f64)
+        }
+    }
+
+    fn rebuilt(bounds: &[Option<Rect>]) -> SpatialIndex {
+        let mut index = SpatialIndex::default();
+        index.rebuild(bounds.to_vec());
+        index
+    }
+
+    #[test]
+    fn edits_match_a_rebuilt_index() {
+        let mut rng = Lcg(7);
+        for _ in 0..200 {
+            let mut bounds: Vec<Option<Rect>> = (0..1 + rng.below(20)).map(|_| rng.bounds()).collect();
+            let mut index = rebuilt(&bounds);
+
+            for _ in 0..12 {
+                match rng.below(3) {
+                    0 if !bounds.is_empty() => {
+                        let at = rng.below(bounds.len());
+                        index.remove(at);
+                        bounds.remove(at);
+                    }
+                    1 if !bounds.is_empty() => {
+                        let (from, to) = (rng.below(bounds.len()), rng.below(bounds.len()));
+                        index.reorder(from, to);
+                        let moved = bounds.remove(from);
+                        bounds.insert(to, moved);
+                    }
+                    _ => {
+                        let at = rng.below(bounds.len() + 1);
+                        let rect = rng.bounds();
+                        index.set(at, rect);
+                        if at == bounds.len() {
+                            bounds.push(rect);
+                        } else {
+                            bounds[at] = rect;
+                        }
+                    }
+                }
+
+                let expected = rebuilt(&bounds);
+                for _ in 0..6 {
+                    let area = rng.area();
+                    assert_eq!(index.query(&area), expected.query(&area), "bounds {:?}, area {:?}", bounds, area);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn query_returns_ascending_unique_indices() {
+        let bounds = vec![
+            Some(Rect::new(0.0, 0.0, 300.0, 300.0)),
+            None,
+            Some(Rect::new(100.0, 100.0, 10.0, 10.0)),
+            Some(Rect::new(1000.0, 1000.0, 10.0, 10.0)),
+            Some(Rect::new(0.0, 0.0, 1.0, 1_000_000.0)),
+        ];
+        let index = rebuilt(&bounds);
+
+        assert_eq!(index.query(&Rect::new(50.0, 50.0, 100.0, 100.0)), vec![0, 2]);
+        assert_eq!(index.query(&Rect::new(-1e6, -1e6, 2e6, 2e6)), vec![0, 2, 3, 4]);
+        assert_eq!(index.query(&Rect::new(5000.0, 5000.0, 1.0, 1.0)), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn huge_bounds_do_not_overflow_the_cell_count() {
+        let mut index = SpatialIndex::default();
+        index.set(0, Some(Rect::new(-3e11, -3e11, 6e11, 6e11)));
+
+        assert_eq!(index.query(&Rect::new(0.0, 0.0, 1.0, 1.0)), vec![0]);
+        assert_eq!(index.query(&Rect::new(-3e11, -3e11, 6e11, 6e11)), vec![0]);
+        index.remove(0);
+        assert!(index.query(&Rect::new(0.0, 0.0, 1.0, 1.0)).is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::Stroke;
+use serde::{Deserialize, Serialize};
+
+/// Space between the box edge and its text.
+pub const PADDING: f64 = 6.0;
+pub const BORDER_WIDTH: f64 = 1.5;
+// Text boxes are never resized narrower than this.
+pub const MIN_WIDTH: f64 = 24.0;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FontFamily {
+    #[default]
+    Sans,
+    Serif,
+    Mono,
+    Handwriting,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// A multi-line text annotation wrapped to `width`. Its origin is the box's
+/// top-left corner and its height follows the wrapped text. The font size
+/// comes from the stroke's thickness, the background from its fill colour.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct TextBox {
+    pub content: String,
+    pub width: f64,
+    pub font_family: FontFamily,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub align: TextAlign,
+    /// Line height as a multiple of the font size.
+    pub line_spacing: f64,
+    pub border_color: Option<String>,
+}
+
+impl Default for TextBox {
+    fn default() -> Self {
+        Self {
+            content: String::new(),
+            width: 240.0,
+            font_family: FontFamily::default(),
+            bold: false,
+            italic: false,
+            underline: false,
+            align: TextAlign::default(),
+            line_spacing: 1.2,
+            border_color: None,
+        }
+    }
+}
+
+impl FontFamily {
+    fn css(&self) -> &'static str {
+        match self {
+            Self::Sans => "Inter, system-ui, sans-serif",
+            Self::Serif => "Georgia, 'Times New Roman', serif",
+            Self::Mono => "'JetBrains Mono', Menlo, Consolas, monospace",
+            Self::Handwriting => "'Comic Sans MS', 'Segoe Print', cursive",
+        }
+    }
+}
+
+impl TextAlign {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+        }
+    }
+}
+
+impl TextBox {
+    /// The canvas font shorthand at `font_size`.
+    pub fn font(&self, font_size: f64) -> String {
+        format!(
+            "{}{}{}px {}",
+            if self.italic { "italic " } else { "" },
+            if self.bold { "bold " } else { "" },
+            font_size,
+            self.font_family.css()
+        )
+    }
+
+    pub fn line_height(&self, font_size: f64) -> f64 {
+        font_size * self.line_spacing.max(0.5)
+    }
+
+    /// Height of the box around `lines` lines of text.
+    pub fn height(&self, lines: usize, font_size: f64) -> f64 {
+        lines.max(1) as f64 * self.line_height(font_size) + PADDING * 2.0
+    }
+
+    /// Room for text between the paddings.
+    pub fn text_width(&self) -> f64 {
+        (self.width - PADDING * 2.0).max(1.0)
+    }
+}
+
+/// Wrapped lines of a text box, each with its measured width.
+pub type Lines = Vec<(String, f64)>;
+
+pub fn is_text_box(stroke: &Stroke) -> bool {
+    stroke.tool == "textbox"
+}
+
+/// Greedy word wrap of each paragraph to `max_width`, splitting words that
+/// don't fit on a line of their own. `measure` gives a run's width.
+pub fn wrap(content: &str, max_width: f64, measure: impl Fn(&str) -> f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in content.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() { word.to_string() } else { format!("{line} {word}") };
+            if line.is_empty() || measure(&candidate) <= max_width {
+                line = candidate;
+            } else {
+                lines.push(std::mem::take(&mut line));
+                line = word.to_string();
+            }
+            // A word wider than the whole line is broken between characters.
+            while measure(&line) > max_width && line.chars().count() > 1 {
+                let mut fitted = String::new();
+                for c in line.chars() {
+                    let next = format!("{fitted}{c}");
+                    if !fitted.is_empty() && measure(&next) > max_width {
+                        break;
+                    }
+                    fitted = next;
+                }
+                line = line[fitted.len()..].to_string();
+                lines.push(fitted);
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every character is 10 wide.
+    fn measure(run: &str) -> f64 {
+        run.chars().count() as f64 * 10.0
+    }
+
+    #[test]
+    fn wraps_between_words() {
+        assert_eq!(wrap("one two three", 80.0, measure), ["one two", "three"]);
+    }
+
+    #[test]
+    fn breaks_words_wider_than_the_box_between_characters() {
+        assert_eq!(wrap("abcdefgh", 30.0, measure), ["abc", "def", "gh"]);
+        assert_eq!(wrap("ok abcdefgh", 30.0, measure), ["ok", "abc", "def", "gh"]);
+    }
+
+    #[test]
+    fn breaks_multibyte_words_on_character_boundaries() {
+        assert_eq!(wrap("ééééé", 30.0, measure), ["ééé", "éé"]);
+        assert_eq!(wrap("日本語のテキスト", 30.0, measure), ["日本語", "のテキ", "スト"]);
+    }
+
+    #[test]
+    fn keeps_a_single_character_that_does_not_fit() {
+        assert_eq!(wrap("ab", 5.0, measure), ["a", "b"]);
+    }
+
+    #[test]
+    fn empty_content_is_one_empty_line() {
+        assert_eq!(wrap("", 100.0, measure), [""]);
+    }
+
+    #[test]
+    fn keeps_runs_of_spaces_that_fit() {
+        assert_eq!(wrap("a   b", 100.0, measure), ["a   b"]);
+        assert_eq!(wrap("ab   cd", 40.0, measure), ["ab  ", "cd"]);
+    }
+
+    #[test]
+    fn newlines_start_lines_including_a_trailing_one() {
+        assert_eq!(wrap("ab\ncd", 100.0, measure), ["ab", "cd"]);
+        assert_eq!(wrap("ab\n", 100.0, measure), ["ab", ""]);
+        assert_eq!(wrap("\n\n", 100.0, measure), ["", "", ""]);
+    }
+}
@@ -0,0 +1,274 @@
+use crate::hit::{rotate_about, text_content, TextExtent};
+use crate::{markup, path, text_box};
+use crate::{Point, Stroke};
+use std::f64::consts::PI;
+
+/// Gap between the selected strokes and the selection frame.
+pub const FRAME_PADDING: f64 = 5.0;
+/// Distance of the rotation knob above the frame's top edge.
+pub const ROTATE_KNOB_OFFSET: f64 = 24.0;
+const ROTATION_SNAP: f64 = PI / 12.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Handle {
+    Move,
+    Rotate,
+    /// A corner or edge handle; `hx`/`hy` are -1, 0 or 1 along each local axis.
+    Resize { hx: i8, hy: i8 },
+}
+
+const RESIZE_HANDLES: [(&str, i8, i8); 8] = [
+    ("nw", -1, -1),
+    ("n", 0, -1),
+    ("ne", 1, -1),
+    ("e", 1, 0),
+    ("se", 1, 1),
+    ("s", 0, 1),
+    ("sw", -1, 1),
+    ("w", -1, 0),
+];
+
+impl Handle {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "move" => Some(Self::Move),
+            "rotate" => Some(Self::Rotate),
+            _ => RESIZE_HANDLES
+                .iter()
+                .find(|(handle, _, _)| *handle == name)
+                .map(|&(_, hx, hy)| Self::Resize { hx, hy }),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Move => "move",
+            Self::Rotate => "rotate",
+            Self::Resize { hx, hy } => RESIZE_HANDLES
+                .iter()
+                .find(|&&(_, x, y)| x == hx && y == hy)
+                .map_or("move", |(handle, _, _)| handle),
+        }
+    }
+}
+
+/// A possibly rotated box around the selection. Half sizes exclude padding.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub center: Point,
+    pub half_width: f64,
+    pub half_height: f64,
+    pub rotation: f64,
+}
+
+impl Frame {
+    pub fn local_point(&self, p: Point) -> Point {
+        let r = rotate_about(p, self.center, -self.rotation);
+        Point::new(r.x - self.center.x, r.y - self.center.y)
+    }
+
+    pub fn world_point(&self, local: Point) -> Point {
+        let p = Point::new(self.center.x + local.x, self.center.y + local.y);
+        rotate_about(p, self.center, self.rotation)
+    }
+
+    /// Padded corners in drawing order, in world space.
+    pub fn corners(&self) -> [Point; 4] {
+        let (w, h) = (self.half_width + FRAME_PADDING, self.half_height + FRAME_PADDING);
+        [(-w, -h), (w, -h), (w, h), (-w, h)].map(|(x, y)| self.world_point(Point::new(x, y)))
+    }
+
+    pub fn handle_position(&self, handle: Handle) -> Point {
+        let (w, h) = (self.half_width + FRAME_PADDING, self.half_height + FRAME_PADDING);
+        let local = match handle {
+            Handle::Move => Point::new(0.0, 0.0),
+            Handle::Rotate => Point::new(0.0, -h - ROTATE_KNOB_OFFSET),
+            Handle::Resize { hx, hy } => Point::new(hx as f64 * w, hy as f64 * h),
+        };
+        self.world_point(local)
+    }
+
+    pub fn resize_handles(&self) -> impl Iterator<Item = Handle> + '_ {
+        RESIZE_HANDLES.iter().map(|&(_, hx, hy)| Handle::Resize { hx, hy })
+    }
+
+    /// The handle under `p`, or `Move` anywhere inside the frame.
+    pub fn handle_at(&self, p: Point, radius: f64) -> Option<Handle> {
+        let near = |handle: Handle| {
+            let h = self.handle_position(handle);
+            (p.x - h.x).powi(2) + (p.y - h.y).powi(2) <= radius * radius
+        };
+        if near(Handle::Rotate) {
+            return Some(Handle::Rotate);
+        }
+        if let Some(handle) = self.resize_handles().find(|&handle| near(handle)) {
+            return Some(handle);
+        }
+        let local = self.local_point(p);
+        let inside = local.x.abs() <= self.half_width + FRAME_PADDING && local.y.abs() <= self.half_height + FRAME_PADDING;
+        inside.then_some(Handle::Move)
+    }
+}
+
+/// A transform in the frame's local axes: scale about `anchor`, then rotate
+/// by `rotation` about the frame centre, then translate.
+#[derive(Clone, Copy, Debug)]
+pub struct Affine {
+    frame: Frame,
+    anchor: Point,
+    sx: f64,
+    sy: f64,
+    pub rotation: f64,
+    translate: Point,
+}
+
+impl Affine {
+    pub fn apply(&self, p: Point) -> Point {
+        let local = self.frame.local_point(p);
+        let scaled = Point::new(
+            self.anchor.x + (local.x - self.anchor.x) * self.sx,
+            self.anchor.y + (local.y - self.anchor.y) * self.sy,
+        );
+        let world = rotate_about(self.frame.world_point(scaled), self.frame.center, self.rotation);
+        Point { x: world.x + self.translate.x, y: world.y + self.translate.y, ..p }
+    }
+
+    /// Scale factors along the axes of something rotated by `rotation`.
+    /// Off-axis scaling would skew, so it falls back to a uniform scale.
+    fn scale_along(&self, rotation: f64) -> (f64, f64) {
+        let relative = rotation - self.frame.rotation;
+        if relative.sin().abs() < 0.01 {
+            (self.sx, self.sy)
+        } else if relative.cos().abs() < 0.01 {
+            (self.sy, self.sx)
+        } else {
+            let uniform = (self.sx * self.sy).abs().sqrt();
+            (uniform, uniform)
+        }
+    }
+}
+
+/// An in-progress drag of one of the selection frame's handles.
+#[derive(Clone, Copy, Debug)]
+pub struct Gesture {
+    pub frame: Frame,
+    pub handle: Handle,
+    pub start: Point,
+}
+
+impl Gesture {
+    /// The transform for the pointer at `p`. `constrain` keeps the aspect
+    /// ratio while resizing and snaps rotation to 15 degree steps.
+    pub fn affine(&self, p: Point, constrain: bool) -> Affine {
+        let mut affine = Affine {
+            frame: self.frame,
+            anchor: Point::new(0.0, 0.0),
+            sx: 1.0,
+            sy: 1.0,
+            rotation: 0.0,
+            translate: Point::new(0.0, 0.0),
+        };
+        match self.handle {
+            Handle::Move => {
+                affine.translate = Point::new(p.x - self.start.x, p.y - self.start.y);
+            }
+            Handle::Rotate => {
+                let c = self.frame.center;
+                let angle = (p.y - c.y).atan2(p.x - c.x) - (self.start.y - c.y).atan2(self.start.x - c.x);
+                affine.rotation = if constrain { (angle / ROTATION_SNAP).round() * ROTATION_SNAP } else { angle };
+            }
+            Handle::Resize { hx, hy } => {
+                let (start, now) = (self.frame.local_point(self.start), self.frame.local_point(p));
+                let anchor = Point::new(
+                    -(hx as f64) * self.frame.half_width,
+                    -(hy as f64) * self.frame.half_height,
+                );
+                let factor = |now: f64, start: f64, anchor: f64| {
+                    let span = start - anchor;
+                    if span.abs() < 1e-6 { 1.0 } else { (now - anchor) / span }
+                };
+                let (mut sx, mut sy) = (
+                    if hx != 0 { factor(now.x, start.x, anchor.x) } else { 1.0 },
+                    if hy != 0 { factor(now.y, start.y, anchor.y) } else { 1.0 },
+                );
+                if constrain {
+                    let uniform = match (hx, hy) {
+                        (0, _) => sy.abs(),
+                        (_, 0) => sx.abs(),
+                        _ => sx.abs().max(sy.abs()),
+                    };
+                    sx = uniform.copysign(if hx == 0 { 1.0 } else { sx });
+                    sy = uniform.copysign(if hy == 0 { 1.0 } else { sy });
+                }
+                affine.anchor = anchor;
+                affine.sx = sx;
+                affine.sy = sy;
+            }
+        }
+        affine
+    }
+}
+
+/// Whether the stroke keeps its geometry unrotated and stores a rotation.
+pub fn has_rotation(stroke: &Stroke) -> bool {
+    text_content(stroke).is_some()
+        || (stroke.tool.starts_with("shape-")
+            && stroke.tool != "shape-line"
+            && stroke.tool != "shape-arrow"
+            && !path::is_path_shape(&stroke.tool)
+            && !markup::has_tail(&stroke.tool))
+}
+
+/// Applies `affine` to a copy of `stroke`. Point-based strokes move every
+/// point; shapes and text move their pivot, rescale and gain rotation. Text
+/// boxes keep their font and rewrap to the new width.
+pub fn transform_stroke(stroke: &Stroke, affine: &Affine) -> Stroke {
+    let mut out = stroke.clone();
+    if !has_rotation(stroke) {
+        out.points = stroke.points.iter().map(|&p| affine.apply(p)).collect();
+        return out;
+    }
+
+    out.rotation = stroke.rotation + affine.rotation;
+    let (ax, ay) = affine.scale_along(stroke.rotation);
+    if text_content(stroke).is_some() {
+        if let Some(&origin) = stroke.points.first() {
+            out.points[0] = affine.apply(origin);
+        }
+        match out.text_box.as_mut() {
+            Some(text_box) => text_box.width = (text_box.width * ax.abs()).max(text_box::MIN_WIDTH),
+            None => out.thickness = stroke.thickness * ay.abs(),
+        }
+    } else if let (Some(&start), Some(&end)) = (stroke.points.first(), stroke.points.get(1)) {
+        let center = affine.apply(Point::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0));
+        let (half_x, half_y) = ((end.x - start.x) / 2.0 * ax, (end.y - start.y) / 2.0 * ay);
+        out.points[0] = Point::new(center.x - half_x, center.y - half_y);
+        out.points[1] = Point::new(center.x + half_x, center.y + half_y);
+    }
+    out
+}
+
+/// The frame for one rotatable stroke, aligned with its own rotation.
+pub fn stroke_frame(stroke: &Stroke, text_extent: impl Fn(&str) -> TextExtent) -> Option<Frame> {
+    if let Some(text) = text_content(stroke) {
+        let origin = *stroke.points.first()?;
+        let extent = text_extent(text);
+        let local_center = Point::new(
+            origin.x + extent.width / 2.0,
+            origin.y + (extent.descent - extent.ascent) / 2.0,
+        );
+        return Some(Frame {
+            center: rotate_about(local_center, origin, stroke.rotation),
+            half_width: extent.width / 2.0,
+            half_height: (extent.ascent + extent.descent) / 2.0,
+            rotation: stroke.rotation,
+        });
+    }
+    let (start, end) = (*stroke.points.first()?, *stroke.points.get(1)?);
+    Some(Frame {
+        center: Point::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0),
+        half_width: (end.x - start.x).abs() / 2.0 + stroke.thickness / 2.0,
+        half_height: (end.y - start.y).abs() / 2.0 + stroke.thickness / 2.0,
+        rotation: stroke.rotation,
+    })
+}